@@ -1,48 +1,73 @@
-use embedded_hal::{digital::OutputPin, spi::SpiDevice};
+use crate::{interface::WriteData, marker, state, Ad983x, BitFlags, Config, Error, OutputWaveform};
 
-use crate::{marker, Ad983x, BitFlags, Error, OutputWaveform};
-
-impl<DEV, CS, E> Ad983x<DEV, CS, marker::Ad9833Ad9837>
+impl<DEV, SpiE, PinE> Ad983x<DEV, marker::Ad9833Ad9837>
 where
-    DEV: SpiDevice<Error = E>,
-    CS: OutputPin,
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
 {
     /// Create a new instance of an AD9833 device.
     ///
     /// Remember to call `reset()` before using the device after power up.
-    pub fn new_ad9833(spi: DEV, cs: CS) -> Self {
-        Self::create(spi, cs)
+    pub fn new_ad9833(spi: DEV) -> Self {
+        Self::create(spi)
     }
     /// Create a new instance of an AD9837 device.
     ///
     /// Remember to call `reset()` before using the device after power up.
-    pub fn new_ad9837(spi: DEV, cs: CS) -> Self {
+    pub fn new_ad9837(spi: DEV) -> Self {
         // Behaves the same as AD9833
-        Self::create(spi, cs)
+        Self::create(spi)
     }
+}
 
+impl<DEV, S, SpiE, PinE> Ad983x<DEV, marker::Ad9833Ad9837, S>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+    S: state::Configurable,
+{
     /// Set the output waveform
-    pub fn set_output_waveform(&mut self, waveform: OutputWaveform) -> Result<(), Error<E>> {
-        let control = match waveform {
-            OutputWaveform::Sinusoidal => self
-                .control
-                .with_low(BitFlags::OPBITEN)
-                .with_low(BitFlags::MODE),
-            OutputWaveform::Triangle => self
-                .control
-                .with_low(BitFlags::OPBITEN)
-                .with_high(BitFlags::MODE),
+    pub fn set_output_waveform(
+        &mut self,
+        waveform: OutputWaveform,
+    ) -> Result<(), Error<SpiE, PinE>> {
+        let control = self.registers.control.with_output_waveform_ad9833(waveform);
+        self.write_control(control)
+    }
+}
+
+impl<DEV, S> Ad983x<DEV, marker::Ad9833Ad9837, S> {
+    /// Get the output waveform selected
+    pub fn output_waveform(&self) -> OutputWaveform {
+        self.registers.control.output_waveform_ad9833()
+    }
+}
+
+impl Config {
+    pub(crate) fn output_waveform_ad9833(self) -> OutputWaveform {
+        if self.is_high(BitFlags::OPBITEN) {
+            if self.is_high(BitFlags::DIV2) {
+                OutputWaveform::SquareMsbOfDac
+            } else {
+                OutputWaveform::SquareMsbOfDacDiv2
+            }
+        } else if self.is_high(BitFlags::MODE) {
+            OutputWaveform::Triangle
+        } else {
+            OutputWaveform::Sinusoidal
+        }
+    }
+
+    pub(crate) fn with_output_waveform_ad9833(self, waveform: OutputWaveform) -> Self {
+        match waveform {
+            OutputWaveform::Sinusoidal => self.with_low(BitFlags::OPBITEN).with_low(BitFlags::MODE),
+            OutputWaveform::Triangle => self.with_low(BitFlags::OPBITEN).with_high(BitFlags::MODE),
             OutputWaveform::SquareMsbOfDac => self
-                .control
                 .with_high(BitFlags::OPBITEN)
                 .with_low(BitFlags::MODE)
                 .with_high(BitFlags::DIV2),
             OutputWaveform::SquareMsbOfDacDiv2 => self
-                .control
                 .with_high(BitFlags::OPBITEN)
                 .with_low(BitFlags::MODE)
                 .with_low(BitFlags::DIV2),
-        };
-        self.write_control(control)
+        }
     }
 }
@@ -1,79 +1,178 @@
-use embedded_hal::{digital::OutputPin, spi::SpiDevice};
+use crate::{
+    interface::{ControlPins, WithControlPins, WriteData},
+    marker, state, Ad983x, BitFlags, Config, ControlSource, Error, OutputWaveform, SignBitOutput,
+};
 
-use crate::{marker, Ad983x, BitFlags, ControlSource, Error, OutputWaveform, SignBitOutput};
-
-impl<DEV, CS, E> Ad983x<DEV, CS, marker::Ad9834Ad9838>
+impl<DEV, SpiE, PinE> Ad983x<DEV, marker::Ad9834Ad9838>
 where
-    DEV: SpiDevice<Error = E>,
-    CS: OutputPin,
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
 {
     /// Create a new instance of an AD9834 device.
     /// Remember to call `reset()` before using the device after power up.
-    pub fn new_ad9834(spi: DEV, cs: CS) -> Self {
-        Self::create(spi, cs)
+    pub fn new_ad9834(spi: DEV) -> Self {
+        Self::create(spi)
     }
 
     /// Create a new instance of an AD9838 device.
     /// Remember to call `reset()` before using the device after power up.
-    pub fn new_ad9838(spi: DEV, cs: CS) -> Self {
-        Self::create(spi, cs)
+    pub fn new_ad9838(spi: DEV) -> Self {
+        Self::create(spi)
+    }
+}
+
+impl<DEV, P> Ad983x<WithControlPins<DEV, P>, marker::Ad9834Ad9838>
+where
+    WithControlPins<DEV, P>: WriteData,
+{
+    /// Create a new instance of an AD9834 device owning its function control pins.
+    ///
+    /// The pins are used while the hardware pin control source is selected.
+    /// Remember to call `reset()` before using the device after power up.
+    pub fn new_ad9834_with_pins(spi: DEV, pins: ControlPins<P>) -> Self {
+        Self::create(WithControlPins::new(spi, pins))
     }
 
+    /// Create a new instance of an AD9838 device owning its function control pins.
+    ///
+    /// The pins are used while the hardware pin control source is selected.
+    /// Remember to call `reset()` before using the device after power up.
+    pub fn new_ad9838_with_pins(spi: DEV, pins: ControlPins<P>) -> Self {
+        Self::create(WithControlPins::new(spi, pins))
+    }
+}
+
+impl<DEV, S, SpiE, PinE> Ad983x<DEV, marker::Ad9834Ad9838, S>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+    S: state::Configurable,
+{
     /// Set the output waveform
     ///
     /// Will return `Error::InvalidArgument` for `SquareMsbOfDac` and `SquareMsbOfDacDiv2`
     /// as this is not available on AD9834/AD9838 devices. To set the digital output,
-    /// please use
-    pub fn set_output_waveform(&mut self, waveform: OutputWaveform) -> Result<(), Error<E>> {
-        let control = match waveform {
-            OutputWaveform::Sinusoidal => self
-                .control
-                .with_low(BitFlags::OPBITEN)
-                .with_low(BitFlags::MODE),
-            OutputWaveform::Triangle => self
-                .control
-                .with_low(BitFlags::OPBITEN)
-                .with_high(BitFlags::MODE),
-            OutputWaveform::SquareMsbOfDac => return Err(Error::InvalidArgument),
-            OutputWaveform::SquareMsbOfDacDiv2 => return Err(Error::InvalidArgument),
-        };
+    /// please use `set_sign_bit_output()`.
+    pub fn set_output_waveform(
+        &mut self,
+        waveform: OutputWaveform,
+    ) -> Result<(), Error<SpiE, PinE>> {
+        let control = self
+            .registers
+            .control
+            .with_output_waveform_ad9834(waveform)?;
         self.write_control(control)
     }
 
     /// Set the digital output
-    pub fn set_sign_bit_output(&mut self, configuration: SignBitOutput) -> Result<(), Error<E>> {
-        let control = match configuration {
-            SignBitOutput::Disabled => self.control.with_low(BitFlags::OPBITEN),
+    pub fn set_sign_bit_output(
+        &mut self,
+        configuration: SignBitOutput,
+    ) -> Result<(), Error<SpiE, PinE>> {
+        let control = self.registers.control.with_sign_bit_output(configuration);
+        self.write_control(control)
+    }
+
+    /// Set the control source used for the functions:
+    /// frequency register selection, phase register selection,
+    /// reset of internal registers, and DAC power-down.
+    ///
+    /// When selecting the hardware pins, the assigned pins are first set to
+    /// the current state of the functions so that they stay the same.
+    pub fn set_control_source(&mut self, source: ControlSource) -> Result<(), Error<SpiE, PinE>> {
+        let control = self.registers.control.with_control_source(source);
+        if source == ControlSource::HardwarePins {
+            self.sync_control_pins(control)?;
+        }
+        self.write_control(control)
+    }
+}
+
+impl<DEV, S> Ad983x<DEV, marker::Ad9834Ad9838, S> {
+    /// Get the output waveform selected
+    pub fn output_waveform(&self) -> OutputWaveform {
+        self.registers.control.output_waveform_ad9834()
+    }
+
+    /// Get the sign bit output configuration selected
+    pub fn sign_bit_output(&self) -> SignBitOutput {
+        self.registers.control.sign_bit_output()
+    }
+
+    /// Get the control source selected
+    pub fn control_source(&self) -> ControlSource {
+        self.registers.control.control_source()
+    }
+}
+
+impl Config {
+    pub(crate) fn output_waveform_ad9834(self) -> OutputWaveform {
+        if self.is_high(BitFlags::MODE) {
+            OutputWaveform::Triangle
+        } else {
+            OutputWaveform::Sinusoidal
+        }
+    }
+
+    pub(crate) fn sign_bit_output(self) -> SignBitOutput {
+        if !self.is_high(BitFlags::OPBITEN) {
+            SignBitOutput::Disabled
+        } else if self.is_high(BitFlags::SIGN_PIB) {
+            SignBitOutput::Comparator
+        } else if self.is_high(BitFlags::DIV2) {
+            SignBitOutput::SquareMsbOfDac
+        } else {
+            SignBitOutput::SquareMsbOfDacDiv2
+        }
+    }
+
+    pub(crate) fn control_source(self) -> ControlSource {
+        if self.is_high(BitFlags::PIN_SW) {
+            ControlSource::HardwarePins
+        } else {
+            ControlSource::Software
+        }
+    }
+
+    pub(crate) fn with_output_waveform_ad9834<SpiE, PinE>(
+        self,
+        waveform: OutputWaveform,
+    ) -> Result<Self, Error<SpiE, PinE>> {
+        match waveform {
+            OutputWaveform::Sinusoidal => {
+                Ok(self.with_low(BitFlags::OPBITEN).with_low(BitFlags::MODE))
+            }
+            OutputWaveform::Triangle => {
+                Ok(self.with_low(BitFlags::OPBITEN).with_high(BitFlags::MODE))
+            }
+            OutputWaveform::SquareMsbOfDac => Err(Error::InvalidArgument),
+            OutputWaveform::SquareMsbOfDacDiv2 => Err(Error::InvalidArgument),
+        }
+    }
+
+    pub(crate) fn with_sign_bit_output(self, configuration: SignBitOutput) -> Self {
+        match configuration {
+            SignBitOutput::Disabled => self.with_low(BitFlags::OPBITEN),
             SignBitOutput::Comparator => self
-                .control
                 .with_high(BitFlags::OPBITEN)
                 .with_low(BitFlags::MODE)
                 .with_high(BitFlags::SIGN_PIB)
                 .with_high(BitFlags::DIV2),
             SignBitOutput::SquareMsbOfDac => self
-                .control
                 .with_high(BitFlags::OPBITEN)
                 .with_low(BitFlags::MODE)
                 .with_low(BitFlags::SIGN_PIB)
                 .with_high(BitFlags::DIV2),
             SignBitOutput::SquareMsbOfDacDiv2 => self
-                .control
                 .with_high(BitFlags::OPBITEN)
                 .with_low(BitFlags::MODE)
                 .with_low(BitFlags::SIGN_PIB)
                 .with_low(BitFlags::DIV2),
-        };
-        self.write_control(control)
+        }
     }
 
-    /// Set the control source used for the functions:
-    /// frequency register selection, phase register selection,
-    /// reset of internal registers, and DAC power-down.
-    pub fn set_control_source(&mut self, source: ControlSource) -> Result<(), Error<E>> {
-        let control = match source {
-            ControlSource::Software => self.control.with_low(BitFlags::PIN_SW),
-            ControlSource::HardwarePins => self.control.with_high(BitFlags::PIN_SW),
-        };
-        self.write_control(control)
+    pub(crate) fn with_control_source(self, source: ControlSource) -> Self {
+        match source {
+            ControlSource::Software => self.with_low(BitFlags::PIN_SW),
+            ControlSource::HardwarePins => self.with_high(BitFlags::PIN_SW),
+        }
     }
 }
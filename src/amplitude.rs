@@ -0,0 +1,413 @@
+//! Output amplitude control with an external gain device
+//!
+//! The devices have no amplitude register. Boards set the output level with
+//! an external device instead, like a digital potentiometer or a DAC driving
+//! the FS ADJUST pin or a variable gain amplifier after the output.
+//! Implementing [`AmplitudeControl`] for such a device allows an
+//! [`Ad983xWithAmplitude`] to set the amplitude in millivolts together with
+//! the output waveform.
+//!
+//! The amplitude produced for a gain setting depends on the waveform, since
+//! for example the triangle and the sine output of the AD9833 have different
+//! swings and the square output is a logic level. An [`AmplitudeCalibration`]
+//! can be provided per waveform, either linear or as a measured table.
+//! Changing the waveform keeps the amplitude.
+//!
+//! ```no_run
+//! use ad983x::{
+//!     amplitude::{Ad983xWithAmplitude, AmplitudeCalibration, AmplitudeControl, CalibrationPoint},
+//!     Ad983x, OutputWaveform,
+//! };
+//! use linux_embedded_hal::SpidevDevice;
+//!
+//! /// 8-bit digital potentiometer
+//! struct Potentiometer;
+//!
+//! impl AmplitudeControl for Potentiometer {
+//!     type Error = ();
+//!
+//!     fn max_code(&self) -> u32 {
+//!         255
+//!     }
+//!
+//!     fn set_code(&mut self, code: u32) -> Result<(), ()> {
+//!         // Write the wiper position.
+//!         Ok(())
+//!     }
+//! }
+//!
+//! const TRIANGLE: [CalibrationPoint; 3] = [
+//!     CalibrationPoint { code: 0, millivolts: 0 },
+//!     CalibrationPoint { code: 128, millivolts: 290 },
+//!     CalibrationPoint { code: 255, millivolts: 600 },
+//! ];
+//!
+//! let dev = SpidevDevice::open("/dev/spidev0.0").unwrap();
+//! let mut dds = Ad983x::new_ad9833(dev);
+//! dds.reset().unwrap();
+//! dds.enable().unwrap();
+//! let linear = AmplitudeCalibration::Linear { full_scale_mv: 650 };
+//! let mut dds = Ad983xWithAmplitude::new(dds, Potentiometer, linear)
+//!     .with_calibration(OutputWaveform::Triangle, AmplitudeCalibration::Table(&TRIANGLE));
+//! dds.set_amplitude_mv(300).unwrap();
+//! // Still 300 mV
+//! dds.set_output_waveform(OutputWaveform::Triangle).unwrap();
+//! ```
+
+use crate::{interface::WriteData, marker, Ad983x, Error, OutputWaveform};
+
+/// External device setting the output amplitude
+///
+/// This is implemented by the application for the gain device on the board.
+pub trait AmplitudeControl {
+    /// Gain device error
+    type Error;
+
+    /// Highest gain setting (code), for example 255 for an 8-bit digital potentiometer
+    fn max_code(&self) -> u32;
+
+    /// Apply a gain setting between 0 and [`max_code()`](#tymethod.max_code).
+    fn set_code(&mut self, code: u32) -> Result<(), Self::Error>;
+}
+
+/// Measured amplitude for a gain setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CalibrationPoint {
+    /// Gain setting
+    pub code: u32,
+    /// Output amplitude in millivolts peak-to-peak
+    pub millivolts: u32,
+}
+
+/// Relation between the gain settings and the output amplitude
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmplitudeCalibration<'a> {
+    /// The amplitude is proportional to the gain setting and reaches
+    /// `full_scale_mv` millivolts peak-to-peak at the highest setting.
+    Linear {
+        /// Amplitude at the highest gain setting in millivolts peak-to-peak
+        full_scale_mv: u32,
+    },
+    /// Measured points, interpolated linearly.
+    ///
+    /// Both the gain settings and the amplitudes must be increasing.
+    /// Amplitudes outside of the table cannot be set.
+    Table(&'a [CalibrationPoint]),
+}
+
+/// Linear interpolation between two points, rounded
+fn interpolate(x: u32, x0: u32, x1: u32, y0: u32, y1: u32) -> u32 {
+    if x1 == x0 {
+        return y0;
+    }
+    let (x, x0, x1, y0, y1) = (
+        u64::from(x),
+        u64::from(x0),
+        u64::from(x1),
+        u64::from(y0),
+        u64::from(y1),
+    );
+    let span = x1 - x0;
+    let y = if y1 >= y0 {
+        y0 + ((x - x0) * (y1 - y0) + span / 2) / span
+    } else {
+        y0 - ((x - x0) * (y0 - y1) + span / 2) / span
+    };
+    y as u32
+}
+
+impl AmplitudeCalibration<'_> {
+    /// Gain setting producing the amplitude closest to `millivolts`
+    ///
+    /// Returns `None` if the amplitude is out of range.
+    fn code(&self, max_code: u32, millivolts: u32) -> Option<u32> {
+        let code = match *self {
+            AmplitudeCalibration::Linear { full_scale_mv } => {
+                if millivolts > full_scale_mv {
+                    return None;
+                }
+                interpolate(millivolts, 0, full_scale_mv, 0, max_code)
+            }
+            AmplitudeCalibration::Table(points) => {
+                let segment = points.windows(2).find(|segment| {
+                    segment[0].millivolts <= millivolts && millivolts <= segment[1].millivolts
+                });
+                match (segment, points) {
+                    (Some(segment), _) => interpolate(
+                        millivolts,
+                        segment[0].millivolts,
+                        segment[1].millivolts,
+                        segment[0].code,
+                        segment[1].code,
+                    ),
+                    (None, [point]) if point.millivolts == millivolts => point.code,
+                    _ => return None,
+                }
+            }
+        };
+        if code <= max_code {
+            Some(code)
+        } else {
+            None
+        }
+    }
+
+    /// Amplitude in millivolts produced by a gain setting
+    fn millivolts(&self, max_code: u32, code: u32) -> u32 {
+        match *self {
+            AmplitudeCalibration::Linear { full_scale_mv } => {
+                interpolate(code, 0, max_code, 0, full_scale_mv)
+            }
+            AmplitudeCalibration::Table(points) => points
+                .windows(2)
+                .find(|segment| segment[0].code <= code && code <= segment[1].code)
+                .map(|segment| {
+                    interpolate(
+                        code,
+                        segment[0].code,
+                        segment[1].code,
+                        segment[0].millivolts,
+                        segment[1].millivolts,
+                    )
+                })
+                .or_else(|| points.first().map(|point| point.millivolts))
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// Amplitude control errors
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AmplitudeError<SpiE, PinE, ControlE> {
+    /// Error writing to the device
+    Device(Error<SpiE, PinE>),
+    /// Error of the gain device
+    Control(ControlE),
+    /// The amplitude cannot be produced with the calibration of the waveform
+    OutOfRange,
+}
+
+impl<SpiE, PinE, ControlE> From<Error<SpiE, PinE>> for AmplitudeError<SpiE, PinE, ControlE> {
+    fn from(error: Error<SpiE, PinE>) -> Self {
+        AmplitudeError::Device(error)
+    }
+}
+
+/// Index of the calibration used for a waveform
+fn calibration_index(waveform: OutputWaveform) -> usize {
+    match waveform {
+        OutputWaveform::Sinusoidal => 0,
+        OutputWaveform::Triangle => 1,
+        OutputWaveform::SquareMsbOfDac | OutputWaveform::SquareMsbOfDacDiv2 => 2,
+    }
+}
+
+/// Device combined with an external gain device setting its amplitude
+#[derive(Debug)]
+pub struct Ad983xWithAmplitude<'a, DEV, IC, A> {
+    dds: Ad983x<DEV, IC>,
+    control: A,
+    calibrations: [AmplitudeCalibration<'a>; 3],
+    amplitude_mv: Option<u32>,
+    code: Option<u32>,
+}
+
+impl<'a, DEV, IC, A> Ad983xWithAmplitude<'a, DEV, IC, A> {
+    /// Combine a device with a gain device, using the same calibration for
+    /// all waveforms.
+    ///
+    /// The gain device is not written until an amplitude is set.
+    pub fn new(dds: Ad983x<DEV, IC>, control: A, calibration: AmplitudeCalibration<'a>) -> Self {
+        Ad983xWithAmplitude {
+            dds,
+            control,
+            calibrations: [calibration; 3],
+            amplitude_mv: None,
+            code: None,
+        }
+    }
+
+    /// Use a different calibration for a waveform.
+    ///
+    /// Both square waveforms share the same calibration.
+    pub fn with_calibration(
+        mut self,
+        waveform: OutputWaveform,
+        calibration: AmplitudeCalibration<'a>,
+    ) -> Self {
+        self.calibrations[calibration_index(waveform)] = calibration;
+        self
+    }
+
+    /// Destroy the wrapper and return the device and the gain device.
+    pub fn destroy(self) -> (Ad983x<DEV, IC>, A) {
+        (self.dds, self.control)
+    }
+
+    /// Device, for example to read back its settings
+    pub fn dds(&self) -> &Ad983x<DEV, IC> {
+        &self.dds
+    }
+
+    /// Mutable device, for example to set the frequency
+    ///
+    /// The output waveform should be changed with
+    /// [`set_output_waveform()`](#method.set_output_waveform) instead so that
+    /// the amplitude follows.
+    pub fn dds_mut(&mut self) -> &mut Ad983x<DEV, IC> {
+        &mut self.dds
+    }
+
+    /// Gain device
+    pub fn amplitude_control(&self) -> &A {
+        &self.control
+    }
+
+    /// Mutable gain device
+    pub fn amplitude_control_mut(&mut self) -> &mut A {
+        &mut self.control
+    }
+
+    /// Amplitude last set in millivolts peak-to-peak, as requested
+    ///
+    /// Returns `None` until an amplitude has been set.
+    pub fn amplitude_mv(&self) -> Option<u32> {
+        self.amplitude_mv
+    }
+
+    /// Gain setting last written to the gain device
+    pub fn code(&self) -> Option<u32> {
+        self.code
+    }
+}
+
+impl<DEV, IC, A, SpiE, PinE> Ad983xWithAmplitude<'_, DEV, IC, A>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+    A: AmplitudeControl,
+{
+    fn set_code(&mut self, code: u32) -> Result<(), AmplitudeError<SpiE, PinE, A::Error>> {
+        if self.code != Some(code) {
+            self.control
+                .set_code(code)
+                .map_err(AmplitudeError::Control)?;
+            self.code = Some(code);
+        }
+        Ok(())
+    }
+
+    fn apply_amplitude(
+        &mut self,
+        waveform: OutputWaveform,
+        millivolts: u32,
+    ) -> Result<u32, AmplitudeError<SpiE, PinE, A::Error>> {
+        let calibration = self.calibrations[calibration_index(waveform)];
+        let max_code = self.control.max_code();
+        let code = calibration
+            .code(max_code, millivolts)
+            .ok_or(AmplitudeError::OutOfRange)?;
+        self.set_code(code)?;
+        self.amplitude_mv = Some(millivolts);
+        Ok(calibration.millivolts(max_code, code))
+    }
+
+    /// Change the waveform with `write` and the gain setting so that the
+    /// amplitude stays the same.
+    ///
+    /// The gain is lowered before and raised after the waveform change so
+    /// that the amplitude never overshoots.
+    fn change_waveform<F>(
+        &mut self,
+        waveform: OutputWaveform,
+        write: F,
+    ) -> Result<(), AmplitudeError<SpiE, PinE, A::Error>>
+    where
+        F: FnOnce(&mut Ad983x<DEV, IC>) -> Result<(), Error<SpiE, PinE>>,
+    {
+        let millivolts = match self.amplitude_mv {
+            Some(millivolts) => millivolts,
+            None => return Ok(write(&mut self.dds)?),
+        };
+        let code = self.calibrations[calibration_index(waveform)]
+            .code(self.control.max_code(), millivolts)
+            .ok_or(AmplitudeError::OutOfRange)?;
+        match self.code {
+            Some(current) if code < current => {
+                self.set_code(code)?;
+                write(&mut self.dds)?;
+            }
+            _ => {
+                write(&mut self.dds)?;
+                self.set_code(code)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<DEV, A, SpiE, PinE> Ad983xWithAmplitude<'_, DEV, marker::Ad9833Ad9837, A>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+    A: AmplitudeControl,
+{
+    /// Set the output amplitude in millivolts peak-to-peak.
+    ///
+    /// The gain setting is computed with the calibration of the current
+    /// waveform. Returns the amplitude the calibration predicts for it.
+    /// Returns `AmplitudeError::OutOfRange` if the amplitude cannot be produced.
+    pub fn set_amplitude_mv(
+        &mut self,
+        millivolts: u32,
+    ) -> Result<u32, AmplitudeError<SpiE, PinE, A::Error>> {
+        self.apply_amplitude(self.dds.output_waveform(), millivolts)
+    }
+
+    /// Set the output waveform keeping the amplitude.
+    ///
+    /// Returns `AmplitudeError::OutOfRange` without changing anything if the
+    /// amplitude cannot be produced with the new waveform.
+    pub fn set_output_waveform(
+        &mut self,
+        waveform: OutputWaveform,
+    ) -> Result<(), AmplitudeError<SpiE, PinE, A::Error>> {
+        self.change_waveform(waveform, |dds| dds.set_output_waveform(waveform))
+    }
+}
+
+impl<DEV, A, SpiE, PinE> Ad983xWithAmplitude<'_, DEV, marker::Ad9834Ad9838, A>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+    A: AmplitudeControl,
+{
+    /// Set the output amplitude in millivolts peak-to-peak.
+    ///
+    /// The gain setting is computed with the calibration of the current
+    /// waveform. Returns the amplitude the calibration predicts for it.
+    /// Returns `AmplitudeError::OutOfRange` if the amplitude cannot be produced.
+    pub fn set_amplitude_mv(
+        &mut self,
+        millivolts: u32,
+    ) -> Result<u32, AmplitudeError<SpiE, PinE, A::Error>> {
+        self.apply_amplitude(self.dds.output_waveform(), millivolts)
+    }
+
+    /// Set the output waveform keeping the amplitude.
+    ///
+    /// Returns `AmplitudeError::OutOfRange` without changing anything if the
+    /// amplitude cannot be produced with the new waveform.
+    /// Returns `Error::InvalidArgument` for the square waveforms, see
+    /// [`Ad983x::set_output_waveform()`](crate::Ad983x::set_output_waveform).
+    pub fn set_output_waveform(
+        &mut self,
+        waveform: OutputWaveform,
+    ) -> Result<(), AmplitudeError<SpiE, PinE, A::Error>> {
+        if matches!(
+            waveform,
+            OutputWaveform::SquareMsbOfDac | OutputWaveform::SquareMsbOfDacDiv2
+        ) {
+            return Err(Error::InvalidArgument.into());
+        }
+        self.change_waveform(waveform, |dds| dds.set_output_waveform(waveform))
+    }
+}
@@ -0,0 +1,172 @@
+//! Synchronized arrays of devices
+//!
+//! Several devices clocked from the same MCLK produce phase-aligned outputs
+//! if they leave reset at the same time. A [`DdsArray`] holds all devices in
+//! reset while their registers are loaded and then releases them together,
+//! either with consecutive control writes or through a RESET pin shared by
+//! AD9834/AD9838 devices. Fixed phase relationships between the channels are
+//! set with per-channel phase offsets.
+//!
+//! ```no_run
+//! use ad983x::{array::DdsArray, Ad983x, FrequencyRegister, Phase, PhaseRegister};
+//! use linux_embedded_hal::SpidevDevice;
+//!
+//! let channels = ["/dev/spidev0.0", "/dev/spidev0.1", "/dev/spidev1.0", "/dev/spidev1.1"]
+//!     .map(|path| Ad983x::new_ad9833(SpidevDevice::open(path).unwrap()));
+//! let mut array = DdsArray::new(channels);
+//! array.hold_reset().unwrap();
+//! array
+//!     .configure(|_, dds| dds.set_frequency_hz(FrequencyRegister::F0, 10_000.0).map(|_| ()))
+//!     .unwrap();
+//! // Four outputs 90° apart
+//! let offsets = [0.0, 90.0, 180.0, 270.0].map(Phase::from_degrees);
+//! array.set_phase_offsets(PhaseRegister::P0, &offsets).unwrap();
+//! array.release().unwrap();
+//! ```
+
+use embedded_hal::digital::OutputPin;
+
+use crate::{interface::WriteData, Ad983x, Error, Phase, PhaseRegister};
+
+/// Array of devices with a synchronized reset release
+#[derive(Debug)]
+pub struct DdsArray<DEV, IC, const N: usize> {
+    channels: [Ad983x<DEV, IC>; N],
+}
+
+impl<DEV, IC, const N: usize> DdsArray<DEV, IC, N> {
+    /// Create a new array from the devices of all channels.
+    pub fn new(channels: [Ad983x<DEV, IC>; N]) -> Self {
+        DdsArray { channels }
+    }
+
+    /// Destroy the array and return the devices.
+    pub fn destroy(self) -> [Ad983x<DEV, IC>; N] {
+        self.channels
+    }
+
+    /// Device of a channel
+    pub fn channel(&self, index: usize) -> Option<&Ad983x<DEV, IC>> {
+        self.channels.get(index)
+    }
+
+    /// Mutable device of a channel
+    pub fn channel_mut(&mut self, index: usize) -> Option<&mut Ad983x<DEV, IC>> {
+        self.channels.get_mut(index)
+    }
+
+    /// Devices of all channels
+    pub fn channels(&self) -> &[Ad983x<DEV, IC>; N] {
+        &self.channels
+    }
+
+    /// Mutable devices of all channels
+    pub fn channels_mut(&mut self) -> &mut [Ad983x<DEV, IC>; N] {
+        &mut self.channels
+    }
+}
+
+impl<DEV, IC, SpiE, PinE, const N: usize> DdsArray<DEV, IC, N>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+{
+    /// Put all devices in reset.
+    ///
+    /// See [`Ad983x::reset()`].
+    pub fn hold_reset(&mut self) -> Result<(), Error<SpiE, PinE>> {
+        for channel in self.channels.iter_mut() {
+            channel.reset()?;
+        }
+        Ok(())
+    }
+
+    /// Load the registers of every channel with a closure, which gets the
+    /// channel index and its device.
+    pub fn configure<F>(&mut self, mut f: F) -> Result<(), Error<SpiE, PinE>>
+    where
+        F: FnMut(usize, &mut Ad983x<DEV, IC>) -> Result<(), Error<SpiE, PinE>>,
+    {
+        for (index, channel) in self.channels.iter_mut().enumerate() {
+            f(index, channel)?;
+        }
+        Ok(())
+    }
+
+    /// Set a phase register of every channel to its offset.
+    ///
+    /// The phases are rounded to the nearest of the 4096 steps available.
+    pub fn set_phase_offsets(
+        &mut self,
+        register: PhaseRegister,
+        offsets: &[Phase; N],
+    ) -> Result<(), Error<SpiE, PinE>> {
+        for (channel, offset) in self.channels.iter_mut().zip(offsets) {
+            channel.set_phase(register, offset.register_value())?;
+        }
+        Ok(())
+    }
+
+    /// Take all devices out of reset, one right after the other.
+    ///
+    /// Each device only needs a single control write, so consecutive devices
+    /// leave reset one SPI transaction apart.
+    /// See [`Ad983x::enable()`].
+    pub fn release(&mut self) -> Result<(), Error<SpiE, PinE>> {
+        for channel in self.channels.iter_mut() {
+            channel.enable()?;
+        }
+        Ok(())
+    }
+
+    fn check_hardware_control<E>(&self) -> Result<(), Error<SpiE, E>> {
+        if self
+            .channels
+            .iter()
+            .all(|channel| channel.hardware_control())
+        {
+            Ok(())
+        } else {
+            Err(Error::InvalidArgument)
+        }
+    }
+
+    /// Put all devices in reset by setting a RESET pin shared by them.
+    ///
+    /// All devices must be AD9834/AD9838 devices with the hardware pin control
+    /// source selected, otherwise `Error::InvalidArgument` is returned and the
+    /// pin is not changed. Errors of the pin are returned as `Error::Pin`.
+    pub fn hold_reset_pin<P>(&mut self, reset: &mut P) -> Result<(), Error<SpiE, P::Error>>
+    where
+        P: OutputPin,
+    {
+        self.set_reset_pin(reset, true)
+    }
+
+    /// Take all devices out of reset at the same time by clearing a RESET pin
+    /// shared by them.
+    ///
+    /// See [`hold_reset_pin()`](#method.hold_reset_pin).
+    pub fn release_reset_pin<P>(&mut self, reset: &mut P) -> Result<(), Error<SpiE, P::Error>>
+    where
+        P: OutputPin,
+    {
+        self.set_reset_pin(reset, false)
+    }
+
+    fn set_reset_pin<P>(&mut self, reset: &mut P, high: bool) -> Result<(), Error<SpiE, P::Error>>
+    where
+        P: OutputPin,
+    {
+        self.check_hardware_control()?;
+        let result = if high {
+            reset.set_high()
+        } else {
+            reset.set_low()
+        };
+        result.map_err(Error::Pin)?;
+        for channel in self.channels.iter_mut() {
+            channel.record_reset_pin(high);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,474 @@
+use core::marker::PhantomData;
+
+use embedded_hal_async::spi::SpiDevice;
+
+use crate::{
+    common::{Registers, Transfer, MAX_TRANSFER_BYTES},
+    frequency::{self, DEFAULT_MCLK_HZ},
+    marker, AsyncAd983x, BitFlags, Config, ControlSource, DeviceConfig, Error, FrequencyRegister,
+    OutputWaveform, Phase, PhaseRegister, PoweredDown, SignBitOutput,
+};
+
+impl<DEV, IC> AsyncAd983x<DEV, IC> {
+    fn create(spi: DEV) -> Self {
+        AsyncAd983x {
+            spi,
+            registers: Registers::new(),
+            mclk_hz: DEFAULT_MCLK_HZ,
+            mclk_correction_ppm: 0.0,
+            _ic: PhantomData,
+        }
+    }
+
+    /// Destroy driver instance, return SPI device instance.
+    pub fn destroy(self) -> DEV {
+        self.spi
+    }
+
+    /// Get the 28-bit word last written to a frequency register
+    ///
+    /// Returns `None` if any of the two 14-bit halves has not been written yet.
+    pub fn frequency(&self, register: FrequencyRegister) -> Option<u32> {
+        self.registers.frequency(register)
+    }
+
+    /// Get the 14 MSBs last written to a frequency register
+    pub fn frequency_msb(&self, register: FrequencyRegister) -> Option<u16> {
+        self.registers.frequency_msb(register)
+    }
+
+    /// Get the 14 LSBs last written to a frequency register
+    pub fn frequency_lsb(&self, register: FrequencyRegister) -> Option<u16> {
+        self.registers.frequency_lsb(register)
+    }
+
+    /// Get the 12-bit value last written to a phase register
+    pub fn phase(&self, register: PhaseRegister) -> Option<u16> {
+        self.registers.phase(register)
+    }
+
+    /// Get the frequency register selected in software
+    pub fn selected_frequency(&self) -> FrequencyRegister {
+        self.registers.control.selected_frequency()
+    }
+
+    /// Get the phase register selected in software
+    pub fn selected_phase(&self) -> PhaseRegister {
+        self.registers.control.selected_phase()
+    }
+
+    /// Get the powered-down configuration selected in software
+    pub fn powered_down(&self) -> PoweredDown {
+        self.registers.control.powered_down()
+    }
+
+    /// Whether the device is held in reset (disabled)
+    pub fn is_reset(&self) -> bool {
+        self.registers.control.is_high(BitFlags::RESET)
+    }
+
+    /// Set the frequency of the master clock (MCLK) connected to the device in Hz.
+    ///
+    /// This is used to compute the tuning words in the Hz-based frequency
+    /// methods like [`set_frequency_hz()`](#method.set_frequency_hz).
+    /// The default is 25 MHz.
+    pub fn set_mclk_frequency(&mut self, hz: u32) {
+        self.mclk_hz = hz;
+    }
+
+    /// Get the configured master clock (MCLK) frequency in Hz.
+    pub fn mclk_frequency(&self) -> u32 {
+        self.mclk_hz
+    }
+
+    /// Set the error of the master clock (MCLK) frequency in ppm.
+    ///
+    /// See [`Ad983x::set_mclk_correction_ppm()`](struct.Ad983x.html#method.set_mclk_correction_ppm).
+    pub fn set_mclk_correction_ppm(&mut self, ppm: f32) {
+        self.mclk_correction_ppm = ppm;
+    }
+
+    /// Get the configured master clock (MCLK) error in ppm.
+    pub fn mclk_correction_ppm(&self) -> f32 {
+        self.mclk_correction_ppm
+    }
+
+    /// Get the actual master clock (MCLK) frequency in Hz (rounded).
+    ///
+    /// See [`Ad983x::corrected_mclk_frequency()`](struct.Ad983x.html#method.corrected_mclk_frequency).
+    pub fn corrected_mclk_frequency(&self) -> u32 {
+        frequency::corrected_mclk(self.mclk_hz, self.mclk_correction_ppm)
+    }
+}
+
+impl<DEV> AsyncAd983x<DEV, marker::Ad9833Ad9837> {
+    /// Get the output waveform selected
+    pub fn output_waveform(&self) -> OutputWaveform {
+        self.registers.control.output_waveform_ad9833()
+    }
+}
+
+impl<DEV> AsyncAd983x<DEV, marker::Ad9834Ad9838> {
+    /// Get the output waveform selected
+    pub fn output_waveform(&self) -> OutputWaveform {
+        self.registers.control.output_waveform_ad9834()
+    }
+
+    /// Get the sign bit output configuration selected
+    pub fn sign_bit_output(&self) -> SignBitOutput {
+        self.registers.control.sign_bit_output()
+    }
+
+    /// Get the control source selected
+    pub fn control_source(&self) -> ControlSource {
+        self.registers.control.control_source()
+    }
+}
+
+impl<DEV, E> AsyncAd983x<DEV, marker::Ad9833Ad9837>
+where
+    DEV: SpiDevice<Error = E>,
+{
+    /// Create a new instance of an AD9833 device.
+    ///
+    /// Remember to call `reset()` before using the device after power up.
+    pub fn new_ad9833(spi: DEV) -> Self {
+        Self::create(spi)
+    }
+
+    /// Create a new instance of an AD9837 device.
+    ///
+    /// Remember to call `reset()` before using the device after power up.
+    pub fn new_ad9837(spi: DEV) -> Self {
+        // Behaves the same as AD9833
+        Self::create(spi)
+    }
+
+    /// Set the output waveform
+    pub async fn set_output_waveform(&mut self, waveform: OutputWaveform) -> Result<(), Error<E>> {
+        let control = self.registers.control.with_output_waveform_ad9833(waveform);
+        self.write_control(control).await
+    }
+
+    /// Bring the device to a configuration and enable it
+    ///
+    /// See `apply()` in [`Ad983x`](crate::Ad983x).
+    pub async fn apply(&mut self, config: &DeviceConfig) -> Result<(), Error<E>> {
+        let control = config.control_ad9833()?;
+        let transfer = Transfer::apply(&self.registers, config, control)?;
+        self.write_transfer(&transfer).await
+    }
+}
+
+impl<DEV, E> AsyncAd983x<DEV, marker::Ad9834Ad9838>
+where
+    DEV: SpiDevice<Error = E>,
+{
+    /// Create a new instance of an AD9834 device.
+    /// Remember to call `reset()` before using the device after power up.
+    pub fn new_ad9834(spi: DEV) -> Self {
+        Self::create(spi)
+    }
+
+    /// Create a new instance of an AD9838 device.
+    /// Remember to call `reset()` before using the device after power up.
+    pub fn new_ad9838(spi: DEV) -> Self {
+        Self::create(spi)
+    }
+
+    /// Set the output waveform
+    ///
+    /// Will return `Error::InvalidArgument` for `SquareMsbOfDac` and `SquareMsbOfDacDiv2`
+    /// as this is not available on AD9834/AD9838 devices. To set the digital output,
+    /// please use `set_sign_bit_output()`.
+    pub async fn set_output_waveform(&mut self, waveform: OutputWaveform) -> Result<(), Error<E>> {
+        let control = self
+            .registers
+            .control
+            .with_output_waveform_ad9834(waveform)?;
+        self.write_control(control).await
+    }
+
+    /// Set the digital output
+    pub async fn set_sign_bit_output(
+        &mut self,
+        configuration: SignBitOutput,
+    ) -> Result<(), Error<E>> {
+        let control = self.registers.control.with_sign_bit_output(configuration);
+        self.write_control(control).await
+    }
+
+    /// Set the control source used for the functions:
+    /// frequency register selection, phase register selection,
+    /// reset of internal registers, and DAC power-down.
+    pub async fn set_control_source(&mut self, source: ControlSource) -> Result<(), Error<E>> {
+        let control = self.registers.control.with_control_source(source);
+        self.write_control(control).await
+    }
+
+    /// Bring the device to a configuration and enable it
+    ///
+    /// See `apply()` in [`Ad983x`](crate::Ad983x).
+    pub async fn apply(&mut self, config: &DeviceConfig) -> Result<(), Error<E>> {
+        let control = config.control_ad9834()?;
+        let transfer = Transfer::apply(&self.registers, config, control)?;
+        self.write_transfer(&transfer).await
+    }
+}
+
+impl<DEV, IC, E> AsyncAd983x<DEV, IC>
+where
+    DEV: SpiDevice<Error = E>,
+{
+    /// Resets the internal registers and leaves the device disabled.
+    ///
+    /// Note that this is ignored in AD9834/AD9838 devices if hardware pin
+    /// control source is selected.
+    pub async fn reset(&mut self) -> Result<(), Error<E>> {
+        self.disable().await
+    }
+
+    /// Disable the device (enable reset)
+    ///
+    /// This resets the internal registers.
+    /// Note that this is ignored in AD9834/AD9838 devices if hardware pin
+    /// control source is selected.
+    pub async fn disable(&mut self) -> Result<(), Error<E>> {
+        let control = self.registers.control.with_high(BitFlags::RESET);
+        self.write_control(control).await
+    }
+
+    /// Enable the device (disable reset)
+    ///
+    /// Note that this is ignored in AD9834/AD9838 devices if hardware pin
+    /// control source is selected.
+    pub async fn enable(&mut self) -> Result<(), Error<E>> {
+        let control = self.registers.control.with_low(BitFlags::RESET);
+        self.write_control(control).await
+    }
+
+    /// Set the frequency as a 28-bit word
+    ///
+    /// This will change the mode to 28-bit if it is not used.
+    /// Returns `Error::InvalidArgument` if providing a value that does not fit in 28 bits.
+    pub async fn set_frequency(
+        &mut self,
+        register: FrequencyRegister,
+        value: u32,
+    ) -> Result<(), Error<E>> {
+        let transfer = Transfer::frequency(self.registers.control, register, value)?;
+        self.write_transfer(&transfer).await
+    }
+
+    /// Set the frequency in Hz.
+    ///
+    /// Returns the frequency actually produced by the device.
+    /// See [`Ad983x::set_frequency_hz()`](struct.Ad983x.html#method.set_frequency_hz).
+    pub async fn set_frequency_hz(
+        &mut self,
+        register: FrequencyRegister,
+        frequency: f32,
+    ) -> Result<f32, Error<E>> {
+        let mclk_hz = self.corrected_mclk_frequency();
+        let word = frequency::word_from_hz(mclk_hz, frequency).ok_or(Error::InvalidArgument)?;
+        self.set_frequency(register, word).await?;
+        Ok(frequency::hz_from_word(mclk_hz, word))
+    }
+
+    /// Set the frequency in millihertz.
+    ///
+    /// Returns the frequency actually produced by the device in millihertz (rounded).
+    /// See [`Ad983x::set_frequency_millihertz()`](struct.Ad983x.html#method.set_frequency_millihertz).
+    pub async fn set_frequency_millihertz(
+        &mut self,
+        register: FrequencyRegister,
+        frequency: u64,
+    ) -> Result<u64, Error<E>> {
+        let mclk_hz = self.corrected_mclk_frequency();
+        let word =
+            frequency::word_from_millihertz(mclk_hz, frequency).ok_or(Error::InvalidArgument)?;
+        self.set_frequency(register, word).await?;
+        Ok(frequency::millihertz_from_word(mclk_hz, word))
+    }
+
+    /// Change the output frequency glitch-free to a 28-bit word
+    ///
+    /// Returns the frequency register now in use.
+    /// See [`Ad983x::retune()`](struct.Ad983x.html#method.retune).
+    pub async fn retune(&mut self, value: u32) -> Result<FrequencyRegister, Error<E>> {
+        let transfer = Transfer::retune(self.registers.control, value)?;
+        self.write_transfer(&transfer).await?;
+        Ok(self.registers.control.selected_frequency())
+    }
+
+    /// Change the output frequency glitch-free to a frequency in Hz.
+    ///
+    /// Returns the frequency actually produced by the device.
+    /// See [`Ad983x::retune_hz()`](struct.Ad983x.html#method.retune_hz).
+    pub async fn retune_hz(&mut self, frequency: f32) -> Result<f32, Error<E>> {
+        let mclk_hz = self.corrected_mclk_frequency();
+        let word = frequency::word_from_hz(mclk_hz, frequency).ok_or(Error::InvalidArgument)?;
+        self.retune(word).await?;
+        Ok(frequency::hz_from_word(mclk_hz, word))
+    }
+
+    /// Change the output frequency glitch-free to a frequency in millihertz.
+    ///
+    /// Returns the frequency actually produced by the device in millihertz (rounded).
+    /// See [`Ad983x::retune_millihertz()`](struct.Ad983x.html#method.retune_millihertz).
+    pub async fn retune_millihertz(&mut self, frequency: u64) -> Result<u64, Error<E>> {
+        let mclk_hz = self.corrected_mclk_frequency();
+        let word =
+            frequency::word_from_millihertz(mclk_hz, frequency).ok_or(Error::InvalidArgument)?;
+        self.retune(word).await?;
+        Ok(frequency::millihertz_from_word(mclk_hz, word))
+    }
+
+    /// Set the frequency 14-bit MSBs
+    ///
+    /// This will deactivate the 28-bit mode if it is not already the case.
+    /// Returns `Error::InvalidArgument` if providing a value that does not fit in 14 bits.
+    pub async fn set_frequency_msb(
+        &mut self,
+        register: FrequencyRegister,
+        value: u16,
+    ) -> Result<(), Error<E>> {
+        let transfer = Transfer::frequency_msb(self.registers.control, register, value)?;
+        self.write_transfer(&transfer).await
+    }
+
+    /// Set the frequency 14-bit LSBs
+    ///
+    /// This will deactivate the 28-bit mode if it is not already the case.
+    /// Returns `Error::InvalidArgument` if providing a value that does not fit in 14 bits.
+    pub async fn set_frequency_lsb(
+        &mut self,
+        register: FrequencyRegister,
+        value: u16,
+    ) -> Result<(), Error<E>> {
+        let transfer = Transfer::frequency_lsb(self.registers.control, register, value)?;
+        self.write_transfer(&transfer).await
+    }
+
+    /// Select the frequency register that is used
+    ///
+    /// Note: this can be overriden through the FSELECT pin in AD9834/AD9838
+    /// devices if hardware pin control source is selected.
+    pub async fn select_frequency(&mut self, register: FrequencyRegister) -> Result<(), Error<E>> {
+        let control = self.registers.control.with_frequency_selected(register);
+        self.write_control(control).await
+    }
+
+    /// Set a phase register (12-bit value)
+    ///
+    /// Returns `Error::InvalidArgument` if providing a value that does not fit in 12 bits.
+    pub async fn set_phase(&mut self, register: PhaseRegister, value: u16) -> Result<(), Error<E>> {
+        let transfer = Transfer::phase(self.registers.control, register, value)?;
+        self.write_transfer(&transfer).await
+    }
+
+    /// Set a phase register from a `Phase`
+    ///
+    /// Returns the phase actually set.
+    /// See [`Ad983x::set_phase_value()`](struct.Ad983x.html#method.set_phase_value).
+    pub async fn set_phase_value(
+        &mut self,
+        register: PhaseRegister,
+        phase: Phase,
+    ) -> Result<Phase, Error<E>> {
+        self.set_phase(register, phase.register_value()).await?;
+        Ok(phase.quantized())
+    }
+
+    /// Set a phase register from a fraction of a turn
+    ///
+    /// Returns the phase actually set in turns.
+    /// See [`Ad983x::set_phase_turns()`](struct.Ad983x.html#method.set_phase_turns).
+    pub async fn set_phase_turns(
+        &mut self,
+        register: PhaseRegister,
+        turns: f32,
+    ) -> Result<f32, Error<E>> {
+        if !turns.is_finite() {
+            return Err(Error::InvalidArgument);
+        }
+        let phase = self
+            .set_phase_value(register, Phase::from_turns(turns))
+            .await?;
+        Ok(phase.turns())
+    }
+
+    /// Set a phase register from an angle in degrees
+    ///
+    /// Returns the phase actually set in degrees.
+    /// See [`Ad983x::set_phase_degrees()`](struct.Ad983x.html#method.set_phase_degrees).
+    pub async fn set_phase_degrees(
+        &mut self,
+        register: PhaseRegister,
+        degrees: f32,
+    ) -> Result<f32, Error<E>> {
+        if !degrees.is_finite() {
+            return Err(Error::InvalidArgument);
+        }
+        let phase = self
+            .set_phase_value(register, Phase::from_degrees(degrees))
+            .await?;
+        Ok(phase.degrees())
+    }
+
+    /// Set a phase register from an angle in radians
+    ///
+    /// Returns the phase actually set in radians.
+    /// See [`Ad983x::set_phase_radians()`](struct.Ad983x.html#method.set_phase_radians).
+    pub async fn set_phase_radians(
+        &mut self,
+        register: PhaseRegister,
+        radians: f32,
+    ) -> Result<f32, Error<E>> {
+        if !radians.is_finite() {
+            return Err(Error::InvalidArgument);
+        }
+        let phase = self
+            .set_phase_value(register, Phase::from_radians(radians))
+            .await?;
+        Ok(phase.radians())
+    }
+
+    /// Select the phase register that is used.
+    ///
+    /// Note: this can be overriden through the PSELECT pin in AD9834/AD9838
+    /// devices if hardware pin control source is selected.
+    pub async fn select_phase(&mut self, register: PhaseRegister) -> Result<(), Error<E>> {
+        let control = self.registers.control.with_phase_selected(register);
+        self.write_control(control).await
+    }
+
+    /// Set device parts powered-down state.
+    ///
+    /// Note: This can be overriden through the SLEEP pin
+    /// in AD9834/AD9838 devices if hardware pin control source is selected.
+    pub async fn set_powered_down(&mut self, config: PoweredDown) -> Result<(), Error<E>> {
+        let control = self.registers.control.with_powered_down(config);
+        self.write_control(control).await
+    }
+
+    async fn write_control(&mut self, control: Config) -> Result<(), Error<E>> {
+        self.write_transfer(&Transfer::control(control)).await
+    }
+
+    async fn write_transfer(&mut self, transfer: &Transfer) -> Result<(), Error<E>> {
+        if transfer.words().is_empty() {
+            return Ok(());
+        }
+        #[cfg(any(feature = "defmt", feature = "log"))]
+        self.registers.trace(transfer.words());
+        let mut buffer = [0; MAX_TRANSFER_BYTES];
+        let result = self
+            .spi
+            .write(transfer.bytes(&mut buffer))
+            .await
+            .map_err(Error::Spi);
+        self.registers.update(transfer.words(), result.is_ok());
+        result
+    }
+}
@@ -0,0 +1,652 @@
+//! Command-line tool to control AD9833/AD9834/AD9837/AD9838 devices
+//! connected to a Linux spidev device.
+//!
+//! Every command prints the SPI frames written as 16-bit words. With
+//! `--dry-run` no device is opened and the frames are only printed.
+//!
+//! The devices are write-only, so the settings not changed by a command are
+//! assumed to be the defaults unless a `--state` file is given. The settings
+//! are then loaded from it and saved back after each command.
+//!
+//! ```text
+//! ad983x --chip ad9838 --mclk 16000000 --state dds.toml reset
+//! ad983x --chip ad9838 --mclk 16000000 --state dds.toml freq 1000
+//! ad983x --dry-run sweep 1000 10000 --points 10 --dwell-us 1000
+//! ```
+
+use std::{cell::Cell, fmt::Debug, fs, path::PathBuf, process::ExitCode, rc::Rc};
+
+use ad983x::{
+    fsk::{Framing, FskConfig, FskModulator},
+    interface::{SpiBusInterface, WriteData},
+    marker,
+    settings::{Chip, DeviceSettings},
+    sweep::{Sweep, Sweeper},
+    Ad983x, ControlSource, DeviceConfig, Error, FrequencyRegister, OutputWaveform, PhaseRegister,
+    PoweredDown, SignBitOutput,
+};
+use clap::{Parser, Subcommand, ValueEnum};
+use embedded_hal::{
+    delay::DelayNs,
+    spi::{ErrorKind, ErrorType, Operation, SpiBus, SpiDevice},
+};
+use linux_embedded_hal::{
+    gpio_cdev::{self, LineRequestFlags},
+    spidev::{SpiModeFlags, SpidevOptions},
+    CdevPin, Delay, SpidevBus, SpidevDevice,
+};
+
+/// Control AD9833/AD9834/AD9837/AD9838 devices on Linux spidev boards
+#[derive(Debug, Parser)]
+#[command(name = "ad983x", version)]
+struct Cli {
+    /// spidev device the DDS is connected to
+    #[arg(long, default_value = "/dev/spidev0.0")]
+    spi: PathBuf,
+    /// GPIO chip of the FSYNC line. The spidev chip select is used if not given.
+    #[arg(long, requires = "fsync_line")]
+    gpiochip: Option<PathBuf>,
+    /// GPIO line offset of the FSYNC pin
+    #[arg(long, requires = "gpiochip")]
+    fsync_line: Option<u32>,
+    /// SPI clock frequency in Hz
+    #[arg(long, default_value_t = 1_000_000)]
+    speed: u32,
+    /// Device model
+    #[arg(long, value_enum, default_value_t = Model::Ad9833)]
+    chip: Model,
+    /// Master clock (MCLK) frequency in Hz
+    #[arg(long, default_value_t = 25_000_000)]
+    mclk: u32,
+    /// TOML file keeping the device settings between invocations
+    #[arg(long)]
+    state: Option<PathBuf>,
+    /// Only print the SPI frames without opening any device
+    #[arg(long)]
+    dry_run: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    #[command(flatten)]
+    Setting(Setting),
+    #[command(flatten)]
+    Stream(Stream),
+}
+
+/// Commands changing the device settings
+#[derive(Debug, Subcommand)]
+enum Setting {
+    /// Set a frequency register in Hz
+    Freq {
+        /// Frequency in Hz
+        hz: f32,
+        /// Frequency register. The selected one is used if not given.
+        #[arg(long, short, value_enum)]
+        register: Option<FreqReg>,
+    },
+    /// Set a phase register in degrees
+    Phase {
+        /// Phase in degrees
+        #[arg(allow_negative_numbers = true)]
+        degrees: f32,
+        /// Phase register. The selected one is used if not given.
+        #[arg(long, short, value_enum)]
+        register: Option<PhaseReg>,
+    },
+    /// Set the output waveform
+    Wave {
+        /// Output waveform. The square waves are not available on AD9834/AD9838.
+        #[arg(value_enum)]
+        waveform: Waveform,
+    },
+    /// Select the frequency or phase register used for the output
+    Select {
+        /// Frequency or phase register
+        #[arg(value_enum)]
+        register: Register,
+    },
+    /// Power down device parts
+    Sleep {
+        /// Parts to power down
+        #[arg(value_enum)]
+        parts: Parts,
+    },
+    /// Reset the device and load all settings
+    Reset,
+}
+
+/// Commands streaming frequencies to the device
+#[derive(Debug, Subcommand)]
+enum Stream {
+    /// Sweep the output frequency
+    Sweep {
+        /// Start frequency in Hz
+        start: f32,
+        /// Stop frequency in Hz
+        stop: f32,
+        /// Number of frequencies
+        #[arg(long, default_value_t = 100)]
+        points: usize,
+        /// Time spent on each frequency in microseconds
+        #[arg(long, default_value_t = 10_000)]
+        dwell_us: u32,
+        /// Space the frequencies logarithmically
+        #[arg(long)]
+        log: bool,
+    },
+    /// Transmit data with binary FSK modulation
+    Fsk {
+        /// Mark (bit 1) frequency in Hz
+        mark: f32,
+        /// Space (bit 0) frequency in Hz
+        space: f32,
+        /// Text to transmit
+        data: String,
+        /// Symbol rate in baud
+        #[arg(long, default_value_t = 1200)]
+        baud: u32,
+        /// Use UART-style 8N1 framing
+        #[arg(long)]
+        uart: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Model {
+    Ad9833,
+    Ad9834,
+    Ad9837,
+    Ad9838,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum FreqReg {
+    F0,
+    F1,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum PhaseReg {
+    P0,
+    P1,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Register {
+    F0,
+    F1,
+    P0,
+    P1,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Waveform {
+    Sine,
+    Triangle,
+    Square,
+    SquareHalf,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Parts {
+    None,
+    Dac,
+    Clock,
+    All,
+}
+
+impl From<Model> for Chip {
+    fn from(model: Model) -> Self {
+        match model {
+            Model::Ad9833 => Chip::Ad9833,
+            Model::Ad9834 => Chip::Ad9834,
+            Model::Ad9837 => Chip::Ad9837,
+            Model::Ad9838 => Chip::Ad9838,
+        }
+    }
+}
+
+impl From<FreqReg> for FrequencyRegister {
+    fn from(register: FreqReg) -> Self {
+        match register {
+            FreqReg::F0 => FrequencyRegister::F0,
+            FreqReg::F1 => FrequencyRegister::F1,
+        }
+    }
+}
+
+impl From<PhaseReg> for PhaseRegister {
+    fn from(register: PhaseReg) -> Self {
+        match register {
+            PhaseReg::P0 => PhaseRegister::P0,
+            PhaseReg::P1 => PhaseRegister::P1,
+        }
+    }
+}
+
+impl From<Waveform> for OutputWaveform {
+    fn from(waveform: Waveform) -> Self {
+        match waveform {
+            Waveform::Sine => OutputWaveform::Sinusoidal,
+            Waveform::Triangle => OutputWaveform::Triangle,
+            Waveform::Square => OutputWaveform::SquareMsbOfDac,
+            Waveform::SquareHalf => OutputWaveform::SquareMsbOfDacDiv2,
+        }
+    }
+}
+
+impl From<Parts> for PoweredDown {
+    fn from(parts: Parts) -> Self {
+        match parts {
+            Parts::None => PoweredDown::Nothing,
+            Parts::Dac => PoweredDown::Dac,
+            Parts::Clock => PoweredDown::InternalClock,
+            Parts::All => PoweredDown::DacAndInternalClock,
+        }
+    }
+}
+
+/// Where the SPI frames go
+enum Target {
+    /// spidev device using the chip select as FSYNC, `None` in dry-run mode
+    Device(Option<SpidevDevice>),
+    /// spidev bus with the FSYNC pin on a GPIO line
+    Bus(SpidevBus, CdevPin),
+}
+
+/// SPI device or bus printing the frames written to it
+///
+/// While muted, nothing is printed or written. This is used to bring the
+/// driver to the settings of the state file.
+struct Frames<T> {
+    target: T,
+    muted: Rc<Cell<bool>>,
+}
+
+impl<T> Frames<T> {
+    /// Print a frame unless muted. Returns whether it should be written.
+    fn print(&self, bytes: &[u8]) -> bool {
+        if self.muted.get() {
+            return false;
+        }
+        let words: Vec<String> = bytes
+            .chunks(2)
+            .map(|word| format!("0x{:02X}{:02X}", word[0], word[1]))
+            .collect();
+        println!("SPI frame: {}", words.join(" "));
+        true
+    }
+}
+
+#[derive(Debug)]
+struct SpiError(String);
+
+impl embedded_hal::spi::Error for SpiError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+fn spi_error(error: impl ToString) -> SpiError {
+    SpiError(error.to_string())
+}
+
+impl<T> ErrorType for Frames<T> {
+    type Error = SpiError;
+}
+
+impl SpiDevice for Frames<Option<SpidevDevice>> {
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), SpiError> {
+        let mut write = false;
+        for operation in operations.iter() {
+            if let Operation::Write(bytes) = operation {
+                write |= self.print(bytes);
+            }
+        }
+        match &mut self.target {
+            Some(dev) if write => dev.transaction(operations).map_err(spi_error),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl SpiBus for Frames<SpidevBus> {
+    fn read(&mut self, words: &mut [u8]) -> Result<(), SpiError> {
+        self.target.read(words).map_err(spi_error)
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), SpiError> {
+        if !self.print(words) {
+            return Ok(());
+        }
+        self.target.write(words).map_err(spi_error)
+    }
+
+    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), SpiError> {
+        self.target.transfer(read, write).map_err(spi_error)
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), SpiError> {
+        self.target.transfer_in_place(words).map_err(spi_error)
+    }
+
+    fn flush(&mut self) -> Result<(), SpiError> {
+        self.target.flush().map_err(spi_error)
+    }
+}
+
+/// Delay used in dry-run mode, where there is no reason to wait
+struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+enum Dds<DEV> {
+    Ad9833(Ad983x<DEV, marker::Ad9833Ad9837>),
+    Ad9834(Ad983x<DEV, marker::Ad9834Ad9838>),
+}
+
+/// Run the same code for both device families
+macro_rules! dispatch {
+    ($dds:expr, $d:ident => $body:expr) => {
+        match $dds {
+            Dds::Ad9833($d) => $body,
+            Dds::Ad9834($d) => $body,
+        }
+    };
+}
+
+impl<DEV, PinE> Dds<DEV>
+where
+    DEV: WriteData<SpiError = SpiError, PinError = PinE>,
+    PinE: Debug,
+{
+    fn new(model: Model, mclk_hz: u32, dev: DEV) -> Self {
+        let mut dds = match model {
+            Model::Ad9833 => Dds::Ad9833(Ad983x::new_ad9833(dev)),
+            Model::Ad9837 => Dds::Ad9833(Ad983x::new_ad9837(dev)),
+            Model::Ad9834 => Dds::Ad9834(Ad983x::new_ad9834(dev)),
+            Model::Ad9838 => Dds::Ad9834(Ad983x::new_ad9838(dev)),
+        };
+        dispatch!(&mut dds, d => d.set_mclk_frequency(mclk_hz));
+        dds
+    }
+
+    /// Settings of the device as written so far
+    fn config(&self) -> DeviceConfig {
+        let (sign_bit_output, control_source) = match self {
+            Dds::Ad9833(_) => (SignBitOutput::Disabled, ControlSource::Software),
+            Dds::Ad9834(d) => (d.sign_bit_output(), d.control_source()),
+        };
+        dispatch!(self, d => DeviceConfig {
+            frequency0: d.frequency(FrequencyRegister::F0).unwrap_or(0),
+            frequency1: d.frequency(FrequencyRegister::F1).unwrap_or(0),
+            phase0: d.phase(PhaseRegister::P0).unwrap_or(0),
+            phase1: d.phase(PhaseRegister::P1).unwrap_or(0),
+            selected_frequency: d.selected_frequency(),
+            selected_phase: d.selected_phase(),
+            output_waveform: d.output_waveform(),
+            sign_bit_output,
+            powered_down: d.powered_down(),
+            control_source,
+        })
+    }
+
+    /// Run a command. `state` holds the settings loaded from the state file.
+    fn run<D: DelayNs>(
+        mut self,
+        command: &Command,
+        state: &DeviceConfig,
+        delay: &mut D,
+    ) -> Result<Self, String> {
+        match command {
+            Command::Setting(setting) => {
+                self.configure(setting, state)?;
+                Ok(self)
+            }
+            Command::Stream(stream) => self.stream(stream, delay),
+        }
+    }
+
+    fn stream<D: DelayNs>(self, stream: &Stream, delay: &mut D) -> Result<Self, String> {
+        match *stream {
+            Stream::Sweep {
+                start,
+                stop,
+                points,
+                dwell_us,
+                log,
+            } => {
+                let sweep = if log {
+                    Sweep::logarithmic(start, stop, points)
+                } else {
+                    Sweep::linear(start, stop, points)
+                };
+                let sweep = sweep.with_dwell_us(dwell_us);
+                Ok(match self {
+                    Dds::Ad9833(d) => Dds::Ad9833(run_sweep(d, sweep, delay)?),
+                    Dds::Ad9834(d) => Dds::Ad9834(run_sweep(d, sweep, delay)?),
+                })
+            }
+            Stream::Fsk {
+                mark,
+                space,
+                ref data,
+                baud,
+                uart,
+            } => {
+                let framing = if uart {
+                    Framing::UART_8N1
+                } else {
+                    Framing::NONE
+                };
+                let config = FskConfig::new(mark, space, baud).with_framing(framing);
+                Ok(match self {
+                    Dds::Ad9833(d) => Dds::Ad9833(run_fsk(d, config, data.as_bytes(), delay)?),
+                    Dds::Ad9834(d) => Dds::Ad9834(run_fsk(d, config, data.as_bytes(), delay)?),
+                })
+            }
+        }
+    }
+
+    fn configure(&mut self, setting: &Setting, state: &DeviceConfig) -> Result<(), String> {
+        let config = self.config();
+        match *setting {
+            Setting::Freq { hz, register } => {
+                let register = register.map_or(config.selected_frequency, Into::into);
+                let actual =
+                    dispatch!(self, d => d.set_frequency_hz(register, hz)).map_err(describe)?;
+                println!("{:?}: {} Hz", register, actual);
+            }
+            Setting::Phase { degrees, register } => {
+                let register = register.map_or(config.selected_phase, Into::into);
+                let actual = dispatch!(self, d => d.set_phase_degrees(register, degrees))
+                    .map_err(describe)?;
+                println!("{:?}: {}°", register, actual);
+            }
+            Setting::Wave { waveform } => {
+                dispatch!(self, d => d.set_output_waveform(waveform.into())).map_err(describe)?;
+            }
+            Setting::Select { register } => match register {
+                Register::F0 | Register::F1 => {
+                    let register = match register {
+                        Register::F0 => FrequencyRegister::F0,
+                        _ => FrequencyRegister::F1,
+                    };
+                    dispatch!(self, d => d.select_frequency(register)).map_err(describe)?;
+                }
+                Register::P0 | Register::P1 => {
+                    let register = match register {
+                        Register::P0 => PhaseRegister::P0,
+                        _ => PhaseRegister::P1,
+                    };
+                    dispatch!(self, d => d.select_phase(register)).map_err(describe)?;
+                }
+            },
+            Setting::Sleep { parts } => {
+                dispatch!(self, d => d.set_powered_down(parts.into())).map_err(describe)?;
+            }
+            Setting::Reset => {
+                dispatch!(self, d => d.apply(state)).map_err(describe)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn run_sweep<DEV, IC, D, PinE>(
+    dds: Ad983x<DEV, IC>,
+    sweep: Sweep<'_>,
+    delay: &mut D,
+) -> Result<Ad983x<DEV, IC>, String>
+where
+    DEV: WriteData<SpiError = SpiError, PinError = PinE>,
+    D: DelayNs,
+    PinE: Debug,
+{
+    let mut sweeper = Sweeper::new(dds, sweep);
+    sweeper.run(delay).map_err(describe)?;
+    Ok(sweeper.destroy())
+}
+
+fn run_fsk<DEV, IC, D, PinE>(
+    dds: Ad983x<DEV, IC>,
+    config: FskConfig,
+    data: &[u8],
+    delay: &mut D,
+) -> Result<Ad983x<DEV, IC>, String>
+where
+    DEV: WriteData<SpiError = SpiError, PinError = PinE>,
+    D: DelayNs,
+    PinE: Debug,
+{
+    let mut fsk = FskModulator::new(dds, config);
+    fsk.init().map_err(describe)?;
+    fsk.send_bytes(data, delay).map_err(describe)?;
+    fsk.idle().map_err(describe)?;
+    Ok(fsk.destroy())
+}
+
+fn describe<PinE: Debug>(error: Error<SpiError, PinE>) -> String {
+    match error {
+        Error::Spi(e) => format!("SPI error: {}", e.0),
+        Error::Pin(e) => format!("FSYNC pin error: {:?}", e),
+        Error::InvalidArgument => "invalid argument for this device".into(),
+        Error::PinNotAssigned(pin) => format!("no {:?} pin assigned", pin),
+    }
+}
+
+fn open(cli: &Cli) -> Result<Target, String> {
+    if cli.dry_run {
+        return Ok(Target::Device(None));
+    }
+    let spi_error = |e: &dyn std::fmt::Display| format!("{}: {}", cli.spi.display(), e);
+    let mut options = SpidevOptions::new();
+    options.bits_per_word(8).max_speed_hz(cli.speed);
+    match (&cli.gpiochip, cli.fsync_line) {
+        (Some(gpiochip), Some(line)) => {
+            let mut bus = SpidevBus::open(&cli.spi).map_err(|e| spi_error(&e))?;
+            options.mode(SpiModeFlags::SPI_MODE_2 | SpiModeFlags::SPI_NO_CS);
+            bus.configure(&options).map_err(|e| spi_error(&e))?;
+            let gpio_error = |e: gpio_cdev::Error| format!("{}: {}", gpiochip.display(), e);
+            let handle = gpio_cdev::Chip::new(gpiochip)
+                .and_then(|mut chip| chip.get_line(line))
+                .and_then(|line| line.request(LineRequestFlags::OUTPUT, 1, "ad983x-fsync"))
+                .map_err(gpio_error)?;
+            let fsync = CdevPin::new(handle).map_err(gpio_error)?;
+            Ok(Target::Bus(bus, fsync))
+        }
+        _ => {
+            let mut dev = SpidevDevice::open(&cli.spi).map_err(|e| spi_error(&e))?;
+            options.mode(SpiModeFlags::SPI_MODE_2);
+            dev.configure(&options).map_err(|e| spi_error(&e))?;
+            Ok(Target::Device(Some(dev)))
+        }
+    }
+}
+
+fn load_state(cli: &Cli) -> Result<DeviceConfig, String> {
+    let path = match &cli.state {
+        Some(path) if path.exists() => path,
+        _ => return Ok(DeviceConfig::default()),
+    };
+    let error = |e: &dyn std::fmt::Display| format!("{}: {}", path.display(), e);
+    let text = fs::read_to_string(path).map_err(|e| error(&e))?;
+    let settings: DeviceSettings = toml::from_str(&text).map_err(|e| error(&e))?;
+    if settings.chip != cli.chip.into() {
+        return Err(error(&"settings for a different chip"));
+    }
+    settings.to_config().map_err(|e| error(&e))
+}
+
+fn save_state(cli: &Cli, config: &DeviceConfig) -> Result<(), String> {
+    if let Some(path) = &cli.state {
+        let settings = DeviceSettings::from_config(cli.chip.into(), cli.mclk, config);
+        let text = toml::to_string(&settings).map_err(|e| e.to_string())?;
+        fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+fn run(cli: &Cli) -> Result<(), String> {
+    let config = load_state(cli)?;
+    let muted = Rc::new(Cell::new(true));
+    let config = match open(cli)? {
+        Target::Device(dev) => {
+            let frames = Frames {
+                target: dev,
+                muted: Rc::clone(&muted),
+            };
+            execute(cli, &config, &muted, frames)?
+        }
+        Target::Bus(bus, fsync) => {
+            let frames = Frames {
+                target: bus,
+                muted: Rc::clone(&muted),
+            };
+            execute(cli, &config, &muted, SpiBusInterface::new(frames, fsync))?
+        }
+    };
+    save_state(cli, &config)
+}
+
+/// Run the command on a device. Returns the settings of the device afterwards.
+fn execute<DEV, PinE>(
+    cli: &Cli,
+    config: &DeviceConfig,
+    muted: &Cell<bool>,
+    dev: DEV,
+) -> Result<DeviceConfig, String>
+where
+    DEV: WriteData<SpiError = SpiError, PinError = PinE>,
+    PinE: Debug,
+{
+    let mut dds = Dds::new(cli.chip, cli.mclk, dev);
+    // Bring the driver to the stored settings without writing anything.
+    // Resetting writes everything instead.
+    if !matches!(cli.command, Command::Setting(Setting::Reset)) {
+        dispatch!(&mut dds, d => d.apply(config)).map_err(describe)?;
+    }
+    muted.set(false);
+    let dds = if cli.dry_run {
+        dds.run(&cli.command, config, &mut NoDelay)?
+    } else {
+        dds.run(&cli.command, config, &mut Delay)?
+    };
+    Ok(dds.config())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
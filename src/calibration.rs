@@ -0,0 +1,115 @@
+//! Master clock (MCLK) calibration
+//!
+//! Crystal oscillators are off from their nominal frequency by tens of ppm,
+//! and every output frequency is off by the same factor. The error can be
+//! configured with [`Ad983x::set_mclk_correction_ppm()`] so that the Hz-based
+//! frequency methods compensate it.
+//!
+//! [`Ad983x::calibrate_mclk()`] measures the error: it sets a known tuning
+//! word, measures the output frequency with a [`FrequencyMeter`], for example
+//! a timer capturing the edges of the sign bit output or a frequency counter,
+//! and stores the resulting correction.
+//!
+//! ```no_run
+//! use ad983x::{calibration::FrequencyMeter, Ad983x};
+//! use linux_embedded_hal::SpidevDevice;
+//!
+//! struct Counter;
+//!
+//! impl FrequencyMeter for Counter {
+//!     type Error = ();
+//!
+//!     fn measure_hz(&mut self) -> Result<f64, ()> {
+//!         // Count the output edges during a gate time.
+//!         Ok(1_000_012.5)
+//!     }
+//! }
+//!
+//! let dev = SpidevDevice::open("/dev/spidev0.0").unwrap();
+//! let mut dds = Ad983x::new_ad9833(dev);
+//! dds.reset().unwrap();
+//! dds.enable().unwrap();
+//! // 1 MHz nominal output
+//! let ppm = dds.calibrate_mclk(10_737_418, &mut Counter).unwrap();
+//! println!("MCLK error: {} ppm", ppm);
+//! // Now corrected
+//! dds.set_frequency_hz(ad983x::FrequencyRegister::F0, 440.0).unwrap();
+//! ```
+
+use crate::{frequency, interface::WriteData, state, Ad983x, Error};
+
+/// Measurement of the output frequency of a device
+///
+/// This is implemented by the application with whatever is available to
+/// measure the output, like a timer input capture or a frequency counter.
+pub trait FrequencyMeter {
+    /// Measurement error
+    type Error;
+
+    /// Measure the current output frequency in Hz.
+    ///
+    /// This should wait as long as needed for the output to settle and for
+    /// the desired measurement resolution.
+    fn measure_hz(&mut self) -> Result<f64, Self::Error>;
+}
+
+/// Calibration errors
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CalibrationError<SpiE, PinE, MeterE> {
+    /// Error setting the tuning word
+    Device(Error<SpiE, PinE>),
+    /// Error of the frequency meter
+    Meter(MeterE),
+    /// The measured frequency is not positive or not finite
+    InvalidMeasurement,
+}
+
+impl<SpiE, PinE, MeterE> From<Error<SpiE, PinE>> for CalibrationError<SpiE, PinE, MeterE> {
+    fn from(error: Error<SpiE, PinE>) -> Self {
+        CalibrationError::Device(error)
+    }
+}
+
+/// Error in ppm of a measured output frequency relative to the nominal one
+fn error_ppm(mclk_hz: u32, word: u32, measured_hz: f64) -> f32 {
+    let nominal = frequency::hz_from_word_f64(mclk_hz, word);
+    ((measured_hz / nominal - 1.0) * 1e6) as f32
+}
+
+impl<DEV, IC, S, SpiE, PinE> Ad983x<DEV, IC, S>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+    S: state::Configurable,
+{
+    /// Measure and store the master clock (MCLK) error.
+    ///
+    /// This sets the 28-bit tuning `word` in the selected frequency register,
+    /// measures the output frequency and compares it to the frequency expected
+    /// with the nominal master clock frequency. The resulting error is stored
+    /// like with [`set_mclk_correction_ppm()`](#method.set_mclk_correction_ppm)
+    /// and returned.
+    ///
+    /// The device must be running and its output connected to the meter.
+    /// A large word gives a better resolution, for example the word
+    /// of about 1 MHz. The previous correction is kept if the measurement fails.
+    /// Returns `Error::InvalidArgument` if the word is zero or does not fit
+    /// in 28 bits.
+    pub fn calibrate_mclk<M: FrequencyMeter>(
+        &mut self,
+        word: u32,
+        meter: &mut M,
+    ) -> Result<f32, CalibrationError<SpiE, PinE, M::Error>> {
+        if word == 0 || self.mclk_hz == 0 {
+            return Err(Error::InvalidArgument.into());
+        }
+        self.set_frequency(self.selected_frequency(), word)?;
+        let measured = meter.measure_hz().map_err(CalibrationError::Meter)?;
+        if !measured.is_finite() || measured <= 0.0 {
+            return Err(CalibrationError::InvalidMeasurement);
+        }
+        let ppm = error_ppm(self.mclk_hz, word, measured);
+        self.mclk_correction_ppm = ppm;
+        Ok(ppm)
+    }
+}
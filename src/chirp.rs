@@ -0,0 +1,322 @@
+//! Linear frequency chirps (FMCW)
+//!
+//! A [`Chirp`] describes a linear up, down or triangle frequency ramp with a
+//! fixed number of frequency updates per second. Its tuning words are
+//! precomputed into a buffer with [`Chirp::tuning_words()`] so that a
+//! [`Chirper`] only needs to write them out, for example from a timer
+//! interrupt with [`Chirper::next_step()`].
+//!
+//! Every step writes into the frequency register that is not used for the
+//! output and then selects it, like [`Ad983x::retune()`]. However, only the
+//! 14-bit halves of the tuning word that differ from what that register holds
+//! are written, and the control word selecting the register already sets up
+//! the mode for the following step. For a slow ramp this is two SPI frames
+//! per step instead of three.
+//!
+//! ```no_run
+//! use ad983x::{chirp::{Chirp, Chirper}, Ad983x};
+//! use embedded_hal_bus::spi::ExclusiveDevice;
+//! use linux_embedded_hal::{Delay, SpidevBus, SysfsPin};
+//!
+//! let spi = SpidevBus::open("/dev/spidev0.0").unwrap();
+//! let chip_select = SysfsPin::new(25);
+//! let dev = ExclusiveDevice::new(spi, chip_select, Delay);
+//! let mut dds = Ad983x::new_ad9833(dev);
+//! dds.reset().unwrap();
+//! dds.enable().unwrap();
+//! // 38 kHz to 42 kHz and back in 2 ms with 200 updates per millisecond
+//! let chirp = Chirp::triangle(38_000.0, 42_000.0, 2_000, 200_000);
+//! let mut buffer = [0; 400];
+//! let words = chirp.tuning_words(dds.corrected_mclk_frequency(), &mut buffer).unwrap();
+//! let mut chirper = Chirper::new(dds, chirp, words);
+//! // 16-bit SPI frames at 8 MHz
+//! println!("{} Hz/s", chirper.achieved_sweep_rate(2_000));
+//! chirper.run(&mut Delay).unwrap();
+//! ```
+
+use embedded_hal::delay::DelayNs;
+
+use crate::{frequency, interface::WriteData, timing::SymbolClock, Ad983x, Error};
+
+/// Shape of a chirp
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ChirpShape {
+    /// From the start to the stop frequency
+    Up,
+    /// From the stop to the start frequency
+    Down,
+    /// From the start to the stop frequency in the first half and back in the
+    /// second half. The last step is just above the start frequency so that
+    /// repeating the chirp is seamless.
+    Triangle,
+}
+
+/// Description of a linear chirp
+///
+/// All frequencies are in Hz.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Chirp {
+    shape: ChirpShape,
+    start: f32,
+    stop: f32,
+    duration_us: u32,
+    update_rate: u32,
+}
+
+impl Chirp {
+    /// Chirp of the given shape between `start` and `stop` lasting `duration_us`
+    /// microseconds, with `update_rate` frequency steps per second.
+    pub fn new(
+        shape: ChirpShape,
+        start: f32,
+        stop: f32,
+        duration_us: u32,
+        update_rate: u32,
+    ) -> Self {
+        Chirp {
+            shape,
+            start,
+            stop,
+            duration_us,
+            update_rate,
+        }
+    }
+
+    /// Up chirp from `start` to `stop`. See [`new()`](#method.new).
+    pub fn up(start: f32, stop: f32, duration_us: u32, update_rate: u32) -> Self {
+        Self::new(ChirpShape::Up, start, stop, duration_us, update_rate)
+    }
+
+    /// Down chirp from `stop` to `start`. See [`new()`](#method.new).
+    pub fn down(start: f32, stop: f32, duration_us: u32, update_rate: u32) -> Self {
+        Self::new(ChirpShape::Down, start, stop, duration_us, update_rate)
+    }
+
+    /// Triangle chirp from `start` to `stop` and back. See [`new()`](#method.new).
+    pub fn triangle(start: f32, stop: f32, duration_us: u32, update_rate: u32) -> Self {
+        Self::new(ChirpShape::Triangle, start, stop, duration_us, update_rate)
+    }
+
+    /// Chirp shape
+    pub fn shape(&self) -> ChirpShape {
+        self.shape
+    }
+
+    /// Duration in microseconds
+    pub fn duration_us(&self) -> u32 {
+        self.duration_us
+    }
+
+    /// Frequency steps per second
+    pub fn update_rate(&self) -> u32 {
+        self.update_rate
+    }
+
+    /// Number of frequency steps
+    pub fn len(&self) -> usize {
+        let steps = u64::from(self.duration_us) * u64::from(self.update_rate);
+        ((steps + 500_000) / 1_000_000) as usize
+    }
+
+    /// Whether the chirp contains no steps
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Nominal frequency change rate in Hz per second
+    pub fn sweep_rate(&self) -> f32 {
+        if self.duration_us == 0 {
+            return 0.0;
+        }
+        let span = (f64::from(self.stop) - f64::from(self.start)).abs();
+        let span = match self.shape {
+            ChirpShape::Up | ChirpShape::Down => span,
+            ChirpShape::Triangle => 2.0 * span,
+        };
+        (span * 1e6 / f64::from(self.duration_us)) as f32
+    }
+
+    /// Compute the tuning words of all the steps for a master clock frequency
+    /// into a buffer.
+    ///
+    /// Returns `None` if the buffer is shorter than [`len()`](#method.len)
+    /// or a frequency cannot be produced by the device.
+    pub fn tuning_words<'b>(&self, mclk_hz: u32, buffer: &'b mut [u32]) -> Option<&'b [u32]> {
+        let points = self.len();
+        let words = buffer.get_mut(..points)?;
+        let start = frequency::word_from_hz_f64(mclk_hz, f64::from(self.start))?;
+        let stop = frequency::word_from_hz_f64(mclk_hz, f64::from(self.stop))?;
+        let (from, to) = match self.shape {
+            ChirpShape::Up | ChirpShape::Triangle => (i64::from(start), i64::from(stop)),
+            ChirpShape::Down => (i64::from(stop), i64::from(start)),
+        };
+        let span = to - from;
+        let half = points / 2;
+        for (index, word) in words.iter_mut().enumerate() {
+            let (position, length) = match self.shape {
+                ChirpShape::Up | ChirpShape::Down => (index, points.saturating_sub(1)),
+                ChirpShape::Triangle if index <= half => (index, half),
+                ChirpShape::Triangle => (points - index, half),
+            };
+            let offset = if length == 0 {
+                0
+            } else {
+                let scaled = span * position as i64;
+                let length = length as i64;
+                (2 * scaled + scaled.signum() * length) / (2 * length)
+            };
+            *word = (from + offset) as u32;
+        }
+        Some(words)
+    }
+}
+
+/// Writes the steps of a [`Chirp`] to a device
+///
+/// On AD9834/AD9838 devices with the hardware pin control source selected
+/// every step is written with [`Ad983x::retune()`] instead, which requires the
+/// FSELECT pin to be assigned.
+#[derive(Debug)]
+pub struct Chirper<'a, DEV, IC> {
+    dds: Ad983x<DEV, IC>,
+    chirp: Chirp,
+    words: &'a [u32],
+    index: usize,
+    repeat: bool,
+    clock: SymbolClock,
+}
+
+impl<'a, DEV, IC> Chirper<'a, DEV, IC> {
+    /// Create a new chirper for the tuning words computed with
+    /// [`Chirp::tuning_words()`]. The device should already be reset and enabled.
+    pub fn new(dds: Ad983x<DEV, IC>, chirp: Chirp, words: &'a [u32]) -> Self {
+        Chirper {
+            dds,
+            chirp,
+            words,
+            index: 0,
+            repeat: false,
+            clock: SymbolClock::new(chirp.update_rate),
+        }
+    }
+
+    /// Start over from the first step after the last one instead of finishing.
+    pub fn with_repeat(self) -> Self {
+        Chirper {
+            repeat: true,
+            ..self
+        }
+    }
+
+    /// Destroy the chirper and return the device.
+    pub fn destroy(self) -> Ad983x<DEV, IC> {
+        self.dds
+    }
+
+    /// Chirp description
+    pub fn chirp(&self) -> &Chirp {
+        &self.chirp
+    }
+
+    /// Index of the next step
+    pub fn position(&self) -> usize {
+        self.index
+    }
+
+    /// Whether all the steps of the chirp have been written
+    ///
+    /// This is never the case when repeating.
+    pub fn is_finished(&self) -> bool {
+        !self.repeat && self.index >= self.words.len()
+    }
+
+    /// Start over from the first step
+    pub fn restart(&mut self) {
+        self.index = 0;
+        self.clock = SymbolClock::new(self.chirp.update_rate);
+    }
+
+    /// Number of SPI frames written in a step once the chirp is running
+    ///
+    /// The first two steps of a chirp that is not repeated find the frequency
+    /// registers with unknown content, so they take three frames.
+    pub fn step_frames(&self, index: usize) -> usize {
+        let len = self.words.len();
+        let previous = if index >= 2 {
+            self.words.get(index - 2)
+        } else if self.repeat && len >= 2 {
+            self.words.get(len + index - 2)
+        } else {
+            None
+        };
+        match (previous, self.words.get(index)) {
+            (Some(previous), Some(word)) => {
+                let changed = previous ^ word;
+                1 + usize::from(changed & 0x3FFF != 0) + usize::from(changed >> 14 != 0)
+            }
+            _ => 3,
+        }
+    }
+
+    /// Sweep rate in Hz per second achieved when each SPI frame takes `frame_ns`
+    /// nanoseconds
+    ///
+    /// Steps whose SPI frames take longer than the update period stretch the
+    /// chirp and reduce the sweep rate below [`Chirp::sweep_rate()`].
+    pub fn achieved_sweep_rate(&self, frame_ns: u32) -> f32 {
+        if self.words.is_empty() || self.chirp.update_rate == 0 {
+            return 0.0;
+        }
+        let period_ns = 1e9 / f64::from(self.chirp.update_rate);
+        let actual_ns: f64 = (0..self.words.len())
+            .map(|index| {
+                let spi_ns = self.step_frames(index) as f64 * f64::from(frame_ns);
+                spi_ns.max(period_ns)
+            })
+            .sum();
+        let nominal_ns = self.words.len() as f64 * period_ns;
+        (f64::from(self.chirp.sweep_rate()) * nominal_ns / actual_ns) as f32
+    }
+}
+
+impl<DEV, IC, SpiE, PinE> Chirper<'_, DEV, IC>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+{
+    /// Write the next step of the chirp without waiting.
+    ///
+    /// This is intended to be called from a timer interrupt at the update
+    /// rate. Returns the tuning word written or `None` if the chirp is finished.
+    /// Returns `Error::InvalidArgument` if a tuning word does not fit in 28 bits.
+    pub fn next_step(&mut self) -> Result<Option<u32>, Error<SpiE, PinE>> {
+        if self.index >= self.words.len() {
+            if !self.repeat || self.words.is_empty() {
+                return Ok(None);
+            }
+            self.index = 0;
+        }
+        let word = self.words[self.index];
+        let next = match self.words.get(self.index + 1) {
+            Some(next) => Some(*next),
+            None if self.repeat => self.words.first().copied(),
+            None => None,
+        };
+        self.dds.retune_changed(word, next)?;
+        self.index += 1;
+        Ok(Some(word))
+    }
+
+    /// Write the remaining steps of the chirp, waiting for the update period
+    /// after each.
+    ///
+    /// The time needed for the SPI write adds to the update period.
+    /// When repeating, this stops at the end of the current repetition.
+    pub fn run<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<SpiE, PinE>> {
+        while self.index < self.words.len() {
+            self.next_step()?;
+            delay.delay_ns(self.clock.next_period_ns());
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,8 @@
-use embedded_hal::{digital::OutputPin, spi::SpiDevice};
-
+#[cfg(any(feature = "defmt", feature = "log"))]
+use crate::trace;
 use crate::{
-    Ad983x, BitFlags, Config, DataFormat, Error, FrequencyRegister, PhaseRegister, PoweredDown,
+    frequency::DEFAULT_MCLK_HZ, interface::WriteData, state, Ad983x, BitFlags, Config, ControlPin,
+    DeviceConfig, Error, FrequencyRegister, PhaseRegister, PoweredDown,
 };
 use core::marker::PhantomData;
 
@@ -16,6 +17,80 @@ impl Config {
             bits: self.bits & !mask,
         }
     }
+
+    pub(crate) fn selected_frequency(self) -> FrequencyRegister {
+        if self.bits & BitFlags::FSELECT != 0 {
+            FrequencyRegister::F1
+        } else {
+            FrequencyRegister::F0
+        }
+    }
+
+    pub(crate) fn with_frequency_selected(self, register: FrequencyRegister) -> Self {
+        match register {
+            FrequencyRegister::F0 => self.with_low(BitFlags::FSELECT),
+            FrequencyRegister::F1 => self.with_high(BitFlags::FSELECT),
+        }
+    }
+
+    pub(crate) fn selected_phase(self) -> PhaseRegister {
+        if self.bits & BitFlags::PSELECT != 0 {
+            PhaseRegister::P1
+        } else {
+            PhaseRegister::P0
+        }
+    }
+
+    pub(crate) fn with_phase_selected(self, register: PhaseRegister) -> Self {
+        match register {
+            PhaseRegister::P0 => self.with_low(BitFlags::PSELECT),
+            PhaseRegister::P1 => self.with_high(BitFlags::PSELECT),
+        }
+    }
+
+    /// Mode for writing only the LSBs, only the MSBs or both halves of a
+    /// frequency register. If nothing needs to be written, the mode is kept.
+    pub(crate) fn with_halves_mode(self, lsb: bool, msb: bool) -> Self {
+        match (lsb, msb) {
+            (true, true) => self.with_high(BitFlags::B28),
+            (true, false) => self.with_low(BitFlags::B28).with_low(BitFlags::HLB),
+            (false, true) => self.with_low(BitFlags::B28).with_high(BitFlags::HLB),
+            (false, false) => self,
+        }
+    }
+
+    pub(crate) fn is_high(self, mask: u16) -> bool {
+        self.bits & mask != 0
+    }
+
+    pub(crate) fn powered_down(self) -> PoweredDown {
+        match (
+            self.is_high(BitFlags::SLEEP_MCLK),
+            self.is_high(BitFlags::SLEEP_DAC),
+        ) {
+            (false, false) => PoweredDown::Nothing,
+            (false, true) => PoweredDown::Dac,
+            (true, false) => PoweredDown::InternalClock,
+            (true, true) => PoweredDown::DacAndInternalClock,
+        }
+    }
+
+    pub(crate) fn with_powered_down(self, config: PoweredDown) -> Self {
+        match config {
+            PoweredDown::Nothing => self
+                .with_low(BitFlags::SLEEP_MCLK)
+                .with_low(BitFlags::SLEEP_DAC),
+            PoweredDown::Dac => self
+                .with_low(BitFlags::SLEEP_MCLK)
+                .with_high(BitFlags::SLEEP_DAC),
+            PoweredDown::InternalClock => self
+                .with_high(BitFlags::SLEEP_MCLK)
+                .with_low(BitFlags::SLEEP_DAC),
+            PoweredDown::DacAndInternalClock => self
+                .with_high(BitFlags::SLEEP_MCLK)
+                .with_high(BitFlags::SLEEP_DAC),
+        }
+    }
 }
 
 impl BitFlags {
@@ -36,67 +111,564 @@ impl BitFlags {
     pub(crate) const MODE: u16 = 1 << 1;
 }
 
-impl<DEV, CS, IC> Ad983x<DEV, CS, IC> {
-    pub(crate) fn create(spi: DEV, cs: CS) -> Self {
-        Ad983x {
-            spi,
-            cs,
+const MAX_TRANSFER_WORDS: usize = 8;
+pub(crate) const MAX_TRANSFER_BYTES: usize = 2 * MAX_TRANSFER_WORDS;
+
+/// Words to be written to the device for one logical update together with
+/// the control configuration the device has afterwards.
+///
+/// This contains all the register encoding so that it is shared between
+/// the blocking and the asynchronous drivers.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Transfer {
+    words: [u16; MAX_TRANSFER_WORDS],
+    len: usize,
+    pub(crate) control: Config,
+}
+
+impl Transfer {
+    fn new(control: Config) -> Self {
+        Transfer {
+            words: [0; MAX_TRANSFER_WORDS],
+            len: 0,
+            control,
+        }
+    }
+
+    fn push(&mut self, word: u16) {
+        self.words[self.len] = word;
+        self.len += 1;
+    }
+
+    /// Words to write in order
+    pub(crate) fn words(&self) -> &[u16] {
+        &self.words[..self.len]
+    }
+
+    /// Encode all words big-endian into a buffer so that they can be written
+    /// to the device in a single SPI transaction
+    pub(crate) fn bytes<'a>(&self, buffer: &'a mut [u8; MAX_TRANSFER_BYTES]) -> &'a [u8] {
+        for (chunk, word) in buffer.chunks_exact_mut(2).zip(self.words()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        &buffer[..2 * self.len]
+    }
+
+    /// Write the control register unconditionally
+    pub(crate) fn control(control: Config) -> Self {
+        let mut transfer = Transfer::new(control);
+        transfer.push_control(control);
+        transfer
+    }
+
+    fn push_control(&mut self, control: Config) {
+        self.push(control.bits & 0b0011_1111_1111_1111);
+        self.control = control;
+    }
+
+    /// Write the control register only if it differs from the current one
+    fn control_if_different(current: Config, control: Config) -> Self {
+        if control != current {
+            Transfer::control(control)
+        } else {
+            Transfer::new(control)
+        }
+    }
+
+    fn check_value_fits<T, SpiE, PinE>(value: T, bit_count: T) -> Result<(), Error<SpiE, PinE>>
+    where
+        T: From<u8> + PartialOrd + core::ops::Shl<Output = T>,
+    {
+        if value >= (T::from(1) << bit_count) {
+            Err(Error::InvalidArgument)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn freq_register_bits(register: FrequencyRegister) -> u16 {
+        match register {
+            FrequencyRegister::F0 => BitFlags::D14,
+            FrequencyRegister::F1 => BitFlags::D15,
+        }
+    }
+
+    /// Write a 28-bit frequency word, enabling the 28-bit mode if necessary
+    pub(crate) fn frequency<SpiE, PinE>(
+        current: Config,
+        register: FrequencyRegister,
+        value: u32,
+    ) -> Result<Self, Error<SpiE, PinE>> {
+        Self::check_value_fits(value, 28)?;
+        let control = current.with_high(BitFlags::B28);
+        let mut transfer = Self::control_if_different(current, control);
+        transfer.push_frequency(register, value);
+        Ok(transfer)
+    }
+
+    /// Push a 28-bit frequency word as LSBs and MSBs (needs the 28-bit mode)
+    fn push_frequency(&mut self, register: FrequencyRegister, value: u32) {
+        let reg = Self::freq_register_bits(register);
+        self.push(reg | (value & 0x3FFF) as u16);
+        self.push(reg | ((value >> 14) & 0x3FFF) as u16);
+    }
+
+    /// Write the 14 MSBs of a frequency register, disabling the 28-bit mode if necessary
+    pub(crate) fn frequency_msb<SpiE, PinE>(
+        current: Config,
+        register: FrequencyRegister,
+        value: u16,
+    ) -> Result<Self, Error<SpiE, PinE>> {
+        Self::check_value_fits(value, 14)?;
+        let control = current.with_low(BitFlags::B28).with_high(BitFlags::HLB);
+        let mut transfer = Self::control_if_different(current, control);
+        transfer.push(Self::freq_register_bits(register) | value);
+        Ok(transfer)
+    }
+
+    /// Write the 14 LSBs of a frequency register, disabling the 28-bit mode if necessary
+    pub(crate) fn frequency_lsb<SpiE, PinE>(
+        current: Config,
+        register: FrequencyRegister,
+        value: u16,
+    ) -> Result<Self, Error<SpiE, PinE>> {
+        Self::check_value_fits(value, 14)?;
+        let control = current.with_low(BitFlags::B28).with_low(BitFlags::HLB);
+        let mut transfer = Self::control_if_different(current, control);
+        transfer.push(Self::freq_register_bits(register) | value);
+        Ok(transfer)
+    }
+
+    /// Write a 28-bit frequency word into the frequency register not currently
+    /// selected and then select it
+    pub(crate) fn retune<SpiE, PinE>(
+        current: Config,
+        value: u32,
+    ) -> Result<Self, Error<SpiE, PinE>> {
+        let register = match current.selected_frequency() {
+            FrequencyRegister::F0 => FrequencyRegister::F1,
+            FrequencyRegister::F1 => FrequencyRegister::F0,
+        };
+        let mut transfer = Self::frequency(current, register, value)?;
+        transfer.push_control(transfer.control.with_frequency_selected(register));
+        Ok(transfer)
+    }
+
+    /// Like `retune()`, but only write the 14-bit halves that differ from what
+    /// the register not currently selected holds
+    ///
+    /// The control word selecting the register already sets up the mode for
+    /// the halves `next` will need in the other register, so that consecutive
+    /// steps do not need an additional control write.
+    pub(crate) fn retune_changed<SpiE, PinE>(
+        registers: &Registers,
+        value: u32,
+        next: Option<u32>,
+    ) -> Result<Self, Error<SpiE, PinE>> {
+        Self::check_value_fits(value, 28)?;
+        let current = registers.control;
+        let selected = current.selected_frequency();
+        let register = match selected {
+            FrequencyRegister::F0 => FrequencyRegister::F1,
+            FrequencyRegister::F1 => FrequencyRegister::F0,
+        };
+        let (lsb, msb) = registers.changed_halves(register, value);
+        let control = current.with_halves_mode(lsb.is_some(), msb.is_some());
+        let mut transfer = Self::control_if_different(current, control);
+        let reg = Self::freq_register_bits(register);
+        for half in [lsb, msb].into_iter().flatten() {
+            transfer.push(reg | half);
+        }
+        let mut control = control.with_frequency_selected(register);
+        if let Some(next) = next {
+            let (lsb, msb) = registers.changed_halves(selected, next);
+            control = control.with_halves_mode(lsb.is_some(), msb.is_some());
+        }
+        transfer.push_control(control);
+        Ok(transfer)
+    }
+
+    /// Write a 12-bit phase register
+    pub(crate) fn phase<SpiE, PinE>(
+        current: Config,
+        register: PhaseRegister,
+        value: u16,
+    ) -> Result<Self, Error<SpiE, PinE>> {
+        Self::check_value_fits(value, 12)?;
+        let mut transfer = Transfer::new(current);
+        transfer.push_phase(register, value);
+        Ok(transfer)
+    }
+
+    fn push_phase(&mut self, register: PhaseRegister, value: u16) {
+        let value = value | BitFlags::D14 | BitFlags::D15;
+        let value = match register {
+            PhaseRegister::P0 => value,
+            PhaseRegister::P1 => value | BitFlags::D13,
+        };
+        self.push(value);
+    }
+
+    /// Write a 12-bit phase register and then select it
+    pub(crate) fn phase_selected<SpiE, PinE>(
+        current: Config,
+        register: PhaseRegister,
+        value: u16,
+    ) -> Result<Self, Error<SpiE, PinE>> {
+        let mut transfer = Self::phase(current, register, value)?;
+        transfer.push_control(current.with_phase_selected(register));
+        Ok(transfer)
+    }
+
+    /// Bring the device to a configuration, with the target control register
+    ///
+    /// If any frequency or phase register content is unknown, the device is
+    /// held in reset while all registers are loaded and then released, as
+    /// recommended in the AN-1070 application note. Otherwise only the
+    /// registers which differ from the current ones are written.
+    pub(crate) fn apply<SpiE, PinE>(
+        current: &Registers,
+        config: &DeviceConfig,
+        control: Config,
+    ) -> Result<Self, Error<SpiE, PinE>> {
+        let frequencies = [
+            (FrequencyRegister::F0, config.frequency0),
+            (FrequencyRegister::F1, config.frequency1),
+        ];
+        let phases = [
+            (PhaseRegister::P0, config.phase0),
+            (PhaseRegister::P1, config.phase1),
+        ];
+        for (_, value) in frequencies {
+            Self::check_value_fits(value, 28)?;
+        }
+        for (_, value) in phases {
+            Self::check_value_fits(value, 12)?;
+        }
+        let initial = !current.is_loaded();
+        let frequencies_changed = frequencies
+            .iter()
+            .any(|(register, value)| current.frequency(*register) != Some(*value));
+
+        let mut transfer = Transfer::new(current.control);
+        if initial {
+            transfer.push_control(current.control.with_high(BitFlags::B28 | BitFlags::RESET));
+        } else if frequencies_changed && !current.control.is_high(BitFlags::B28) {
+            transfer.push_control(current.control.with_high(BitFlags::B28));
+        }
+        for (register, value) in frequencies {
+            if initial || current.frequency(register) != Some(value) {
+                transfer.push_frequency(register, value);
+            }
+        }
+        for (register, value) in phases {
+            if initial || current.phase(register) != Some(value) {
+                transfer.push_phase(register, value);
+            }
+        }
+        if transfer.control != control {
+            transfer.push_control(control);
+        }
+        Ok(transfer)
+    }
+}
+
+/// Software copy of the write-only device registers
+///
+/// This is updated by decoding the words written to the device, the same way
+/// the device does. Frequency and phase register contents are unknown until
+/// written, since they are not cleared by a reset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Registers {
+    pub(crate) control: Config,
+    frequency_lsb: [Option<u16>; 2],
+    frequency_msb: [Option<u16>; 2],
+    phase: [Option<u16>; 2],
+    /// 14 LSBs written in 28-bit mode, which take effect with the MSBs
+    pending_lsb: Option<(usize, u16)>,
+}
+
+impl Registers {
+    pub(crate) fn new() -> Self {
+        Registers {
             control: Config {
                 bits: BitFlags::RESET,
             },
+            frequency_lsb: [None; 2],
+            frequency_msb: [None; 2],
+            phase: [None; 2],
+            pending_lsb: None,
+        }
+    }
+
+    fn frequency_index(register: FrequencyRegister) -> usize {
+        match register {
+            FrequencyRegister::F0 => 0,
+            FrequencyRegister::F1 => 1,
+        }
+    }
+
+    pub(crate) fn frequency(&self, register: FrequencyRegister) -> Option<u32> {
+        let index = Self::frequency_index(register);
+        let lsb = self.frequency_lsb[index]?;
+        let msb = self.frequency_msb[index]?;
+        Some((u32::from(msb) << 14) | u32::from(lsb))
+    }
+
+    pub(crate) fn frequency_msb(&self, register: FrequencyRegister) -> Option<u16> {
+        self.frequency_msb[Self::frequency_index(register)]
+    }
+
+    pub(crate) fn frequency_lsb(&self, register: FrequencyRegister) -> Option<u16> {
+        self.frequency_lsb[Self::frequency_index(register)]
+    }
+
+    /// 14-bit halves of a frequency word which differ from the register content
+    pub(crate) fn changed_halves(
+        &self,
+        register: FrequencyRegister,
+        value: u32,
+    ) -> (Option<u16>, Option<u16>) {
+        let lsb = (value & 0x3FFF) as u16;
+        let msb = ((value >> 14) & 0x3FFF) as u16;
+        (
+            Some(lsb).filter(|lsb| self.frequency_lsb(register) != Some(*lsb)),
+            Some(msb).filter(|msb| self.frequency_msb(register) != Some(*msb)),
+        )
+    }
+
+    pub(crate) fn phase(&self, register: PhaseRegister) -> Option<u16> {
+        match register {
+            PhaseRegister::P0 => self.phase[0],
+            PhaseRegister::P1 => self.phase[1],
+        }
+    }
+
+    /// Whether the content of all frequency and phase registers is known
+    pub(crate) fn is_loaded(&self) -> bool {
+        self.frequency_lsb
+            .iter()
+            .chain(&self.frequency_msb)
+            .chain(&self.phase)
+            .all(Option::is_some)
+    }
+
+    /// Update the registers with a word the device has received
+    fn record(&mut self, word: u16) {
+        let data = word & 0x3FFF;
+        match word >> 14 {
+            0b00 => self.control = Config { bits: data },
+            0b11 => {
+                let index = usize::from(word & BitFlags::D13 != 0);
+                self.phase[index] = Some(word & 0xFFF);
+            }
+            register => {
+                let index = usize::from(register == 0b10);
+                if self.control.is_high(BitFlags::B28) {
+                    match self.pending_lsb.take() {
+                        Some((pending, lsb)) if pending == index => {
+                            self.frequency_lsb[index] = Some(lsb);
+                            self.frequency_msb[index] = Some(data);
+                        }
+                        _ => self.pending_lsb = Some((index, data)),
+                    }
+                } else if self.control.is_high(BitFlags::HLB) {
+                    self.frequency_msb[index] = Some(data);
+                } else {
+                    self.frequency_lsb[index] = Some(data);
+                }
+            }
+        }
+    }
+
+    /// Decode a word the way the device interprets it in the current state
+    #[cfg(any(feature = "defmt", feature = "log"))]
+    fn decode(&self, word: u16) -> trace::Word {
+        let data = word & 0x3FFF;
+        match word >> 14 {
+            0b00 => trace::Word::Control(data),
+            0b11 => trace::Word::Phase {
+                index: usize::from(word & BitFlags::D13 != 0),
+                data: word & 0xFFF,
+            },
+            register => {
+                let index = usize::from(register == 0b10);
+                let msb = if self.control.is_high(BitFlags::B28) {
+                    matches!(self.pending_lsb, Some((pending, _)) if pending == index)
+                } else {
+                    self.control.is_high(BitFlags::HLB)
+                };
+                trace::Word::Frequency { index, msb, data }
+            }
+        }
+    }
+
+    /// Emit a decoded trace line for each word of a transfer about to be written
+    #[cfg(any(feature = "defmt", feature = "log"))]
+    pub(crate) fn trace(&self, words: &[u16]) {
+        let mut registers = *self;
+        for word in words {
+            trace::word(registers.decode(*word));
+            registers.record(*word);
+        }
+    }
+
+    /// Update the registers after writing the words of a transfer
+    ///
+    /// If the write failed, it is unknown which of the words the device
+    /// received, so everything they may have changed is forgotten.
+    pub(crate) fn update(&mut self, words: &[u16], written: bool) {
+        for word in words {
+            if written {
+                self.record(*word);
+            } else {
+                self.invalidate(*word);
+            }
+        }
+    }
+
+    /// Forget what a word may have changed after a failed write
+    ///
+    /// The control register keeps its previous value since there is no way
+    /// to tell whether the device received the new one.
+    fn invalidate(&mut self, word: u16) {
+        match word >> 14 {
+            0b00 => (),
+            0b11 => self.phase[usize::from(word & BitFlags::D13 != 0)] = None,
+            register => {
+                let index = usize::from(register == 0b10);
+                self.frequency_lsb[index] = None;
+                self.frequency_msb[index] = None;
+                self.pending_lsb = None;
+            }
+        }
+    }
+}
+
+impl<DEV, IC, S> Ad983x<DEV, IC, S> {
+    pub(crate) fn create(spi: DEV) -> Self {
+        Ad983x {
+            spi,
+            registers: Registers::new(),
+            mclk_hz: DEFAULT_MCLK_HZ,
+            mclk_correction_ppm: 0.0,
             _ic: PhantomData,
+            _state: PhantomData,
         }
     }
 
-    /// Destroy driver instance, return SPI bus instance and CS output pin.
-    pub fn destroy(self) -> (DEV, CS) {
-        (self.spi, self.cs)
+    pub(crate) fn into_state<T>(self) -> Ad983x<DEV, IC, T> {
+        Ad983x {
+            spi: self.spi,
+            registers: self.registers,
+            mclk_hz: self.mclk_hz,
+            mclk_correction_ppm: self.mclk_correction_ppm,
+            _ic: PhantomData,
+            _state: PhantomData,
+        }
+    }
+
+    /// Destroy driver instance, return SPI device (or interface) instance.
+    pub fn destroy(self) -> DEV {
+        self.spi
+    }
+
+    /// Get the 28-bit word last written to a frequency register
+    ///
+    /// The device registers are write-only so this is the value tracked by
+    /// the driver. Returns `None` if any of the two 14-bit halves has not
+    /// been written yet (or a write to it failed).
+    pub fn frequency(&self, register: FrequencyRegister) -> Option<u32> {
+        self.registers.frequency(register)
+    }
+
+    /// Get the 14 MSBs last written to a frequency register
+    pub fn frequency_msb(&self, register: FrequencyRegister) -> Option<u16> {
+        self.registers.frequency_msb(register)
+    }
+
+    /// Get the 14 LSBs last written to a frequency register
+    pub fn frequency_lsb(&self, register: FrequencyRegister) -> Option<u16> {
+        self.registers.frequency_lsb(register)
+    }
+
+    /// Get the 12-bit value last written to a phase register
+    ///
+    /// Returns `None` if the register has not been written yet.
+    pub fn phase(&self, register: PhaseRegister) -> Option<u16> {
+        self.registers.phase(register)
+    }
+
+    /// Get the frequency register selected in software
+    pub fn selected_frequency(&self) -> FrequencyRegister {
+        self.registers.control.selected_frequency()
+    }
+
+    /// Get the phase register selected in software
+    pub fn selected_phase(&self) -> PhaseRegister {
+        self.registers.control.selected_phase()
+    }
+
+    /// Get the powered-down configuration selected in software
+    pub fn powered_down(&self) -> PoweredDown {
+        self.registers.control.powered_down()
+    }
+
+    /// Whether the device is held in reset (disabled)
+    pub fn is_reset(&self) -> bool {
+        self.registers.control.is_high(BitFlags::RESET)
+    }
+
+    pub(crate) fn hardware_control(&self) -> bool {
+        self.registers.control.is_high(BitFlags::PIN_SW)
+    }
+
+    /// Update the software copy of the control register after a RESET pin
+    /// not owned by the driver was changed
+    pub(crate) fn record_reset_pin(&mut self, reset: bool) {
+        self.registers.control = if reset {
+            self.registers.control.with_high(BitFlags::RESET)
+        } else {
+            self.registers.control.with_low(BitFlags::RESET)
+        };
     }
 }
 
-impl<DEV, CS, IC, E> Ad983x<DEV, CS, IC>
+impl<DEV, IC, SpiE, PinE> Ad983x<DEV, IC, state::Unchecked>
 where
-    DEV: SpiDevice<Error = E>,
-    CS: OutputPin,
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
 {
     /// Resets the internal registers and leaves the device disabled.
     ///
-    /// Note that this is ignored in AD9834/AD9838 devices if hardware pin
-    /// control source is selected.
-    pub fn reset(&mut self) -> Result<(), Error<E>> {
+    /// On AD9834/AD9838 devices with the hardware pin control source selected
+    /// this drives the RESET pin instead.
+    pub fn reset(&mut self) -> Result<(), Error<SpiE, PinE>> {
         self.disable()
     }
 
     /// Disable the device (enable reset)
     ///
     /// This resets the internal registers.
-    /// Note that this is ignored in AD9834/AD9838 devices if hardware pin
-    /// control source is selected.
-    pub fn disable(&mut self) -> Result<(), Error<E>> {
-        let control = self.control.with_high(BitFlags::RESET);
-        self.write_control(control)
+    /// On AD9834/AD9838 devices with the hardware pin control source selected
+    /// this drives the RESET pin instead.
+    pub fn disable(&mut self) -> Result<(), Error<SpiE, PinE>> {
+        self.write_reset(true)
     }
 
     /// Enable the device (disable reset)
     ///
-    /// Note that this is ignored in AD9834/AD9838 devices if hardware pin
-    /// control source is selected.
-    pub fn enable(&mut self) -> Result<(), Error<E>> {
-        let control = self.control.with_low(BitFlags::RESET);
-        self.write_control(control)
-    }
-
-    fn check_value_fits<T>(value: T, bit_count: T) -> Result<(), Error<E>>
-    where
-        T: From<u8> + PartialOrd + core::ops::Shl<Output = T>,
-    {
-        if value >= (T::from(1) << bit_count) {
-            Err(Error::InvalidArgument)
-        } else {
-            Ok(())
-        }
+    /// On AD9834/AD9838 devices with the hardware pin control source selected
+    /// this drives the RESET pin instead.
+    pub fn enable(&mut self) -> Result<(), Error<SpiE, PinE>> {
+        self.write_reset(false)
     }
+}
 
+impl<DEV, IC, S, SpiE, PinE> Ad983x<DEV, IC, S>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+    S: state::Configurable,
+{
     /// Set the frequency as a 28-bit word
     ///
     /// This will change the mode to 28-bit if it is not used.
@@ -105,19 +677,9 @@ where
         &mut self,
         register: FrequencyRegister,
         value: u32,
-    ) -> Result<(), Error<E>> {
-        Self::check_value_fits(value, 28)?;
-        let control = self.control.with_high(BitFlags::B28);
-        self.write_control_if_different(control)?;
-        let reg = Self::get_freq_register_bits(register);
-        self.write_data(reg, value)
-    }
-
-    fn get_freq_register_bits(register: FrequencyRegister) -> u16 {
-        match register {
-            FrequencyRegister::F0 => BitFlags::D14,
-            FrequencyRegister::F1 => BitFlags::D15,
-        }
+    ) -> Result<(), Error<SpiE, PinE>> {
+        let transfer = Transfer::frequency(self.registers.control, register, value)?;
+        self.write_transfer(&transfer)
     }
 
     /// Set the frequency 14-bit MSBs
@@ -128,15 +690,9 @@ where
         &mut self,
         register: FrequencyRegister,
         value: u16,
-    ) -> Result<(), Error<E>> {
-        Self::check_value_fits(value, 14)?;
-        let control = self
-            .control
-            .with_low(BitFlags::B28)
-            .with_high(BitFlags::HLB);
-        self.write_control_if_different(control)?;
-        let reg = Self::get_freq_register_bits(register);
-        self.write(DataFormat::U16(reg | value))
+    ) -> Result<(), Error<SpiE, PinE>> {
+        let transfer = Transfer::frequency_msb(self.registers.control, register, value)?;
+        self.write_transfer(&transfer)
     }
 
     /// Set the frequency 14-bit LSBs
@@ -147,122 +703,213 @@ where
         &mut self,
         register: FrequencyRegister,
         value: u16,
-    ) -> Result<(), Error<E>> {
-        Self::check_value_fits(value, 14)?;
-        let control = self.control.with_low(BitFlags::B28).with_low(BitFlags::HLB);
-        self.write_control_if_different(control)?;
-        let reg = Self::get_freq_register_bits(register);
-        self.write(DataFormat::U16(reg | value))
+    ) -> Result<(), Error<SpiE, PinE>> {
+        let transfer = Transfer::frequency_lsb(self.registers.control, register, value)?;
+        self.write_transfer(&transfer)
+    }
+
+    /// Change the output frequency glitch-free to a 28-bit word
+    ///
+    /// The new value is written into the frequency register not currently
+    /// selected, which is then selected with a single control write. This way
+    /// the output never runs on a half-written frequency register and the
+    /// change is phase-continuous. Returns the frequency register now in use.
+    ///
+    /// This will change the mode to 28-bit if it is not used.
+    /// On AD9834/AD9838 devices with the hardware pin control source selected
+    /// the register is selected through the FSELECT pin.
+    /// Returns `Error::InvalidArgument` if providing a value that does not fit in 28 bits.
+    pub fn retune(&mut self, value: u32) -> Result<FrequencyRegister, Error<SpiE, PinE>> {
+        if self.hardware_control() {
+            let register = match self.registers.control.selected_frequency() {
+                FrequencyRegister::F0 => FrequencyRegister::F1,
+                FrequencyRegister::F1 => FrequencyRegister::F0,
+            };
+            self.set_frequency(register, value)?;
+            self.select_frequency(register)?;
+        } else {
+            let transfer = Transfer::retune(self.registers.control, value)?;
+            self.write_transfer(&transfer)?;
+        }
+        Ok(self.registers.control.selected_frequency())
+    }
+
+    /// Glitch-free frequency change writing only the 14-bit halves that
+    /// changed, preparing the mode for the following value
+    pub(crate) fn retune_changed(
+        &mut self,
+        value: u32,
+        next: Option<u32>,
+    ) -> Result<(), Error<SpiE, PinE>> {
+        if self.hardware_control() {
+            return self.retune(value).map(|_| ());
+        }
+        let transfer = Transfer::retune_changed(&self.registers, value, next)?;
+        self.write_transfer(&transfer)
     }
 
     /// Select the frequency register that is used
     ///
-    /// Note: this can be overriden through the FSELECT pin in AD9834/AD9838
-    /// devices if hardware pin control source is selected.
-    pub fn select_frequency(&mut self, register: FrequencyRegister) -> Result<(), Error<E>> {
-        let control = match register {
-            FrequencyRegister::F0 => self.control.with_low(BitFlags::FSELECT),
-            FrequencyRegister::F1 => self.control.with_high(BitFlags::FSELECT),
-        };
-        self.write_control(control)
+    /// On AD9834/AD9838 devices with the hardware pin control source selected
+    /// this drives the FSELECT pin instead. Returns `Error::PinNotAssigned` if
+    /// the driver does not own it.
+    pub fn select_frequency(
+        &mut self,
+        register: FrequencyRegister,
+    ) -> Result<(), Error<SpiE, PinE>> {
+        let control = self.registers.control.with_frequency_selected(register);
+        let high = register == FrequencyRegister::F1;
+        self.write_function(control, ControlPin::FSelect, high)
     }
 
     /// Set a phase register (12-bit value)
     ///
     /// Returns `Error::InvalidArgument` if providing a value that does not fit in 12 bits.
-    pub fn set_phase(&mut self, register: PhaseRegister, value: u16) -> Result<(), Error<E>> {
-        Self::check_value_fits(value, 12)?;
-        let value = value | BitFlags::D14 | BitFlags::D15;
-        let value = match register {
-            PhaseRegister::P0 => value,
-            PhaseRegister::P1 => value | BitFlags::D13,
-        };
-        self.write(DataFormat::U16(value))
+    pub fn set_phase(
+        &mut self,
+        register: PhaseRegister,
+        value: u16,
+    ) -> Result<(), Error<SpiE, PinE>> {
+        let transfer = Transfer::phase(self.registers.control, register, value)?;
+        self.write_transfer(&transfer)
     }
 
     /// Select the phase register that is used.
     ///
-    /// Note: this can be overriden through the PSELECT pin in AD9834/AD9838
-    /// devices if hardware pin control source is selected.
-    pub fn select_phase(&mut self, register: PhaseRegister) -> Result<(), Error<E>> {
-        let control = match register {
-            PhaseRegister::P0 => self.control.with_low(BitFlags::PSELECT),
-            PhaseRegister::P1 => self.control.with_high(BitFlags::PSELECT),
+    /// On AD9834/AD9838 devices with the hardware pin control source selected
+    /// this drives the PSELECT pin instead. Returns `Error::PinNotAssigned` if
+    /// the driver does not own it.
+    pub fn select_phase(&mut self, register: PhaseRegister) -> Result<(), Error<SpiE, PinE>> {
+        let control = self.registers.control.with_phase_selected(register);
+        let high = register == PhaseRegister::P1;
+        self.write_function(control, ControlPin::PSelect, high)
+    }
+
+    /// Set a phase register and select it in a single SPI transaction
+    pub(crate) fn set_and_select_phase(
+        &mut self,
+        register: PhaseRegister,
+        value: u16,
+    ) -> Result<(), Error<SpiE, PinE>> {
+        if self.hardware_control() {
+            self.set_phase(register, value)?;
+            return self.select_phase(register);
+        }
+        let transfer = Transfer::phase_selected(self.registers.control, register, value)?;
+        self.write_transfer(&transfer)
+    }
+
+    /// Output a phase register value, loading it into the phase register not
+    /// in use and selecting that one. Nothing is written if the selected
+    /// register already holds the value and the other register is only
+    /// selected if it does.
+    pub(crate) fn switch_phase(&mut self, value: u16) -> Result<(), Error<SpiE, PinE>> {
+        let active = self.registers.control.selected_phase();
+        if self.registers.phase(active) == Some(value) {
+            return Ok(());
+        }
+        let inactive = match active {
+            PhaseRegister::P0 => PhaseRegister::P1,
+            PhaseRegister::P1 => PhaseRegister::P0,
         };
-        self.write_control(control)
+        if self.registers.phase(inactive) == Some(value) {
+            return self.select_phase(inactive);
+        }
+        self.set_and_select_phase(inactive, value)
     }
 
     /// Set device parts powered-down state.
     ///
-    /// Note: This can be overriden through the SLEEP pin
-    /// in AD9834/AD9838 devices if hardware pin control source is selected.
-    pub fn set_powered_down(&mut self, config: PoweredDown) -> Result<(), Error<E>> {
-        let control = match config {
-            PoweredDown::Nothing => self
-                .control
-                .with_low(BitFlags::SLEEP_MCLK)
-                .with_low(BitFlags::SLEEP_DAC),
-            PoweredDown::Dac => self
-                .control
-                .with_low(BitFlags::SLEEP_MCLK)
-                .with_high(BitFlags::SLEEP_DAC),
-            PoweredDown::InternalClock => self
-                .control
-                .with_high(BitFlags::SLEEP_MCLK)
-                .with_low(BitFlags::SLEEP_DAC),
-            PoweredDown::DacAndInternalClock => self
-                .control
-                .with_high(BitFlags::SLEEP_MCLK)
-                .with_high(BitFlags::SLEEP_DAC),
-        };
-        self.write_control(control)
+    /// On AD9834/AD9838 devices with the hardware pin control source selected
+    /// this drives the SLEEP pin instead, which can only power down the DAC.
+    /// Returns `Error::InvalidArgument` if the internal clock should be
+    /// powered down in this case.
+    pub fn set_powered_down(&mut self, config: PoweredDown) -> Result<(), Error<SpiE, PinE>> {
+        let control = self.registers.control.with_powered_down(config);
+        if self.hardware_control() && control.is_high(BitFlags::SLEEP_MCLK) {
+            return Err(Error::InvalidArgument);
+        }
+        let high = control.is_high(BitFlags::SLEEP_DAC);
+        self.write_function(control, ControlPin::Sleep, high)
     }
+}
 
-    pub(crate) fn write_control_if_different(&mut self, control: Config) -> Result<(), Error<E>> {
-        if control != self.control {
-            self.write_control(control)
+impl<DEV, IC, S, SpiE, PinE> Ad983x<DEV, IC, S>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+{
+    pub(crate) fn write_control(&mut self, control: Config) -> Result<(), Error<SpiE, PinE>> {
+        self.write_transfer(&Transfer::control(control))
+    }
+
+    pub(crate) fn write_reset(&mut self, reset: bool) -> Result<(), Error<SpiE, PinE>> {
+        let control = if reset {
+            self.registers.control.with_high(BitFlags::RESET)
         } else {
+            self.registers.control.with_low(BitFlags::RESET)
+        };
+        self.write_function(control, ControlPin::Reset, reset)
+    }
+
+    /// Change a function which is controlled through a pin while the
+    /// hardware pin control source is selected on AD9834/AD9838 devices
+    ///
+    /// In that case the pin is set and the control register is only updated
+    /// in software, so that it reflects the state of the device and a switch
+    /// back to software control keeps it.
+    fn write_function(
+        &mut self,
+        control: Config,
+        pin: ControlPin,
+        high: bool,
+    ) -> Result<(), Error<SpiE, PinE>> {
+        if self.hardware_control() {
+            self.spi.set_control_pin(pin, high)?;
+            self.registers.control = control;
             Ok(())
+        } else {
+            self.write_control(control)
         }
     }
 
-    pub(crate) fn write_control(&mut self, control: Config) -> Result<(), Error<E>> {
-        let payload = control.bits & 0b0011_1111_1111_1111;
-        self.write(DataFormat::U16(payload))?;
-        self.control = control;
+    /// Set the assigned function control pins to the state of a control register
+    ///
+    /// The RESET pin is set last, so that a device leaving reset starts with
+    /// the other functions already selected.
+    pub(crate) fn sync_control_pins(&mut self, control: Config) -> Result<(), Error<SpiE, PinE>> {
+        let pins = [
+            (ControlPin::FSelect, BitFlags::FSELECT),
+            (ControlPin::PSelect, BitFlags::PSELECT),
+            (ControlPin::Sleep, BitFlags::SLEEP_DAC),
+            (ControlPin::Reset, BitFlags::RESET),
+        ];
+        for (pin, mask) in pins {
+            self.set_assigned_control_pin(pin, control.is_high(mask))?;
+        }
         Ok(())
     }
 
-    pub(crate) fn write(&mut self, payload: DataFormat) -> Result<(), Error<E>> {
-        self.cs.set_low();
-        let error = match payload {
-            DataFormat::U32(data) => {
-                let d2s = data.to_be_bytes();
-                self.spi.write(&d2s).map_err(Error::Spi)
-            }
-            DataFormat::U16(data) => {
-                let d2s = data.to_be_bytes();
-                self.spi.write(&d2s).map_err(Error::Spi)
-            }
-            DataFormat::U8(data) => {
-                let d2s = data.to_be_bytes();
-                self.spi.write(&d2s).map_err(Error::Spi)
-            }
-        };
-        self.cs.set_high();
-        error
+    /// Set a function control pin, if it is assigned
+    pub(crate) fn set_assigned_control_pin(
+        &mut self,
+        pin: ControlPin,
+        high: bool,
+    ) -> Result<(), Error<SpiE, PinE>> {
+        match self.spi.set_control_pin(pin, high) {
+            Ok(()) | Err(Error::PinNotAssigned(_)) => Ok(()),
+            Err(e) => Err(e),
+        }
     }
 
-    pub(crate) fn write_data(&mut self, reg: u16, payload: u32) -> Result<(), Error<E>> {
-        self.cs.set_low();
-        let mut msb = ((payload & 0x0FFFC000) >> 14) as u16;
-        msb = reg | msb;
-
-        let mut lsb = (payload & 0x00003FFF) as u16;
-        lsb = reg | lsb;
-        self.spi.write(&lsb.to_be_bytes()).map_err(Error::Spi)?;
-        self.spi.write(&msb.to_be_bytes()).map_err(Error::Spi)?;
-        self.cs.set_high();
-        Ok(())
+    pub(crate) fn write_transfer(&mut self, transfer: &Transfer) -> Result<(), Error<SpiE, PinE>> {
+        if transfer.words().is_empty() {
+            return Ok(());
+        }
+        #[cfg(any(feature = "defmt", feature = "log"))]
+        self.registers.trace(transfer.words());
+        let mut buffer = [0; MAX_TRANSFER_BYTES];
+        let result = self.spi.write_data(transfer.bytes(&mut buffer));
+        self.registers.update(transfer.words(), result.is_ok());
+        result
     }
 }
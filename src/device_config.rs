@@ -0,0 +1,155 @@
+use crate::{
+    common::Transfer, interface::WriteData, marker, state, Ad983x, BitFlags, Config, ControlPin,
+    ControlSource, DeviceConfig, Error, FrequencyRegister, OutputWaveform, PhaseRegister,
+    PoweredDown, SignBitOutput,
+};
+
+impl Default for DeviceConfig {
+    fn default() -> Self {
+        DeviceConfig {
+            frequency0: 0,
+            frequency1: 0,
+            phase0: 0,
+            phase1: 0,
+            selected_frequency: FrequencyRegister::F0,
+            selected_phase: PhaseRegister::P0,
+            output_waveform: OutputWaveform::Sinusoidal,
+            sign_bit_output: SignBitOutput::Disabled,
+            powered_down: PoweredDown::Nothing,
+            control_source: ControlSource::Software,
+        }
+    }
+}
+
+impl DeviceConfig {
+    /// Control register of a running device with the common settings
+    fn control(&self) -> Config {
+        Config::default()
+            .with_high(BitFlags::B28)
+            .with_frequency_selected(self.selected_frequency)
+            .with_phase_selected(self.selected_phase)
+            .with_powered_down(self.powered_down)
+    }
+
+    pub(crate) fn control_ad9833<SpiE, PinE>(&self) -> Result<Config, Error<SpiE, PinE>> {
+        if self.sign_bit_output != SignBitOutput::Disabled
+            || self.control_source != ControlSource::Software
+        {
+            return Err(Error::InvalidArgument);
+        }
+        Ok(self
+            .control()
+            .with_output_waveform_ad9833(self.output_waveform))
+    }
+
+    pub(crate) fn control_ad9834<SpiE, PinE>(&self) -> Result<Config, Error<SpiE, PinE>> {
+        // The sign bit output needs the DAC MSB, which is not available in triangle mode.
+        if self.output_waveform == OutputWaveform::Triangle
+            && self.sign_bit_output != SignBitOutput::Disabled
+        {
+            return Err(Error::InvalidArgument);
+        }
+        // The SLEEP pin can only power down the DAC.
+        if self.control_source == ControlSource::HardwarePins
+            && matches!(
+                self.powered_down,
+                PoweredDown::InternalClock | PoweredDown::DacAndInternalClock
+            )
+        {
+            return Err(Error::InvalidArgument);
+        }
+        Ok(self
+            .control()
+            .with_output_waveform_ad9834(self.output_waveform)?
+            .with_sign_bit_output(self.sign_bit_output)
+            .with_control_source(self.control_source))
+    }
+}
+
+impl<DEV, IC, S, SpiE, PinE> Ad983x<DEV, IC, S>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+    S: state::Configurable,
+{
+    /// Control register to apply, still held in reset in the `Reset` state
+    fn applied_control(control: Config) -> Config {
+        if S::HOLDS_RESET {
+            control.with_high(BitFlags::RESET)
+        } else {
+            control
+        }
+    }
+
+    fn apply_with_control(
+        &mut self,
+        config: &DeviceConfig,
+        control: Config,
+    ) -> Result<(), Error<SpiE, PinE>> {
+        let transfer = Transfer::apply(&self.registers, config, control)?;
+        self.write_transfer(&transfer)
+    }
+}
+
+impl<DEV, S, SpiE, PinE> Ad983x<DEV, marker::Ad9833Ad9837, S>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+    S: state::Configurable,
+{
+    /// Bring the device to a configuration and enable it
+    ///
+    /// The first time, the device is held in reset while all registers are
+    /// loaded and then released (see the AN-1070 application note).
+    /// Afterwards, only the registers which differ from the configuration
+    /// applied (or set) before are written. Everything is written in a single
+    /// SPI transaction.
+    ///
+    /// In the typestate [`Reset`](state::Reset) state the device stays held
+    /// in reset: release it with `enable()` afterwards.
+    ///
+    /// Returns `Error::InvalidArgument` if a register value does not fit or
+    /// if the sign bit output or the hardware pin control source are
+    /// selected, which are not available on AD9833/AD9837 devices.
+    pub fn apply(&mut self, config: &DeviceConfig) -> Result<(), Error<SpiE, PinE>> {
+        let control = Self::applied_control(config.control_ad9833()?);
+        self.apply_with_control(config, control)
+    }
+}
+
+impl<DEV, S, SpiE, PinE> Ad983x<DEV, marker::Ad9834Ad9838, S>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+    S: state::Configurable,
+{
+    /// Bring the device to a configuration and enable it
+    ///
+    /// The first time, the device is held in reset while all registers are
+    /// loaded and then released (see the AN-1070 application note).
+    /// Afterwards, only the registers which differ from the configuration
+    /// applied (or set) before are written. Everything is written in a single
+    /// SPI transaction.
+    ///
+    /// In the typestate [`Reset`](state::Reset) state the device stays held
+    /// in reset: release it with `enable()` afterwards.
+    ///
+    /// With the hardware pin control source, the assigned function control
+    /// pins are set as well after writing the registers. The first time, the
+    /// RESET pin is held high while the registers are loaded and released
+    /// last.
+    ///
+    /// Returns `Error::InvalidArgument` if a register value does not fit, if
+    /// a square output waveform is selected, if the sign bit output is
+    /// combined with the triangle output waveform or if the internal clock is
+    /// disabled with the hardware pin control source.
+    pub fn apply(&mut self, config: &DeviceConfig) -> Result<(), Error<SpiE, PinE>> {
+        let control = Self::applied_control(config.control_ad9834()?);
+        if config.control_source != ControlSource::HardwarePins {
+            return self.apply_with_control(config, control);
+        }
+        // The RESET bit is ignored once the hardware pins are selected.
+        if !self.registers.is_loaded() {
+            self.set_assigned_control_pin(ControlPin::Reset, true)?;
+        }
+        self.apply_with_control(config, control)?;
+        self.sync_control_pins(control)
+    }
+}
@@ -0,0 +1,183 @@
+use crate::{interface::WriteData, state, Ad983x, Error, FrequencyRegister};
+
+/// Master clock frequency assumed until one is configured (25 MHz).
+pub(crate) const DEFAULT_MCLK_HZ: u32 = 25_000_000;
+
+const FREQUENCY_WORD_LIMIT: u64 = 1 << 28;
+
+/// Compute the rounded 28-bit tuning word for a frequency given in millihertz.
+///
+/// Returns `None` if the master clock is zero or the result does not fit in 28 bits.
+pub(crate) fn word_from_millihertz(mclk_hz: u32, millihertz: u64) -> Option<u32> {
+    if mclk_hz == 0 {
+        return None;
+    }
+    let divisor = u128::from(mclk_hz) * 1000;
+    let word = (u128::from(millihertz) * u128::from(FREQUENCY_WORD_LIMIT) + divisor / 2) / divisor;
+    if word < u128::from(FREQUENCY_WORD_LIMIT) {
+        Some(word as u32)
+    } else {
+        None
+    }
+}
+
+/// Compute the output frequency in millihertz (rounded) produced by a tuning word.
+pub(crate) fn millihertz_from_word(mclk_hz: u32, word: u32) -> u64 {
+    let limit = u128::from(FREQUENCY_WORD_LIMIT);
+    let millihertz = (u128::from(word) * u128::from(mclk_hz) * 1000 + limit / 2) / limit;
+    millihertz as u64
+}
+
+/// Compute the rounded 28-bit tuning word for a frequency given in Hz.
+///
+/// Returns `None` for negative or non-finite frequencies, if the master clock
+/// is zero or if the result does not fit in 28 bits.
+pub(crate) fn word_from_hz(mclk_hz: u32, hz: f32) -> Option<u32> {
+    word_from_hz_f64(mclk_hz, f64::from(hz))
+}
+
+/// Compute the output frequency in Hz produced by a tuning word.
+pub(crate) fn hz_from_word(mclk_hz: u32, word: u32) -> f32 {
+    hz_from_word_f64(mclk_hz, word) as f32
+}
+
+/// Same as [`word_from_hz()`] with double precision.
+pub(crate) fn word_from_hz_f64(mclk_hz: u32, hz: f64) -> Option<u32> {
+    if mclk_hz == 0 || !hz.is_finite() || hz < 0.0 {
+        return None;
+    }
+    let word = hz * FREQUENCY_WORD_LIMIT as f64 / f64::from(mclk_hz) + 0.5;
+    if word < FREQUENCY_WORD_LIMIT as f64 {
+        Some(word as u32)
+    } else {
+        None
+    }
+}
+
+/// Same as [`hz_from_word()`] with double precision.
+pub(crate) fn hz_from_word_f64(mclk_hz: u32, word: u32) -> f64 {
+    f64::from(word) * f64::from(mclk_hz) / FREQUENCY_WORD_LIMIT as f64
+}
+
+/// Compute the actual master clock frequency in Hz (rounded) from the nominal
+/// one and its error in ppm.
+///
+/// Returns zero if the correction is not finite or not greater than -10^6 ppm.
+pub(crate) fn corrected_mclk(mclk_hz: u32, ppm: f32) -> u32 {
+    let hz = f64::from(mclk_hz) * (1.0 + f64::from(ppm) / 1e6);
+    if hz.is_finite() && hz > 0.0 {
+        (hz + 0.5) as u32
+    } else {
+        0
+    }
+}
+
+impl<DEV, IC, S> Ad983x<DEV, IC, S> {
+    /// Set the frequency of the master clock (MCLK) connected to the device in Hz.
+    ///
+    /// This is used to compute the tuning words in the Hz-based frequency
+    /// methods like [`set_frequency_hz()`](#method.set_frequency_hz).
+    /// The default is 25 MHz.
+    pub fn set_mclk_frequency(&mut self, hz: u32) {
+        self.mclk_hz = hz;
+    }
+
+    /// Get the configured master clock (MCLK) frequency in Hz.
+    ///
+    /// This is the nominal frequency, without the correction.
+    pub fn mclk_frequency(&self) -> u32 {
+        self.mclk_hz
+    }
+
+    /// Set the error of the master clock (MCLK) frequency in ppm.
+    ///
+    /// A positive value means the clock runs faster than its nominal
+    /// frequency. The Hz-based frequency methods then compute the tuning
+    /// words from the corrected frequency, see
+    /// [`corrected_mclk_frequency()`](#method.corrected_mclk_frequency).
+    /// The default is 0 ppm. See also
+    /// [`calibrate_mclk()`](#method.calibrate_mclk) to measure it.
+    pub fn set_mclk_correction_ppm(&mut self, ppm: f32) {
+        self.mclk_correction_ppm = ppm;
+    }
+
+    /// Get the configured master clock (MCLK) error in ppm.
+    pub fn mclk_correction_ppm(&self) -> f32 {
+        self.mclk_correction_ppm
+    }
+
+    /// Get the actual master clock (MCLK) frequency in Hz (rounded).
+    ///
+    /// This is the configured frequency corrected by the configured error and
+    /// is used to compute the tuning words in the Hz-based frequency methods.
+    pub fn corrected_mclk_frequency(&self) -> u32 {
+        corrected_mclk(self.mclk_hz, self.mclk_correction_ppm)
+    }
+}
+
+impl<DEV, IC, S, SpiE, PinE> Ad983x<DEV, IC, S>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+    S: state::Configurable,
+{
+    /// Set the frequency in Hz.
+    ///
+    /// The 28-bit tuning word is computed from the corrected master clock
+    /// frequency and rounded to the nearest value. Returns the frequency
+    /// actually produced by the device, which differs from the requested one
+    /// by the quantization error.
+    ///
+    /// This will change the mode to 28-bit if it is not used.
+    /// Returns `Error::InvalidArgument` if the frequency is negative, not finite or
+    /// its tuning word does not fit in 28 bits.
+    pub fn set_frequency_hz(
+        &mut self,
+        register: FrequencyRegister,
+        frequency: f32,
+    ) -> Result<f32, Error<SpiE, PinE>> {
+        let mclk_hz = self.corrected_mclk_frequency();
+        let word = word_from_hz(mclk_hz, frequency).ok_or(Error::InvalidArgument)?;
+        self.set_frequency(register, word)?;
+        Ok(hz_from_word(mclk_hz, word))
+    }
+
+    /// Change the output frequency glitch-free to a frequency in Hz.
+    ///
+    /// See [`retune()`](#method.retune) and [`set_frequency_hz()`](#method.set_frequency_hz).
+    /// Returns the frequency actually produced by the device.
+    pub fn retune_hz(&mut self, frequency: f32) -> Result<f32, Error<SpiE, PinE>> {
+        let mclk_hz = self.corrected_mclk_frequency();
+        let word = word_from_hz(mclk_hz, frequency).ok_or(Error::InvalidArgument)?;
+        self.retune(word)?;
+        Ok(hz_from_word(mclk_hz, word))
+    }
+
+    /// Change the output frequency glitch-free to a frequency in millihertz.
+    ///
+    /// See [`retune()`](#method.retune) and [`set_frequency_hz()`](#method.set_frequency_hz).
+    /// Returns the frequency actually produced by the device in millihertz (rounded).
+    pub fn retune_millihertz(&mut self, frequency: u64) -> Result<u64, Error<SpiE, PinE>> {
+        let mclk_hz = self.corrected_mclk_frequency();
+        let word = word_from_millihertz(mclk_hz, frequency).ok_or(Error::InvalidArgument)?;
+        self.retune(word)?;
+        Ok(millihertz_from_word(mclk_hz, word))
+    }
+
+    /// Set the frequency in millihertz.
+    ///
+    /// Integer variant of [`set_frequency_hz()`](#method.set_frequency_hz).
+    /// Returns the frequency actually produced by the device in millihertz (rounded).
+    ///
+    /// This will change the mode to 28-bit if it is not used.
+    /// Returns `Error::InvalidArgument` if the tuning word does not fit in 28 bits.
+    pub fn set_frequency_millihertz(
+        &mut self,
+        register: FrequencyRegister,
+        frequency: u64,
+    ) -> Result<u64, Error<SpiE, PinE>> {
+        let mclk_hz = self.corrected_mclk_frequency();
+        let word = word_from_millihertz(mclk_hz, frequency).ok_or(Error::InvalidArgument)?;
+        self.set_frequency(register, word)?;
+        Ok(millihertz_from_word(mclk_hz, word))
+    }
+}
@@ -0,0 +1,251 @@
+//! Binary frequency-shift keying (FSK) modulation
+//!
+//! The [`FskModulator`] loads the mark frequency (bit `1`) into frequency
+//! register 1 and the space frequency (bit `0`) into frequency register 0.
+//! Each symbol is then transmitted by selecting the corresponding register,
+//! which is a single control write.
+//!
+//! ```no_run
+//! use ad983x::{fsk::{FskConfig, FskModulator, Framing}, Ad983x};
+//! use embedded_hal_bus::spi::ExclusiveDevice;
+//! use linux_embedded_hal::{Delay, SpidevBus, SysfsPin};
+//!
+//! let spi = SpidevBus::open("/dev/spidev0.0").unwrap();
+//! let chip_select = SysfsPin::new(25);
+//! let dev = ExclusiveDevice::new(spi, chip_select, Delay);
+//! let mut dds = Ad983x::new_ad9833(dev);
+//! dds.reset().unwrap();
+//! dds.enable().unwrap();
+//! // Bell 202 tones at 1200 baud with 8N1 UART framing
+//! let config = FskConfig::new(1200.0, 2200.0, 1200).with_framing(Framing::UART_8N1);
+//! let mut fsk = FskModulator::new(dds, config);
+//! fsk.init().unwrap();
+//! fsk.send_bytes(b"hello", &mut Delay).unwrap();
+//! ```
+
+use embedded_hal::delay::DelayNs;
+
+use crate::{interface::WriteData, timing::SymbolClock, Ad983x, Error, FrequencyRegister};
+
+/// Order in which the bits of a byte are transmitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BitOrder {
+    /// Least significant bit first (as in UART)
+    LsbFirst,
+    /// Most significant bit first
+    MsbFirst,
+}
+
+/// Framing of each transmitted byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Framing {
+    /// Number of start bits (space) sent before the data bits
+    pub start_bits: u8,
+    /// Number of stop bits (mark) sent after the data bits
+    pub stop_bits: u8,
+    /// Order of the data bits
+    pub bit_order: BitOrder,
+}
+
+impl Framing {
+    /// No framing: only the data bits are sent, most significant bit first.
+    pub const NONE: Framing = Framing {
+        start_bits: 0,
+        stop_bits: 0,
+        bit_order: BitOrder::MsbFirst,
+    };
+
+    /// UART-style framing: one start bit, 8 data bits LSB first and one stop bit.
+    pub const UART_8N1: Framing = Framing {
+        start_bits: 1,
+        stop_bits: 1,
+        bit_order: BitOrder::LsbFirst,
+    };
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Framing::NONE
+    }
+}
+
+/// FSK modulation configuration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FskConfig {
+    mark_hz: f32,
+    space_hz: f32,
+    baud_rate: u32,
+    framing: Framing,
+}
+
+impl FskConfig {
+    /// Create a configuration with the mark (bit `1`) and space (bit `0`)
+    /// frequencies in Hz and the symbol rate in baud. No framing is used.
+    pub fn new(mark_hz: f32, space_hz: f32, baud_rate: u32) -> Self {
+        FskConfig {
+            mark_hz,
+            space_hz,
+            baud_rate,
+            framing: Framing::NONE,
+        }
+    }
+
+    /// Set the framing used when sending bytes.
+    pub fn with_framing(self, framing: Framing) -> Self {
+        FskConfig { framing, ..self }
+    }
+
+    /// Mark (bit `1`) frequency in Hz
+    pub fn mark_hz(&self) -> f32 {
+        self.mark_hz
+    }
+
+    /// Space (bit `0`) frequency in Hz
+    pub fn space_hz(&self) -> f32 {
+        self.space_hz
+    }
+
+    /// Symbol rate in baud
+    pub fn baud_rate(&self) -> u32 {
+        self.baud_rate
+    }
+
+    /// Framing used when sending bytes
+    pub fn framing(&self) -> Framing {
+        self.framing
+    }
+}
+
+/// Binary FSK modulator using the two frequency registers of a device
+///
+/// The symbol duration is timed with a delay after selecting each symbol
+/// frequency, so the time needed for the SPI write adds to it.
+/// On AD9834/AD9838 devices with the hardware pin control source selected
+/// this requires the FSELECT pin to be assigned.
+#[derive(Debug)]
+pub struct FskModulator<DEV, IC> {
+    dds: Ad983x<DEV, IC>,
+    config: FskConfig,
+    clock: SymbolClock,
+}
+
+impl<DEV, IC> FskModulator<DEV, IC> {
+    /// Create a new modulator. The device should already be reset and enabled.
+    ///
+    /// Call [`init()`](#method.init) to load the frequencies before transmitting.
+    pub fn new(dds: Ad983x<DEV, IC>, config: FskConfig) -> Self {
+        FskModulator {
+            dds,
+            config,
+            clock: SymbolClock::new(config.baud_rate),
+        }
+    }
+
+    /// Destroy the modulator and return the device.
+    pub fn destroy(self) -> Ad983x<DEV, IC> {
+        self.dds
+    }
+
+    /// Modulation configuration
+    pub fn config(&self) -> &FskConfig {
+        &self.config
+    }
+}
+
+impl<DEV, IC, SpiE, PinE> FskModulator<DEV, IC>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+{
+    /// Load the mark and space frequencies and output the mark frequency (idle).
+    ///
+    /// Returns `Error::InvalidArgument` if the baud rate is zero or
+    /// a frequency cannot be set.
+    pub fn init(&mut self) -> Result<(), Error<SpiE, PinE>> {
+        if self.config.baud_rate == 0 {
+            return Err(Error::InvalidArgument);
+        }
+        self.dds
+            .set_frequency_hz(FrequencyRegister::F0, self.config.space_hz)?;
+        self.dds
+            .set_frequency_hz(FrequencyRegister::F1, self.config.mark_hz)?;
+        self.dds.select_frequency(FrequencyRegister::F1)?;
+        self.clock = SymbolClock::new(self.config.baud_rate);
+        Ok(())
+    }
+
+    /// Output the mark frequency without waiting.
+    pub fn idle(&mut self) -> Result<(), Error<SpiE, PinE>> {
+        self.select(true)
+    }
+
+    fn select(&mut self, bit: bool) -> Result<(), Error<SpiE, PinE>> {
+        let register = if bit {
+            FrequencyRegister::F1
+        } else {
+            FrequencyRegister::F0
+        };
+        if self.dds.selected_frequency() != register {
+            self.dds.select_frequency(register)?;
+        }
+        Ok(())
+    }
+
+    /// Transmit a single bit, waiting for one symbol period.
+    pub fn send_bit<D: DelayNs>(
+        &mut self,
+        bit: bool,
+        delay: &mut D,
+    ) -> Result<(), Error<SpiE, PinE>> {
+        self.select(bit)?;
+        delay.delay_ns(self.clock.next_period_ns());
+        Ok(())
+    }
+
+    /// Transmit a sequence of bits. No framing is applied.
+    pub fn send_bits<I, D>(&mut self, bits: I, delay: &mut D) -> Result<(), Error<SpiE, PinE>>
+    where
+        I: IntoIterator<Item = bool>,
+        D: DelayNs,
+    {
+        for bit in bits {
+            self.send_bit(bit, delay)?;
+        }
+        Ok(())
+    }
+
+    /// Transmit a byte applying the configured framing.
+    pub fn send_byte<D: DelayNs>(
+        &mut self,
+        byte: u8,
+        delay: &mut D,
+    ) -> Result<(), Error<SpiE, PinE>> {
+        let framing = self.config.framing;
+        for _ in 0..framing.start_bits {
+            self.send_bit(false, delay)?;
+        }
+        for i in 0..8 {
+            let bit = match framing.bit_order {
+                BitOrder::LsbFirst => (byte >> i) & 1,
+                BitOrder::MsbFirst => (byte >> (7 - i)) & 1,
+            };
+            self.send_bit(bit != 0, delay)?;
+        }
+        for _ in 0..framing.stop_bits {
+            self.send_bit(true, delay)?;
+        }
+        Ok(())
+    }
+
+    /// Transmit bytes applying the configured framing to each of them.
+    pub fn send_bytes<D: DelayNs>(
+        &mut self,
+        bytes: &[u8],
+        delay: &mut D,
+    ) -> Result<(), Error<SpiE, PinE>> {
+        for byte in bytes {
+            self.send_byte(*byte, delay)?;
+        }
+        Ok(())
+    }
+}
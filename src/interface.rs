@@ -0,0 +1,231 @@
+//! Interfaces used to write data to the device
+//!
+//! In the common case the driver is created with an
+//! [`SpiDevice`], which already manages the
+//! FSYNC (chip select) pin. If the FSYNC pin must be controlled by the driver
+//! instead, wrap an [`SpiBus`] and the pin in a
+//! [`SpiBusInterface`] and create the driver with it.
+//!
+//! On AD9834/AD9838 devices, the driver can also own the function control
+//! pins (see [`ControlPins`]), which are then used instead of the control
+//! register bits while the hardware pin control source is selected.
+
+use core::convert::Infallible;
+
+use embedded_hal::{
+    digital::OutputPin,
+    spi::{SpiBus, SpiDevice},
+};
+
+use crate::{ControlPin, Error};
+
+/// SPI bus and FSYNC pin controlled by the driver
+///
+/// The FSYNC pin is driven low for each write and back high afterwards.
+/// Make sure it is high before creating the driver.
+#[derive(Debug)]
+pub struct SpiBusInterface<BUS, FSYNC> {
+    bus: BUS,
+    fsync: FSYNC,
+}
+
+impl<BUS, FSYNC> SpiBusInterface<BUS, FSYNC> {
+    /// Create a new interface from an SPI bus and the FSYNC output pin.
+    pub fn new(bus: BUS, fsync: FSYNC) -> Self {
+        SpiBusInterface { bus, fsync }
+    }
+
+    /// Destroy the interface, return SPI bus instance and FSYNC output pin.
+    pub fn release(self) -> (BUS, FSYNC) {
+        (self.bus, self.fsync)
+    }
+}
+
+/// Write data to the device
+///
+/// This trait is sealed and implemented for any `SpiDevice` and for
+/// [`SpiBusInterface`].
+pub trait WriteData: private::Sealed {
+    /// SPI communication error
+    type SpiError;
+    /// FSYNC pin error
+    type PinError;
+
+    /// Write the bytes to the device in a single frame
+    fn write_data(&mut self, data: &[u8]) -> Result<(), Error<Self::SpiError, Self::PinError>>;
+
+    /// Set the level of a function control pin
+    fn set_control_pin(
+        &mut self,
+        pin: ControlPin,
+        _high: bool,
+    ) -> Result<(), Error<Self::SpiError, Self::PinError>> {
+        Err(Error::PinNotAssigned(pin))
+    }
+}
+
+impl<DEV> WriteData for DEV
+where
+    DEV: SpiDevice,
+{
+    type SpiError = DEV::Error;
+    type PinError = Infallible;
+
+    fn write_data(&mut self, data: &[u8]) -> Result<(), Error<Self::SpiError, Self::PinError>> {
+        self.write(data).map_err(Error::Spi)
+    }
+}
+
+impl<BUS, FSYNC> WriteData for SpiBusInterface<BUS, FSYNC>
+where
+    BUS: SpiBus,
+    FSYNC: OutputPin,
+{
+    type SpiError = BUS::Error;
+    type PinError = FSYNC::Error;
+
+    fn write_data(&mut self, data: &[u8]) -> Result<(), Error<Self::SpiError, Self::PinError>> {
+        self.fsync.set_low().map_err(Error::Pin)?;
+        let result = self
+            .bus
+            .write(data)
+            .and_then(|_| self.bus.flush())
+            .map_err(Error::Spi);
+        let released = self.fsync.set_high().map_err(Error::Pin);
+        result.and(released)
+    }
+}
+
+/// Function control pins of AD9834/AD9838 devices
+///
+/// Pins which are not connected can be left as `None`. Operations which need
+/// them while the hardware pin control source is selected return
+/// [`Error::PinNotAssigned`].
+#[derive(Debug)]
+pub struct ControlPins<P> {
+    /// FSELECT pin: selects frequency register 1 when high
+    pub fselect: Option<P>,
+    /// PSELECT pin: selects phase register 1 when high
+    pub pselect: Option<P>,
+    /// RESET pin: holds the device in reset when high
+    pub reset: Option<P>,
+    /// SLEEP pin: powers down the DAC when high
+    pub sleep: Option<P>,
+}
+
+impl<P> Default for ControlPins<P> {
+    fn default() -> Self {
+        ControlPins {
+            fselect: None,
+            pselect: None,
+            reset: None,
+            sleep: None,
+        }
+    }
+}
+
+impl<P> ControlPins<P>
+where
+    P: OutputPin,
+{
+    fn set(&mut self, pin: ControlPin, high: bool) -> Result<(), Error<Infallible, P::Error>> {
+        let output = match pin {
+            ControlPin::FSelect => self.fselect.as_mut(),
+            ControlPin::PSelect => self.pselect.as_mut(),
+            ControlPin::Reset => self.reset.as_mut(),
+            ControlPin::Sleep => self.sleep.as_mut(),
+        };
+        let output = output.ok_or(Error::PinNotAssigned(pin))?;
+        let result = if high {
+            output.set_high()
+        } else {
+            output.set_low()
+        };
+        result.map_err(Error::Pin)
+    }
+}
+
+/// Interface together with the function control pins of AD9834/AD9838 devices
+///
+/// This is created by the `new_ad9834_with_pins()` and `new_ad9838_with_pins()`
+/// constructors. All pins must have the same type, which must match the
+/// FSYNC pin type if a [`SpiBusInterface`] is used.
+#[derive(Debug)]
+pub struct WithControlPins<DEV, P> {
+    dev: DEV,
+    pins: ControlPins<P>,
+}
+
+impl<DEV, P> WithControlPins<DEV, P> {
+    pub(crate) fn new(dev: DEV, pins: ControlPins<P>) -> Self {
+        WithControlPins { dev, pins }
+    }
+
+    /// Destroy the interface, return the inner interface and the control pins.
+    pub fn release(self) -> (DEV, ControlPins<P>) {
+        (self.dev, self.pins)
+    }
+}
+
+impl<DEV, P> WriteData for WithControlPins<DEV, P>
+where
+    DEV: SpiDevice,
+    P: OutputPin,
+{
+    type SpiError = DEV::Error;
+    type PinError = P::Error;
+
+    fn write_data(&mut self, data: &[u8]) -> Result<(), Error<Self::SpiError, Self::PinError>> {
+        self.dev.write(data).map_err(Error::Spi)
+    }
+
+    fn set_control_pin(
+        &mut self,
+        pin: ControlPin,
+        high: bool,
+    ) -> Result<(), Error<Self::SpiError, Self::PinError>> {
+        self.pins.set(pin, high).map_err(Error::with_spi_error)
+    }
+}
+
+impl<BUS, FSYNC, P> WriteData for WithControlPins<SpiBusInterface<BUS, FSYNC>, P>
+where
+    BUS: SpiBus,
+    FSYNC: OutputPin,
+    P: OutputPin<Error = FSYNC::Error>,
+{
+    type SpiError = BUS::Error;
+    type PinError = FSYNC::Error;
+
+    fn write_data(&mut self, data: &[u8]) -> Result<(), Error<Self::SpiError, Self::PinError>> {
+        self.dev.write_data(data)
+    }
+
+    fn set_control_pin(
+        &mut self,
+        pin: ControlPin,
+        high: bool,
+    ) -> Result<(), Error<Self::SpiError, Self::PinError>> {
+        self.pins.set(pin, high).map_err(Error::with_spi_error)
+    }
+}
+
+impl<PinE> Error<Infallible, PinE> {
+    fn with_spi_error<SpiE>(self) -> Error<SpiE, PinE> {
+        match self {
+            Error::Spi(e) => match e {},
+            Error::Pin(e) => Error::Pin(e),
+            Error::InvalidArgument => Error::InvalidArgument,
+            Error::PinNotAssigned(pin) => Error::PinNotAssigned(pin),
+        }
+    }
+}
+
+mod private {
+    use super::{SpiBusInterface, SpiDevice, WithControlPins};
+
+    pub trait Sealed {}
+    impl<DEV: SpiDevice> Sealed for DEV {}
+    impl<BUS, FSYNC> Sealed for SpiBusInterface<BUS, FSYNC> {}
+    impl<DEV, P> Sealed for WithControlPins<DEV, P> {}
+}
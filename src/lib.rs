@@ -6,24 +6,54 @@
 //!
 //! This driver allows you to:
 //! - Enable/disable/reset the device. See [`enable()`].
+//! - Check the reset/enable sequence at compile time. See: [`into_typestate()`].
 //! - Set the frequency registers. See: [`set_frequency()`].
+//! - Set the frequency registers in Hz. See: [`set_frequency_hz()`].
+//! - Correct and calibrate the master clock frequency error. See: [`calibration`].
 //! - Select the output frequency register. See: [`select_frequency()`].
+//! - Change the output frequency glitch-free. See: [`retune()`].
 //! - Set the phase registers. See: [`set_phase()`].
+//! - Set the phase registers in degrees or radians. See: [`set_phase_degrees()`].
 //! - Select the output phase register. See: [`select_phase()`].
 //! - Set the frequency registers MSBs/LSBs separately. See: [`set_frequency_msb()`].
 //! - Set the output waveform. See: [`set_output_waveform()`].
+//! - Set the output amplitude with an external gain device. See: [`amplitude`].
 //! - Power down/up device parts. See: [`set_powered_down()`].
 //! - Select control source on AD9834/AD9838. See: [`set_control_source()`].
+//! - Drive the FSELECT/PSELECT/RESET/SLEEP pins on AD9834/AD9838. See: [`new_ad9834_with_pins()`].
+//! - Read back the current register values and settings. See: [`frequency()`].
+//! - Plan tuning words and predict their spurs. See: [`planner`].
+//! - Apply a complete configuration writing only the changes. See: [`apply()`].
+//! - Control the FSYNC pin from the driver on an SPI bus. See: [`interface`].
+//! - Run linear, logarithmic or table-based frequency sweeps. See: [`sweep`].
+//! - Generate linear up, down and triangle chirps (FMCW) from a timer. See: [`chirp`].
+//! - Transmit data with binary FSK modulation. See: [`fsk`].
+//! - Transmit data with BPSK, QPSK or 8-PSK modulation. See: [`psk`].
+//! - Stream tables of phases for low-rate phase modulation. See: [`phase_stream`].
+//! - Play notes and monophonic Standard MIDI File melodies. See: [`music`].
+//! - Release several devices from reset together for phase-aligned outputs. See: [`array`](mod@array).
+//! - Generate phase-aligned quadrature (I/Q) signal pairs with two devices. See: [`quadrature`].
+//! - Simulate the devices in software for testing. See: `sim` (`sim` feature).
+//! - Store and load validated presets with Serde. See: `settings` (`serde` feature).
+//! - Control devices on Linux boards from the command line. See: `ad983x` binary (`cli` feature).
+//! - Trace every word written, decoded. See: `defmt` and `log` features.
 //!
 //! [`enable()`]: struct.Ad983x.html#method.enable
+//! [`into_typestate()`]: struct.Ad983x.html#method.into_typestate
 //! [`set_frequency()`]: struct.Ad983x.html#method.set_frequency
+//! [`set_frequency_hz()`]: struct.Ad983x.html#method.set_frequency_hz
 //! [`select_frequency()`]: struct.Ad983x.html#method.select_frequency
+//! [`retune()`]: struct.Ad983x.html#method.retune
 //! [`set_phase()`]: struct.Ad983x.html#method.set_phase
+//! [`set_phase_degrees()`]: struct.Ad983x.html#method.set_phase_degrees
 //! [`select_phase()`]: struct.Ad983x.html#method.select_phase
 //! [`set_frequency_msb()`]: struct.Ad983x.html#method.set_frequency_msb
 //! [`set_output_waveform()`]: struct.Ad983x.html#method.set_output_waveform
 //! [`set_powered_down()`]: struct.Ad983x.html#method.set_powered_down
 //! [`set_control_source()`]: struct.Ad983x.html#method.set_control_source
+//! [`new_ad9834_with_pins()`]: struct.Ad983x.html#method.new_ad9834_with_pins
+//! [`frequency()`]: struct.Ad983x.html#method.frequency
+//! [`apply()`]: struct.Ad983x.html#method.apply
 //!
 //! [Introductory blog post](https://blog.eldruin.com/ad983x-waveform-generator-dds-driver-in-rust/)
 //!
@@ -55,14 +85,91 @@
 //! Article explaining DDS using an AD9833:
 //! - [All about direct digital synthesis](https://www.analog.com/en/analog-dialogue/articles/all-about-direct-digital-synthesis.html)
 //!
-//! ## SysfsPin / Software control source on AD9834/AD9838
+//! ## Hardware pin / Software control source on AD9834/AD9838
 //!
 //! AD9834/AD9838 devices offer the possibility to control several functions
-//! either through hardware pins or software settings. While hardware pin
-//! control is selected, these software operations will be ignored by the hardware.
-//! This driver allows this as well as it would be a valid use case to
-//! configure the status of these functions while on hardware pin control mode
-//! in preparation for a smooth switch to software control.
+//! either through hardware pins or software settings. The driver can own the
+//! FSELECT, PSELECT, RESET and SLEEP pins when created with
+//! `new_ad9834_with_pins()` or `new_ad9838_with_pins()`. While hardware pin
+//! control is selected, `select_frequency()`, `select_phase()`, `reset()`,
+//! `enable()`, `disable()` and `set_powered_down()` then drive these pins
+//! instead of the control register bits. Operations whose pin was not given
+//! to the driver return [`Error::PinNotAssigned`].
+//! The driver keeps track of the state of these functions so that switching
+//! back to software control is smooth.
+//!
+//! ## FSYNC pin
+//!
+//! The driver is usually created with an `SpiDevice`, which manages the FSYNC
+//! (chip select) pin itself. Alternatively, the driver can control the FSYNC
+//! pin on a plain `SpiBus` through a
+//! [`SpiBusInterface`](interface::SpiBusInterface). Errors setting the pin
+//! are then reported as [`Error::Pin`].
+//!
+//! ```no_run
+//! use ad983x::{interface::SpiBusInterface, Ad983x, FrequencyRegister};
+//! use linux_embedded_hal::{SpidevBus, SysfsPin};
+//!
+//! let spi = SpidevBus::open("/dev/spidev0.0").unwrap();
+//! let fsync = SysfsPin::new(25);
+//! let mut dds = Ad983x::new_ad9833(SpiBusInterface::new(spi, fsync));
+//! dds.reset().unwrap();
+//! dds.set_frequency(FrequencyRegister::F0, 4724).unwrap();
+//! dds.enable().unwrap();
+//! let (_spi, _fsync) = dds.destroy().release();
+//! ```
+//!
+//! ## Typestate interface
+//!
+//! Optionally, the reset and enable sequence can be checked at compile time.
+//! After calling [`into_typestate()`](struct.Ad983x.html#method.into_typestate),
+//! the device must be reset before it can be configured and the device
+//! state is tracked in its type. See the [`state`] module.
+//!
+//! ```no_run
+//! use ad983x::{Ad983x, FrequencyRegister};
+//! use embedded_hal_bus::spi::ExclusiveDevice;
+//! use linux_embedded_hal::{Delay, SpidevBus, SysfsPin};
+//!
+//! let spi = SpidevBus::open("/dev/spidev0.0").unwrap();
+//! let chip_select = SysfsPin::new(25);
+//! let dev = ExclusiveDevice::new(spi, chip_select, Delay);
+//! let dds = Ad983x::new_ad9833(dev).into_typestate();
+//! // `dds.set_frequency()` is not available before the reset
+//! let mut dds = match dds.reset() {
+//!     Ok(dds) => dds,
+//!     // The device is returned together with the error: try again
+//!     Err((dds, _error)) => dds.reset().map_err(|(_dds, e)| e).unwrap(),
+//! };
+//! dds.set_frequency_hz(FrequencyRegister::F0, 440.0).unwrap();
+//! let dds = dds.enable().map_err(|(_dds, e)| e).unwrap();
+//! // Back to reset: output stops
+//! let _dds = dds.disable().map_err(|(_dds, e)| e).unwrap();
+//! ```
+//!
+//! ## Logging
+//!
+//! With the `defmt` or `log` feature enabled, every word written to the
+//! device is decoded and emitted at the trace level, for example
+//! `CTRL B28|RESET`, `FREQ1 LSB=0x0DEF` or `PHASE0=0x0400`.
+//! The `defmt` feature also implements `defmt::Format` for all public enums
+//! and [`Error`].
+//!
+//! ## Asynchronous interface
+//!
+//! When the `async` feature is enabled, [`AsyncAd983x`] provides the same
+//! functionality using the `embedded-hal-async` SPI traits, as used for example
+//! in Embassy-based firmware. Both drivers share the register encoding.
+//! This feature requires Rust 1.75 or newer.
+//!
+//! ```ignore
+//! use ad983x::{AsyncAd983x, FrequencyRegister};
+//!
+//! let mut dds = AsyncAd983x::new_ad9833(spi_device);
+//! dds.reset().await.unwrap();
+//! dds.set_frequency_hz(FrequencyRegister::F0, 440.0).await.unwrap();
+//! dds.enable().await.unwrap();
+//! ```
 //!
 //! ## Usage examples (see also examples folder)
 //!
@@ -75,6 +182,7 @@
 //! Please find additional examples using hardware in this repository: [driver-examples].
 //!
 //! This includes an example MIDI player that plays Beethoven's ninth symphony.
+//! A MIDI file player is also included in the [`music`] module.
 //!
 //! [driver-examples]: https://github.com/eldruin/driver-examples
 //!
@@ -100,6 +208,27 @@
 //! let _dev = dds.destroy();
 //! ```
 //!
+//! ### Set the frequency in Hz
+//!
+//! The tuning word is computed from the master clock frequency, which
+//! is 25 MHz unless configured otherwise.
+//!
+//! ```no_run
+//! use ad983x::{Ad983x, FrequencyRegister};
+//! use embedded_hal_bus::spi::ExclusiveDevice;
+//! use linux_embedded_hal::{Delay, SpidevBus, SysfsPin};
+//!
+//! let spi = SpidevBus::open("/dev/spidev0.0").unwrap();
+//! let chip_select = SysfsPin::new(25);
+//! let dev = ExclusiveDevice::new(spi, chip_select, Delay);
+//! let mut dds = Ad983x::new_ad9833(dev);
+//! dds.set_mclk_frequency(25_000_000);
+//! dds.reset().unwrap(); // reset is necessary before operation
+//! let actual = dds.set_frequency_hz(FrequencyRegister::F0, 440.0).unwrap();
+//! dds.enable().unwrap();
+//! // `actual` is the frequency produced after quantization: ~439.96 Hz
+//! ```
+//!
 //! ### Set frequency registers 0 and 1 and alternate between them
 //!
 //! With a 25 MHz clock this alternates between A4 and D5 tones.
@@ -127,6 +256,26 @@
 //! }
 //! ```
 //!
+//! ### Change the frequency glitch-free
+//!
+//! Each new frequency is written into the frequency register not in use,
+//! which is then selected.
+//!
+//! ```no_run
+//! use ad983x::Ad983x;
+//! use embedded_hal_bus::spi::ExclusiveDevice;
+//! use linux_embedded_hal::{Delay, SpidevBus, SysfsPin};
+//!
+//! let spi = SpidevBus::open("/dev/spidev0.0").unwrap();
+//! let chip_select = SysfsPin::new(25);
+//! let dev = ExclusiveDevice::new(spi, chip_select, Delay);
+//! let mut dds = Ad983x::new_ad9833(dev);
+//! dds.reset().unwrap(); // reset is necessary before operation
+//! dds.enable().unwrap();
+//! dds.retune_hz(440.0).unwrap();
+//! dds.retune_hz(587.33).unwrap();
+//! ```
+//!
 //! ### Set the phase register 1 and select it
 //!
 //! ```no_run
@@ -143,6 +292,23 @@
 //! dds.select_phase(PhaseRegister::P1).unwrap();
 //! ```
 //!
+//! ### Set the phase register 0 in degrees
+//!
+//! ```no_run
+//! use ad983x::{Ad983x, PhaseRegister};
+//! use embedded_hal_bus::spi::ExclusiveDevice;
+//! use linux_embedded_hal::{Delay, SpidevBus, SysfsPin};
+//!
+//! let spi = SpidevBus::open("/dev/spidev0.0").unwrap();
+//! let chip_select = SysfsPin::new(25);
+//! let dev = ExclusiveDevice::new(spi, chip_select, Delay);
+//! let mut dds = Ad983x::new_ad9833(dev);
+//! dds.reset().unwrap(); // reset is necessary before operation
+//! // -90° is wrapped to 270°
+//! let actual = dds.set_phase_degrees(PhaseRegister::P0, -90.0).unwrap();
+//! assert_eq!(270.0, actual);
+//! ```
+//!
 //! ### Set output waveform to be triangular
 //!
 //! ```no_run
@@ -158,6 +324,31 @@
 //! dds.set_output_waveform(OutputWaveform::Triangle).unwrap();
 //! ```
 //!
+//! ### Apply a complete configuration
+//!
+//! The first time, the device is held in reset while all registers are
+//! loaded. Afterwards only the changed registers are written.
+//!
+//! ```no_run
+//! use ad983x::{Ad983x, DeviceConfig, FrequencyRegister, OutputWaveform};
+//! use embedded_hal_bus::spi::ExclusiveDevice;
+//! use linux_embedded_hal::{Delay, SpidevBus, SysfsPin};
+//!
+//! let spi = SpidevBus::open("/dev/spidev0.0").unwrap();
+//! let chip_select = SysfsPin::new(25);
+//! let dev = ExclusiveDevice::new(spi, chip_select, Delay);
+//! let mut dds = Ad983x::new_ad9833(dev);
+//! let mut config = DeviceConfig {
+//!     frequency0: 4724,
+//!     frequency1: 9448,
+//!     output_waveform: OutputWaveform::Triangle,
+//!     ..Default::default()
+//! };
+//! dds.apply(&config).unwrap();
+//! config.selected_frequency = FrequencyRegister::F1;
+//! dds.apply(&config).unwrap(); // only writes the control register
+//! ```
+//!
 //! ### Power down the DAC
 //!
 //! ```no_run
@@ -176,28 +367,35 @@
 //! ### Use hardware pins as control source
 //!
 //! ```no_run
-//! use ad983x::{Ad983x, ControlSource};
+//! use ad983x::{interface::ControlPins, Ad983x, ControlSource, FrequencyRegister};
 //! use embedded_hal_bus::spi::ExclusiveDevice;
 //! use linux_embedded_hal::{Delay, SpidevBus, SysfsPin};
 //!
 //! let spi = SpidevBus::open("/dev/spidev0.0").unwrap();
 //! let chip_select = SysfsPin::new(25);
 //! let dev = ExclusiveDevice::new(spi, chip_select, Delay);
-//! let mut dds = Ad983x::new_ad9838(dev);
+//! let pins = ControlPins {
+//!     fselect: Some(SysfsPin::new(23)),
+//!     reset: Some(SysfsPin::new(24)),
+//!     ..Default::default()
+//! };
+//! let mut dds = Ad983x::new_ad9838_with_pins(dev, pins);
 //! dds.reset().unwrap(); // reset is necessary before operation
 //! dds.set_control_source(ControlSource::HardwarePins).unwrap();
-//! // Hardware pins can now be used to control the device.
-//! // The corresponding software settings will be ignored.
+//! // These now drive the FSELECT and RESET pins.
+//! dds.select_frequency(FrequencyRegister::F1).unwrap();
+//! dds.enable().unwrap();
 //! ```
 
 #![deny(unsafe_code, missing_docs)]
 #![no_std]
 
-use core::marker::PhantomData;
+use core::{convert::Infallible, marker::PhantomData};
 use embedded_hal::spi::{Mode, MODE_2};
 
 /// Possible data types that might be send via spi
 #[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum DataFormat {
     /// For storing U32
     U32(u32),
@@ -209,17 +407,31 @@ pub enum DataFormat {
 
 /// All possible errors in this crate
 #[derive(Debug)]
-pub enum Error<E> {
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error<SpiE, PinE = Infallible> {
     /// SPI communication error
-    Spi(E),
+    Spi(SpiE),
+    /// Pin error
+    ///
+    /// Only possible when the driver controls the FSYNC pin through a
+    /// [`SpiBusInterface`](interface::SpiBusInterface), drives the function
+    /// control pins or when setting the RESET pin shared by a
+    /// [`DdsArray`](array::DdsArray).
+    Pin(PinE),
     /// Invalid argument provided
     InvalidArgument,
-    /// CS Pin Error,
-    CSPinError(E),
+    /// The operation needs a function control pin which has not been assigned
+    ///
+    /// Only possible on AD9834/AD9838 devices with the hardware pin
+    /// control source selected.
+    PinNotAssigned(ControlPin),
 }
 
 /// Frequency registers
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum FrequencyRegister {
     /// Frequency register 0
     F0,
@@ -229,6 +441,9 @@ pub enum FrequencyRegister {
 
 /// Phase registers
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum PhaseRegister {
     /// Phase register 0
     P0,
@@ -236,8 +451,19 @@ pub enum PhaseRegister {
     P1,
 }
 
+/// Phase as a fixed-point fraction of a turn
+///
+/// Values are always in the range `[0, 1)` turns: angles outside of it are
+/// wrapped modulo one turn. The resolution (2^-32 turns) is finer than the
+/// 12-bit phase registers, so the value is rounded when set on a device.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Phase(u32);
+
 /// Output waveform
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum OutputWaveform {
     /// Sinusoidal wave (default)
     Sinusoidal,
@@ -253,6 +479,9 @@ pub enum OutputWaveform {
 
 /// Sign bit output on AD9834/AD9838 devices
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum SignBitOutput {
     /// Disabled (high impedance) (default)
     Disabled,
@@ -266,6 +495,9 @@ pub enum SignBitOutput {
 
 /// Powered-down device configuration
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum PoweredDown {
     /// All chip parts are enabled (default)
     Nothing,
@@ -282,6 +514,9 @@ pub enum PoweredDown {
 /// reset of internal registers, and DAC power-down.
 /// (Only available on AD9834 and AD9838 devices)
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum ControlSource {
     /// Functions are controlled only through software (default)
     Software,
@@ -289,6 +524,53 @@ pub enum ControlSource {
     HardwarePins,
 }
 
+/// Function control pins on AD9834/AD9838 devices
+///
+/// These are used instead of the control register bits while the hardware pin
+/// control source is selected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ControlPin {
+    /// FSELECT: frequency register selection
+    FSelect,
+    /// PSELECT: phase register selection
+    PSelect,
+    /// RESET: reset of internal registers
+    Reset,
+    /// SLEEP: DAC power-down
+    Sleep,
+}
+
+/// Complete device configuration
+///
+/// This can be brought to a device at once with `apply()`, which only
+/// writes the registers that changed since the last time.
+/// The defaults match the defaults of the individual settings, with all
+/// registers set to zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeviceConfig {
+    /// Frequency register 0 28-bit word
+    pub frequency0: u32,
+    /// Frequency register 1 28-bit word
+    pub frequency1: u32,
+    /// Phase register 0 12-bit value
+    pub phase0: u16,
+    /// Phase register 1 12-bit value
+    pub phase1: u16,
+    /// Frequency register used
+    pub selected_frequency: FrequencyRegister,
+    /// Phase register used
+    pub selected_phase: PhaseRegister,
+    /// Output waveform
+    pub output_waveform: OutputWaveform,
+    /// Sign bit output (only available on AD9834/AD9838 devices)
+    pub sign_bit_output: SignBitOutput,
+    /// Powered-down device parts
+    pub powered_down: PoweredDown,
+    /// Control source (only available on AD9834/AD9838 devices)
+    pub control_source: ControlSource,
+}
+
 /// SPI mode (CPOL = 1, CPHA = 0)
 pub const MODE: Mode = MODE_2;
 
@@ -296,8 +578,10 @@ pub const MODE: Mode = MODE_2;
 #[doc(hidden)]
 pub mod marker {
     /// AD9833/AD9837 device
+    #[derive(Debug)]
     pub struct Ad9833Ad9837(());
     /// AD9834/AD9838 device
+    #[derive(Debug)]
     pub struct Ad9834Ad9838(());
 }
 
@@ -308,15 +592,118 @@ struct Config {
     bits: u16,
 }
 
+/// Device states for the optional typestate interface
+///
+/// Devices are created in the [`Unchecked`](state::Unchecked) state, where
+/// all operations are available at any time. Calling
+/// [`into_typestate()`](struct.Ad983x.html#method.into_typestate) turns the
+/// device into the [`Uninitialized`](state::Uninitialized) state, from which
+/// the reset and enable sequence is checked at compile time.
+///
+/// Configuring a device which has not been reset does not compile:
+///
+/// ```compile_fail
+/// use ad983x::{Ad983x, FrequencyRegister};
+/// use embedded_hal_bus::spi::ExclusiveDevice;
+/// use linux_embedded_hal::{Delay, SpidevBus, SysfsPin};
+///
+/// let spi = SpidevBus::open("/dev/spidev0.0").unwrap();
+/// let dev = ExclusiveDevice::new(spi, SysfsPin::new(25), Delay);
+/// let mut dds = Ad983x::new_ad9833(dev).into_typestate();
+/// dds.set_frequency(FrequencyRegister::F0, 4724).unwrap();
+/// ```
+pub mod state {
+    /// No compile-time checks: all operations are available (default)
+    #[derive(Debug)]
+    pub struct Unchecked(());
+    /// Device not reset yet after power up: it must be reset before use
+    #[derive(Debug)]
+    pub struct Uninitialized(());
+    /// Device held in reset: registers can be configured but there is no output
+    #[derive(Debug)]
+    pub struct Reset(());
+    /// Device enabled and generating output
+    #[derive(Debug)]
+    pub struct Running(());
+
+    /// States in which the device registers can be configured
+    pub trait Configurable: private::Sealed {}
+    impl Configurable for Unchecked {}
+    impl Configurable for Reset {}
+    impl Configurable for Running {}
+
+    /// Result of a state transition
+    ///
+    /// On error, the device is returned in its previous state `From`
+    /// together with the error.
+    pub type Transition<DEV, IC, From, To, SpiE, PinE> = Result<
+        crate::Ad983x<DEV, IC, To>,
+        (crate::Ad983x<DEV, IC, From>, crate::Error<SpiE, PinE>),
+    >;
+
+    pub(crate) mod private {
+        pub trait Sealed {
+            /// Whether the device must stay held in reset
+            const HOLDS_RESET: bool = false;
+        }
+        impl Sealed for super::Unchecked {}
+        impl Sealed for super::Reset {
+            const HOLDS_RESET: bool = true;
+        }
+        impl Sealed for super::Running {}
+    }
+}
+
 /// AD983x direct digital synthesizer
 #[derive(Debug)]
-pub struct Ad983x<DEV, CS, IC> {
+pub struct Ad983x<DEV, IC, S = state::Unchecked> {
+    spi: DEV,
+    registers: common::Registers,
+    mclk_hz: u32,
+    mclk_correction_ppm: f32,
+    _ic: PhantomData<IC>,
+    _state: PhantomData<S>,
+}
+
+/// AD983x direct digital synthesizer with an asynchronous interface
+///
+/// This offers the same functionality as [`Ad983x`] on top of the
+/// `embedded-hal-async` SPI traits. Only available with the `async` feature.
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct AsyncAd983x<DEV, IC> {
     spi: DEV,
-    cs: CS,
-    control: Config,
+    registers: common::Registers,
+    mclk_hz: u32,
+    mclk_correction_ppm: f32,
     _ic: PhantomData<IC>,
 }
 
 mod ad9833_ad9837;
 mod ad9834_ad9838;
+pub mod amplitude;
+pub mod array;
+#[cfg(feature = "async")]
+mod asynch;
+pub mod calibration;
+pub mod chirp;
 mod common;
+mod device_config;
+mod frequency;
+pub mod fsk;
+pub mod interface;
+pub mod music;
+mod phase;
+pub mod phase_stream;
+pub mod planner;
+pub mod psk;
+pub mod quadrature;
+#[cfg(feature = "serde")]
+pub mod settings;
+#[cfg(feature = "sim")]
+pub mod sim;
+pub mod sweep;
+mod timing;
+#[cfg(any(feature = "defmt", feature = "log"))]
+mod trace;
+mod typestate;
@@ -0,0 +1,204 @@
+//! Musical notes and Standard MIDI File playback
+//!
+//! [`Note`]s can be created from MIDI note numbers or from their names in
+//! scientific pitch notation, like `"A4"` or `"C#5"`, and are turned into
+//! frequencies with a [`Tuning`]: equal temperament with a configurable A4
+//! frequency and optionally one of the alternative temperaments.
+//!
+//! The [`smf`] module parses Standard MIDI Files (format 0 and 1) without
+//! allocating and extracts a monophonic melody out of one track, which the
+//! [`Player`] then plays on a device.
+//!
+//! ```no_run
+//! use ad983x::{music::{smf::Smf, Player}, Ad983x};
+//! use embedded_hal_bus::spi::ExclusiveDevice;
+//! use linux_embedded_hal::{Delay, SpidevBus, SysfsPin};
+//!
+//! let spi = SpidevBus::open("/dev/spidev0.0").unwrap();
+//! let chip_select = SysfsPin::new(25);
+//! let dev = ExclusiveDevice::new(spi, chip_select, Delay);
+//! let mut dds = Ad983x::new_ad9833(dev);
+//! dds.reset().unwrap();
+//! dds.enable().unwrap();
+//! let song = std::fs::read("ode_to_joy.mid").unwrap();
+//! let smf = Smf::parse(&song).unwrap();
+//! let mut player = Player::new(dds, smf.melody(1).unwrap());
+//! player.play(&mut Delay).unwrap();
+//! ```
+
+use core::{fmt, str::FromStr};
+
+mod player;
+pub mod smf;
+
+pub use self::player::Player;
+
+/// Musical note as a MIDI note number
+///
+/// The octave numbers follow the scientific pitch notation, so that MIDI note
+/// number 60 is C4 (middle C) and 69 is A4.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Note(u8);
+
+const NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+impl Note {
+    /// A4 (MIDI note number 69), the usual tuning reference
+    pub const A4: Note = Note(69);
+
+    /// Create a note from a MIDI note number.
+    ///
+    /// Returns `None` if the number is greater than 127.
+    pub const fn from_midi(number: u8) -> Option<Self> {
+        if number < 128 {
+            Some(Note(number))
+        } else {
+            None
+        }
+    }
+
+    /// MIDI note number
+    pub const fn midi(self) -> u8 {
+        self.0
+    }
+
+    /// Pitch class from 0 (C) to 11 (B)
+    pub const fn pitch_class(self) -> u8 {
+        self.0 % 12
+    }
+
+    /// Octave number in scientific pitch notation, from -1 to 9
+    pub const fn octave(self) -> i8 {
+        (self.0 / 12) as i8 - 1
+    }
+
+    /// Frequency in Hz in equal temperament with A4 at 440 Hz
+    pub fn frequency(self) -> f32 {
+        Tuning::default().frequency(self)
+    }
+}
+
+/// Error parsing a note name
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParseNoteError(());
+
+impl FromStr for Note {
+    type Err = ParseNoteError;
+
+    /// Parse a note name like `"A4"`, `"C#5"`, `"Bb3"` or `"C-1"`.
+    ///
+    /// Both `#` and `b` may be repeated once.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let mut chars = name.chars();
+        let mut semitone = match chars.next().map(|c| c.to_ascii_uppercase()) {
+            Some('C') => 0,
+            Some('D') => 2,
+            Some('E') => 4,
+            Some('F') => 5,
+            Some('G') => 7,
+            Some('A') => 9,
+            Some('B') => 11,
+            _ => return Err(ParseNoteError(())),
+        };
+        let rest = chars.as_str();
+        let sharps = rest.len() - rest.trim_start_matches('#').len();
+        let rest = &rest[sharps..];
+        let flats = rest.len() - rest.trim_start_matches('b').len();
+        let octave = &rest[flats..];
+        if sharps > 2 || flats > 2 || (sharps > 0 && flats > 0) {
+            return Err(ParseNoteError(()));
+        }
+        semitone += sharps as i16 - flats as i16;
+        let octave: i16 = octave.parse().map_err(|_| ParseNoteError(()))?;
+        let number = (octave + 1)
+            .checked_mul(12)
+            .and_then(|n| n.checked_add(semitone))
+            .ok_or(ParseNoteError(()))?;
+        u8::try_from(number)
+            .ok()
+            .and_then(Note::from_midi)
+            .ok_or(ParseNoteError(()))
+    }
+}
+
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            NAMES[usize::from(self.pitch_class())],
+            self.octave()
+        )
+    }
+}
+
+/// Deviations from equal temperament in cents for the 12 notes of a scale,
+/// starting with its root
+pub type Temperament = [f32; 12];
+
+/// 5-limit just intonation
+pub const JUST_INTONATION: Temperament = [
+    0.0, 11.73, 3.91, 15.64, -13.69, -1.96, -9.78, 1.96, 13.69, -15.64, -3.91, -11.73,
+];
+
+/// Pythagorean tuning (pure fifths)
+pub const PYTHAGOREAN: Temperament = [
+    0.0, -9.78, 3.91, -5.87, 7.82, -1.96, 11.73, 1.96, -7.82, 5.87, -3.91, 9.78,
+];
+
+/// Conversion of notes to frequencies
+///
+/// The default is equal temperament with A4 at 440 Hz. With an alternative
+/// temperament, the root of the scale keeps its equal temperament frequency
+/// and the other notes deviate from it.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tuning {
+    a4_hz: f32,
+    root: u8,
+    cents: Temperament,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Tuning::equal_temperament(440.0)
+    }
+}
+
+impl Tuning {
+    /// Equal temperament with the given frequency of A4 in Hz
+    pub const fn equal_temperament(a4_hz: f32) -> Self {
+        Tuning {
+            a4_hz,
+            root: 0,
+            cents: [0.0; 12],
+        }
+    }
+
+    /// Use an alternative temperament for the scale with the given root
+    /// pitch class, from 0 (C) to 11 (B).
+    pub const fn with_temperament(self, root: u8, cents: Temperament) -> Self {
+        Tuning {
+            root: root % 12,
+            cents,
+            ..self
+        }
+    }
+
+    /// Frequency of A4 in Hz
+    pub fn a4_hz(&self) -> f32 {
+        self.a4_hz
+    }
+
+    /// Frequency of a note in Hz
+    pub fn frequency(&self, note: Note) -> f32 {
+        let degree = (note.pitch_class() + 12 - self.root) % 12;
+        let cents = self.cents[usize::from(degree)];
+        let semitones = f64::from(note.midi()) - 69.0 + f64::from(cents) / 100.0;
+        (f64::from(self.a4_hz) * libm::exp2(semitones / 12.0)) as f32
+    }
+}
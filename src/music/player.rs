@@ -0,0 +1,102 @@
+use embedded_hal::delay::DelayNs;
+
+use super::{
+    smf::{Melody, Step},
+    Tuning,
+};
+use crate::{interface::WriteData, Ad983x, Error, PoweredDown};
+
+/// Plays a [`Melody`] on a device
+///
+/// Each note is written with [`Ad983x::set_frequency_hz()`] into the currently
+/// selected frequency register. During rests the DAC is powered down.
+#[derive(Debug)]
+pub struct Player<'a, DEV, IC> {
+    dds: Ad983x<DEV, IC>,
+    /// Whole melody, to start over
+    melody: Melody<'a>,
+    /// Steps not played yet
+    steps: Melody<'a>,
+    tuning: Tuning,
+    next: Option<Step>,
+}
+
+impl<'a, DEV, IC> Player<'a, DEV, IC> {
+    /// Create a new player. The device should already be reset and enabled.
+    pub fn new(dds: Ad983x<DEV, IC>, melody: Melody<'a>) -> Self {
+        let mut steps = melody.clone();
+        let next = steps.next();
+        Player {
+            dds,
+            melody,
+            steps,
+            tuning: Tuning::default(),
+            next,
+        }
+    }
+
+    /// Set the tuning used to convert notes into frequencies.
+    pub fn with_tuning(self, tuning: Tuning) -> Self {
+        Player { tuning, ..self }
+    }
+
+    /// Destroy the player and return the device.
+    pub fn destroy(self) -> Ad983x<DEV, IC> {
+        self.dds
+    }
+
+    /// Whether all the steps of the melody have been played
+    pub fn is_finished(&self) -> bool {
+        self.next.is_none()
+    }
+
+    /// Start over from the beginning of the melody
+    pub fn restart(&mut self) {
+        self.steps = self.melody.clone();
+        self.next = self.steps.next();
+    }
+}
+
+impl<DEV, IC, SpiE, PinE> Player<'_, DEV, IC>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+{
+    /// Play all the steps due at `now_us` microseconds since the start of the melody.
+    ///
+    /// This is intended to be called periodically, for example from a timer
+    /// interrupt. Returns the time of the next step in microseconds or `None`
+    /// if the melody is finished.
+    /// Returns `Error::InvalidArgument` if the frequency of a note cannot be set.
+    pub fn poll(&mut self, now_us: u64) -> Result<Option<u64>, Error<SpiE, PinE>> {
+        while let Some(step) = self.next.filter(|step| step.time_us <= now_us) {
+            match step.note {
+                Some(note) => {
+                    let register = self.dds.selected_frequency();
+                    self.dds
+                        .set_frequency_hz(register, self.tuning.frequency(note))?;
+                    if self.dds.powered_down() != PoweredDown::Nothing {
+                        self.dds.set_powered_down(PoweredDown::Nothing)?;
+                    }
+                }
+                None => self.dds.set_powered_down(PoweredDown::Dac)?,
+            }
+            self.next = self.steps.next();
+        }
+        Ok(self.next.map(|step| step.time_us))
+    }
+
+    /// Play the rest of the melody, waiting between the steps.
+    pub fn play<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<SpiE, PinE>> {
+        let mut now_us = self.next.map_or(0, |step| step.time_us);
+        while let Some(next_us) = self.poll(now_us)? {
+            let mut wait_us = next_us - now_us;
+            while wait_us > 0 {
+                let chunk = wait_us.min(u64::from(u32::MAX)) as u32;
+                delay.delay_us(chunk);
+                wait_us -= u64::from(chunk);
+            }
+            now_us = next_us;
+        }
+        Ok(())
+    }
+}
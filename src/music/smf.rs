@@ -0,0 +1,463 @@
+//! Standard MIDI File parser
+//!
+//! [`Smf::parse()`] checks the structure of a Standard MIDI File of format 0
+//! or 1 kept in memory, without allocating. [`Smf::melody()`] then extracts a
+//! monophonic [`Melody`] out of one of its tracks: whenever a note starts, it
+//! replaces the one sounding before, and the melody rests when the sounding
+//! note ends. In format 1 files, the tempo changes are taken from the first
+//! track.
+
+use super::Note;
+
+/// Standard MIDI File errors
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SmfError {
+    /// The data does not start with a MIDI file header
+    InvalidHeader,
+    /// Format 2 files (independent patterns) are not supported
+    UnsupportedFormat,
+    /// The data ends in the middle of a chunk or an event
+    Truncated,
+    /// A track contains an invalid event
+    InvalidEvent,
+    /// There is no track with the given index
+    NoSuchTrack,
+    /// An event time does not fit in 64 bits of microseconds
+    TimeOverflow,
+}
+
+/// Time division of a file
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Division {
+    /// Ticks per quarter note, timed with the tempo events
+    TicksPerQuarter(u16),
+    /// SMPTE frames per second (24, 25, 29 for 29.97 or 30) and ticks per frame
+    Smpte {
+        /// Frames per second
+        frames_per_second: u8,
+        /// Ticks per frame
+        ticks_per_frame: u8,
+    },
+}
+
+/// Standard MIDI File kept in memory
+#[derive(Debug, Clone, Copy)]
+pub struct Smf<'a> {
+    format: u16,
+    division: Division,
+    track_count: usize,
+    chunks: &'a [u8],
+}
+
+fn read_u16(data: &[u8], position: usize) -> Option<u16> {
+    let bytes = data.get(position..position + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], position: usize) -> Option<u32> {
+    let bytes = data.get(position..position + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Iterator over the chunks of a file as (type, data)
+struct Chunks<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = Result<(&'a [u8], &'a [u8]), SmfError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let chunk = read_u32(self.data, 4)
+            .and_then(|length| {
+                let end = 8usize.checked_add(length as usize)?;
+                let chunk = (&self.data[..4], self.data.get(8..end)?);
+                self.data = &self.data[end..];
+                Some(chunk)
+            })
+            .ok_or(SmfError::Truncated);
+        if chunk.is_err() {
+            self.data = &[];
+        }
+        Some(chunk)
+    }
+}
+
+impl<'a> Smf<'a> {
+    /// Parse the header and check the chunk structure of a file.
+    pub fn parse(data: &'a [u8]) -> Result<Self, SmfError> {
+        if data.get(..4) != Some(b"MThd") {
+            return Err(SmfError::InvalidHeader);
+        }
+        let length = read_u32(data, 4).ok_or(SmfError::Truncated)? as usize;
+        if length < 6 {
+            return Err(SmfError::InvalidHeader);
+        }
+        let chunks = 8usize
+            .checked_add(length)
+            .and_then(|end| data.get(end..))
+            .ok_or(SmfError::Truncated)?;
+        let format = read_u16(data, 8).ok_or(SmfError::Truncated)?;
+        let division = read_u16(data, 12).ok_or(SmfError::Truncated)?;
+        let division = if division & 0x8000 == 0 {
+            Division::TicksPerQuarter(division)
+        } else {
+            Division::Smpte {
+                frames_per_second: ((division >> 8) as i8).unsigned_abs(),
+                ticks_per_frame: division as u8,
+            }
+        };
+        match format {
+            0 | 1 => (),
+            2 => return Err(SmfError::UnsupportedFormat),
+            _ => return Err(SmfError::InvalidHeader),
+        }
+        if matches!(
+            division,
+            Division::TicksPerQuarter(0)
+                | Division::Smpte {
+                    ticks_per_frame: 0,
+                    ..
+                }
+        ) || matches!(division, Division::Smpte { frames_per_second, .. }
+            if ![24, 25, 29, 30].contains(&frames_per_second))
+        {
+            return Err(SmfError::InvalidHeader);
+        }
+        let mut track_count = 0;
+        for chunk in (Chunks { data: chunks }) {
+            if chunk?.0 == b"MTrk" {
+                track_count += 1;
+            }
+        }
+        Ok(Smf {
+            format,
+            division,
+            track_count,
+            chunks,
+        })
+    }
+
+    /// File format: 0 (single track) or 1 (simultaneous tracks)
+    pub fn format(&self) -> u16 {
+        self.format
+    }
+
+    /// Time division
+    pub fn division(&self) -> Division {
+        self.division
+    }
+
+    /// Number of tracks
+    pub fn track_count(&self) -> usize {
+        self.track_count
+    }
+
+    fn track(&self, index: usize) -> Option<&'a [u8]> {
+        Chunks { data: self.chunks }
+            .filter_map(Result::ok)
+            .filter(|(kind, _)| *kind == b"MTrk")
+            .nth(index)
+            .map(|(_, data)| data)
+    }
+
+    /// Extract the monophonic melody of a track.
+    ///
+    /// The whole track (and the tempo track) is checked here, so that the
+    /// melody can be iterated without errors afterwards.
+    pub fn melody(&self, track: usize) -> Result<Melody<'a>, SmfError> {
+        let notes = self.track(track).ok_or(SmfError::NoSuchTrack)?;
+        let tempo = if self.format == 1 && track != 0 {
+            self.track(0).map(Events::new)
+        } else {
+            None
+        };
+        let melody = Melody {
+            readers: [Some(Events::new(notes)), tempo],
+            peeked: [None, None],
+            clock: Clock::new(self.division),
+            sounding: None,
+            rest: None,
+        };
+        let mut check = melody.clone();
+        while check.step()?.is_some() {}
+        Ok(melody)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Event {
+    NoteOn(u8),
+    NoteOff(u8),
+    Tempo(u32),
+    EndOfTrack,
+    Other,
+}
+
+/// Reader of the events of a track
+#[derive(Debug, Clone)]
+struct Events<'a> {
+    data: &'a [u8],
+    position: usize,
+    running_status: Option<u8>,
+    tick: u64,
+}
+
+impl<'a> Events<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Events {
+            data,
+            position: 0,
+            running_status: None,
+            tick: 0,
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, SmfError> {
+        let byte = *self.data.get(self.position).ok_or(SmfError::Truncated)?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn variable_length(&mut self) -> Result<u32, SmfError> {
+        let mut value = 0;
+        for _ in 0..4 {
+            let byte = self.byte()?;
+            value = (value << 7) | u32::from(byte & 0x7F);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(SmfError::InvalidEvent)
+    }
+
+    fn skip(&mut self, length: u32) -> Result<&'a [u8], SmfError> {
+        let end = self
+            .position
+            .checked_add(length as usize)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(SmfError::Truncated)?;
+        let skipped = &self.data[self.position..end];
+        self.position = end;
+        Ok(skipped)
+    }
+
+    /// Next event with its absolute time in ticks
+    ///
+    /// Returns `None` at the end of the track, also if the end of track
+    /// event is missing.
+    fn next(&mut self) -> Result<Option<(u64, Event)>, SmfError> {
+        if self.position >= self.data.len() {
+            return Ok(None);
+        }
+        let delta = u64::from(self.variable_length()?);
+        self.tick = self.tick.checked_add(delta).ok_or(SmfError::TimeOverflow)?;
+        let mut status = self.byte()?;
+        let event = match status {
+            0xFF => {
+                self.running_status = None;
+                let kind = self.byte()?;
+                let length = self.variable_length()?;
+                let data = self.skip(length)?;
+                match (kind, data) {
+                    (0x2F, _) => Event::EndOfTrack,
+                    (0x51, [a, b, c]) => Event::Tempo(u32::from_be_bytes([0, *a, *b, *c]).max(1)),
+                    (0x51, _) => return Err(SmfError::InvalidEvent),
+                    _ => Event::Other,
+                }
+            }
+            0xF0 | 0xF7 => {
+                self.running_status = None;
+                let length = self.variable_length()?;
+                self.skip(length)?;
+                Event::Other
+            }
+            0xF1..=0xFE => return Err(SmfError::InvalidEvent),
+            _ => {
+                let first = if status & 0x80 == 0 {
+                    let data = status;
+                    status = self.running_status.ok_or(SmfError::InvalidEvent)?;
+                    data
+                } else {
+                    self.running_status = Some(status);
+                    self.byte()?
+                };
+                let second = match status & 0xF0 {
+                    0xC0 | 0xD0 => 0,
+                    _ => self.byte()?,
+                };
+                if first & 0x80 != 0 || second & 0x80 != 0 {
+                    return Err(SmfError::InvalidEvent);
+                }
+                match status & 0xF0 {
+                    0x90 if second != 0 => Event::NoteOn(first),
+                    0x80 | 0x90 => Event::NoteOff(first),
+                    _ => Event::Other,
+                }
+            }
+        };
+        if event == Event::EndOfTrack {
+            self.position = self.data.len();
+        }
+        Ok(Some((self.tick, event)))
+    }
+}
+
+/// Conversion of ticks to microseconds following the tempo changes
+#[derive(Debug, Clone, Copy)]
+struct Clock {
+    division: Division,
+    tempo_us: u32,
+    anchor_tick: u64,
+    anchor_us: u64,
+}
+
+impl Clock {
+    fn new(division: Division) -> Self {
+        Clock {
+            division,
+            tempo_us: 500_000, // 120 beats per minute
+            anchor_tick: 0,
+            anchor_us: 0,
+        }
+    }
+
+    /// Time of a tick in microseconds
+    fn time_us(&self, tick: u64) -> Result<u64, SmfError> {
+        let time = match self.division {
+            Division::TicksPerQuarter(ticks) => {
+                let elapsed = u128::from(tick - self.anchor_tick);
+                u128::from(self.anchor_us) + elapsed * u128::from(self.tempo_us) / u128::from(ticks)
+            }
+            Division::Smpte {
+                frames_per_second,
+                ticks_per_frame,
+            } => {
+                let tick = u128::from(tick);
+                let ticks = u128::from(ticks_per_frame);
+                if frames_per_second == 29 {
+                    // 29.97 frames per second
+                    tick * 1_001_000_000 / (30_000 * ticks)
+                } else {
+                    tick * 1_000_000 / (u128::from(frames_per_second) * ticks)
+                }
+            }
+        };
+        u64::try_from(time).map_err(|_| SmfError::TimeOverflow)
+    }
+
+    fn set_tempo(&mut self, tick: u64, tempo_us: u32) -> Result<(), SmfError> {
+        self.anchor_us = self.time_us(tick)?;
+        self.anchor_tick = tick;
+        self.tempo_us = tempo_us;
+        Ok(())
+    }
+}
+
+/// A change of the melody
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Step {
+    /// Time since the start of the song in microseconds
+    pub time_us: u64,
+    /// Note starting at this time or `None` for a rest
+    pub note: Option<Note>,
+}
+
+/// Monophonic melody extracted from a track
+///
+/// This iterates over the changes of the melody in chronological order.
+/// The last step is always a rest.
+#[derive(Debug, Clone)]
+pub struct Melody<'a> {
+    /// Readers of the track with the notes and of the tempo track
+    readers: [Option<Events<'a>>; 2],
+    peeked: [Option<(u64, Event)>; 2],
+    clock: Clock,
+    sounding: Option<u8>,
+    /// Time in ticks of a rest which is only reported if no note starts then
+    rest: Option<u64>,
+}
+
+impl Melody<'_> {
+    /// Next event of the notes (0) or tempo track (1) in chronological order
+    ///
+    /// Returns `None` at the end of the track with the notes.
+    fn next_event(&mut self) -> Result<Option<(u64, usize, Event)>, SmfError> {
+        for (reader, peeked) in self.readers.iter_mut().zip(self.peeked.iter_mut()) {
+            if let (Some(events), None) = (reader.as_mut(), peeked.as_ref()) {
+                *peeked = events.next()?;
+            }
+        }
+        // Tempo changes come first on ties so that they apply to notes at the same time.
+        let index = match self.peeked {
+            [Some((notes, _)), Some((tempo, _))] if tempo <= notes => 1,
+            [Some(_), _] => 0,
+            [None, _] => return Ok(None),
+        };
+        Ok(self.peeked[index]
+            .take()
+            .map(|(tick, event)| (tick, index, event)))
+    }
+
+    fn rest_step(&mut self) -> Result<Option<Step>, SmfError> {
+        match self.rest.take() {
+            Some(tick) => Ok(Some(Step {
+                time_us: self.clock.time_us(tick)?,
+                note: None,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    fn step(&mut self) -> Result<Option<Step>, SmfError> {
+        loop {
+            let (tick, track, event) = match self.next_event()? {
+                Some(event) => event,
+                None => {
+                    if self.sounding.take().is_some() {
+                        self.rest = self.readers[0].as_ref().map(|events| events.tick);
+                    }
+                    return self.rest_step();
+                }
+            };
+            if matches!(self.rest, Some(rest) if rest < tick) {
+                // Put the event back, it is handled after reporting the rest.
+                self.peeked[track] = Some((tick, event));
+                return self.rest_step();
+            }
+            match (track, event) {
+                (_, Event::Tempo(tempo_us)) => self.clock.set_tempo(tick, tempo_us)?,
+                (0, Event::NoteOn(key)) => {
+                    self.rest = None;
+                    if self.sounding != Some(key) {
+                        self.sounding = Some(key);
+                        return Ok(Some(Step {
+                            time_us: self.clock.time_us(tick)?,
+                            note: Note::from_midi(key),
+                        }));
+                    }
+                }
+                (0, Event::NoteOff(key)) if self.sounding == Some(key) => {
+                    self.sounding = None;
+                    self.rest = Some(tick);
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+impl Iterator for Melody<'_> {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Step> {
+        // The track was checked when creating the melody.
+        self.step().ok().flatten()
+    }
+}
@@ -0,0 +1,197 @@
+use core::f64::consts::PI;
+use core::ops::{Add, Neg, Sub};
+
+use crate::{interface::WriteData, state, Ad983x, Error, Phase, PhaseRegister};
+
+const TURN: f64 = 4_294_967_296.0; // 2^32
+const REGISTER_SHIFT: u32 = 32 - 12;
+
+impl Phase {
+    /// Zero phase
+    pub const ZERO: Phase = Phase(0);
+
+    /// Create a phase from a raw fixed-point value in units of 2^-32 turns.
+    pub const fn from_raw(raw: u32) -> Self {
+        Phase(raw)
+    }
+
+    /// Create a phase from a 12-bit phase register value.
+    ///
+    /// Only the 12 LSBs are taken into account.
+    pub const fn from_register_value(value: u16) -> Self {
+        Phase(((value & 0xFFF) as u32) << REGISTER_SHIFT)
+    }
+
+    /// Create a phase from a fraction of a turn.
+    ///
+    /// Values outside `[0, 1)` are wrapped modulo one turn.
+    /// Non-finite values result in a zero phase.
+    pub fn from_turns(turns: f32) -> Self {
+        Self::wrap(f64::from(turns))
+    }
+
+    /// Create a phase from an angle in degrees.
+    ///
+    /// Negative values and values of 360° or more are wrapped modulo one turn.
+    /// Non-finite values result in a zero phase.
+    pub fn from_degrees(degrees: f32) -> Self {
+        Self::wrap(f64::from(degrees) / 360.0)
+    }
+
+    /// Create a phase from an angle in radians.
+    ///
+    /// Negative values and values of 2π or more are wrapped modulo one turn.
+    /// Non-finite values result in a zero phase.
+    pub fn from_radians(radians: f32) -> Self {
+        Self::wrap(f64::from(radians) / (2.0 * PI))
+    }
+
+    fn wrap(turns: f64) -> Self {
+        if !turns.is_finite() {
+            return Phase::ZERO;
+        }
+        // Values this big do not have a fractional part.
+        let fraction = if turns.abs() >= 4_503_599_627_370_496.0 {
+            0.0
+        } else {
+            turns - (turns as i64) as f64
+        };
+        let fraction = if fraction < 0.0 {
+            fraction + 1.0
+        } else {
+            fraction
+        };
+        // A fraction rounding up to a full turn wraps to zero.
+        Phase((fraction * TURN + 0.5) as u64 as u32)
+    }
+
+    /// Raw fixed-point value in units of 2^-32 turns.
+    pub const fn raw(self) -> u32 {
+        self.0
+    }
+
+    /// Nearest 12-bit phase register value.
+    pub const fn register_value(self) -> u16 {
+        (self.0.wrapping_add(1 << (REGISTER_SHIFT - 1)) >> REGISTER_SHIFT) as u16
+    }
+
+    /// Phase rounded to the nearest of the 4096 steps the device can produce.
+    pub const fn quantized(self) -> Self {
+        Self::from_register_value(self.register_value())
+    }
+
+    /// Phase as a fraction of a turn in `[0, 1)`.
+    pub fn turns(self) -> f32 {
+        (f64::from(self.0) / TURN) as f32
+    }
+
+    /// Phase in degrees in `[0, 360)`.
+    pub fn degrees(self) -> f32 {
+        (f64::from(self.0) / TURN * 360.0) as f32
+    }
+
+    /// Phase in radians in `[0, 2π)`.
+    pub fn radians(self) -> f32 {
+        (f64::from(self.0) / TURN * 2.0 * PI) as f32
+    }
+}
+
+impl Add for Phase {
+    type Output = Phase;
+
+    fn add(self, rhs: Phase) -> Phase {
+        Phase(self.0.wrapping_add(rhs.0))
+    }
+}
+
+impl Sub for Phase {
+    type Output = Phase;
+
+    fn sub(self, rhs: Phase) -> Phase {
+        Phase(self.0.wrapping_sub(rhs.0))
+    }
+}
+
+impl Neg for Phase {
+    type Output = Phase;
+
+    fn neg(self) -> Phase {
+        Phase(self.0.wrapping_neg())
+    }
+}
+
+impl<DEV, IC, S, SpiE, PinE> Ad983x<DEV, IC, S>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+    S: state::Configurable,
+{
+    /// Set a phase register from a `Phase`
+    ///
+    /// The phase is rounded to the nearest of the 4096 steps available.
+    /// Returns the phase actually set.
+    pub fn set_phase_value(
+        &mut self,
+        register: PhaseRegister,
+        phase: Phase,
+    ) -> Result<Phase, Error<SpiE, PinE>> {
+        self.set_phase(register, phase.register_value())?;
+        Ok(phase.quantized())
+    }
+
+    /// Set a phase register from a fraction of a turn
+    ///
+    /// Values outside `[0, 1)` are wrapped modulo one turn and the result is
+    /// rounded to the nearest of the 4096 steps available.
+    /// Returns the phase actually set in turns.
+    ///
+    /// Returns `Error::InvalidArgument` if the value is not finite.
+    pub fn set_phase_turns(
+        &mut self,
+        register: PhaseRegister,
+        turns: f32,
+    ) -> Result<f32, Error<SpiE, PinE>> {
+        if !turns.is_finite() {
+            return Err(Error::InvalidArgument);
+        }
+        let phase = self.set_phase_value(register, Phase::from_turns(turns))?;
+        Ok(phase.turns())
+    }
+
+    /// Set a phase register from an angle in degrees
+    ///
+    /// Negative values and values of 360° or more are wrapped modulo one turn
+    /// and the result is rounded to the nearest of the 4096 steps available.
+    /// Returns the phase actually set in degrees.
+    ///
+    /// Returns `Error::InvalidArgument` if the value is not finite.
+    pub fn set_phase_degrees(
+        &mut self,
+        register: PhaseRegister,
+        degrees: f32,
+    ) -> Result<f32, Error<SpiE, PinE>> {
+        if !degrees.is_finite() {
+            return Err(Error::InvalidArgument);
+        }
+        let phase = self.set_phase_value(register, Phase::from_degrees(degrees))?;
+        Ok(phase.degrees())
+    }
+
+    /// Set a phase register from an angle in radians
+    ///
+    /// Negative values and values of 2π or more are wrapped modulo one turn
+    /// and the result is rounded to the nearest of the 4096 steps available.
+    /// Returns the phase actually set in radians.
+    ///
+    /// Returns `Error::InvalidArgument` if the value is not finite.
+    pub fn set_phase_radians(
+        &mut self,
+        register: PhaseRegister,
+        radians: f32,
+    ) -> Result<f32, Error<SpiE, PinE>> {
+        if !radians.is_finite() {
+            return Err(Error::InvalidArgument);
+        }
+        let phase = self.set_phase_value(register, Phase::from_radians(radians))?;
+        Ok(phase.radians())
+    }
+}
@@ -0,0 +1,203 @@
+//! Table-driven phase modulation
+//!
+//! A [`PhaseStreamer`] produces low-rate phase-modulated signals by writing a
+//! table of phases to the device, one sample per tick. Each sample is written
+//! into the phase register not in use, which is then selected, so that the
+//! output changes phase in a single step. When one of the phase registers
+//! already holds the sample, it is only selected.
+//!
+//! The phases come from any cloneable iterator, for example a slice of
+//! [`Phase`] values, or from a slice of angles in degrees or of raw 12-bit
+//! phase register values. The table can be played once or looped.
+//! [`PhaseStreamer::advance()`] writes a single sample and is intended to be
+//! called from a timer interrupt at the sample rate.
+//!
+//! ```no_run
+//! use ad983x::{phase_stream::PhaseStreamer, Ad983x, FrequencyRegister};
+//! use embedded_hal_bus::spi::ExclusiveDevice;
+//! use linux_embedded_hal::{Delay, SpidevBus, SysfsPin};
+//!
+//! let spi = SpidevBus::open("/dev/spidev0.0").unwrap();
+//! let chip_select = SysfsPin::new(25);
+//! let dev = ExclusiveDevice::new(spi, chip_select, Delay);
+//! let mut dds = Ad983x::new_ad9833(dev);
+//! dds.reset().unwrap();
+//! dds.set_frequency_hz(FrequencyRegister::F0, 10_000.0).unwrap();
+//! dds.enable().unwrap();
+//! // Slow sinusoidal phase modulation of ±45° with 8 samples at 100 Hz
+//! const TABLE: [f32; 8] = [0.0, 31.8, 45.0, 31.8, 0.0, -31.8, -45.0, -31.8];
+//! let mut streamer = PhaseStreamer::from_degrees(dds, &TABLE, 100).with_looping();
+//! // Play the table once per call
+//! streamer.run(&mut Delay).unwrap();
+//! ```
+
+use core::{iter, slice};
+
+use embedded_hal::delay::DelayNs;
+
+use crate::{interface::WriteData, timing::SymbolClock, Ad983x, Error, Phase};
+
+/// Phases of a slice of angles in degrees
+///
+/// See [`PhaseStreamer::from_degrees()`].
+pub type Degrees<'a> = iter::Map<iter::Copied<slice::Iter<'a, f32>>, fn(f32) -> Phase>;
+
+/// Phases of a slice of 12-bit phase register values
+///
+/// See [`PhaseStreamer::from_register_values()`].
+pub type RegisterValues<'a> = iter::Map<iter::Copied<slice::Iter<'a, u16>>, fn(u16) -> Phase>;
+
+/// Streams a table of phases into the phase registers of a device
+///
+/// On AD9834/AD9838 devices with the hardware pin control source selected
+/// this requires the PSELECT pin to be assigned.
+#[derive(Debug)]
+pub struct PhaseStreamer<DEV, IC, I> {
+    dds: Ad983x<DEV, IC>,
+    table: I,
+    samples: I,
+    next: Option<Phase>,
+    index: usize,
+    looping: bool,
+    sample_rate: u32,
+    clock: SymbolClock,
+}
+
+impl<DEV, IC, I> PhaseStreamer<DEV, IC, I>
+where
+    I: Iterator<Item = Phase> + Clone,
+{
+    /// Create a new streamer playing a table of phases once at a sample rate
+    /// in Hz. The device should already be reset, have its frequency set and
+    /// be enabled.
+    ///
+    /// The sample rate is only used by [`run()`](#method.run), which rejects
+    /// a zero sample rate.
+    pub fn new<T>(dds: Ad983x<DEV, IC>, phases: T, sample_rate: u32) -> Self
+    where
+        T: IntoIterator<IntoIter = I>,
+    {
+        let table = phases.into_iter();
+        let mut samples = table.clone();
+        PhaseStreamer {
+            dds,
+            next: samples.next(),
+            table,
+            samples,
+            index: 0,
+            looping: false,
+            sample_rate,
+            clock: SymbolClock::new(sample_rate),
+        }
+    }
+
+    /// Start over from the first sample after the last one instead of finishing.
+    pub fn with_looping(self) -> Self {
+        PhaseStreamer {
+            looping: true,
+            ..self
+        }
+    }
+
+    /// Destroy the streamer and return the device.
+    pub fn destroy(self) -> Ad983x<DEV, IC> {
+        self.dds
+    }
+
+    /// Sample rate in Hz
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Index of the next sample in the table
+    pub fn position(&self) -> usize {
+        self.index
+    }
+
+    /// Whether all the samples have been written
+    ///
+    /// When looping, this is only the case for an empty table.
+    pub fn is_finished(&self) -> bool {
+        self.next.is_none()
+    }
+
+    /// Start over from the first sample
+    pub fn restart(&mut self) {
+        self.samples = self.table.clone();
+        self.next = self.samples.next();
+        self.index = 0;
+        self.clock = SymbolClock::new(self.sample_rate);
+    }
+
+    /// Fetch the sample following the current one
+    fn fetch(&mut self) {
+        self.index += 1;
+        self.next = self.samples.next();
+        if self.next.is_none() && self.looping {
+            self.samples = self.table.clone();
+            self.next = self.samples.next();
+            self.index = 0;
+        }
+    }
+}
+
+impl<'a, DEV, IC> PhaseStreamer<DEV, IC, Degrees<'a>> {
+    /// Create a new streamer playing a table of angles in degrees once.
+    ///
+    /// See [`new()`](#method.new).
+    pub fn from_degrees(dds: Ad983x<DEV, IC>, degrees: &'a [f32], sample_rate: u32) -> Self {
+        let phases: Degrees<'a> = degrees.iter().copied().map(Phase::from_degrees);
+        Self::new(dds, phases, sample_rate)
+    }
+}
+
+impl<'a, DEV, IC> PhaseStreamer<DEV, IC, RegisterValues<'a>> {
+    /// Create a new streamer playing a table of 12-bit phase register values once.
+    ///
+    /// Only the 12 LSBs of each value are taken into account.
+    /// See [`new()`](#method.new).
+    pub fn from_register_values(dds: Ad983x<DEV, IC>, values: &'a [u16], sample_rate: u32) -> Self {
+        let phases: RegisterValues<'a> = values.iter().copied().map(Phase::from_register_value);
+        Self::new(dds, phases, sample_rate)
+    }
+}
+
+impl<DEV, IC, I, SpiE, PinE> PhaseStreamer<DEV, IC, I>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+    I: Iterator<Item = Phase> + Clone,
+{
+    /// Write the next sample without waiting.
+    ///
+    /// This is intended to be called from a timer interrupt at the sample
+    /// rate. Returns the phase written, rounded to the 12-bit register
+    /// resolution, or `None` if the table is finished.
+    pub fn advance(&mut self) -> Result<Option<Phase>, Error<SpiE, PinE>> {
+        let phase = match self.next {
+            Some(phase) => phase.quantized(),
+            None => return Ok(None),
+        };
+        self.dds.switch_phase(phase.register_value())?;
+        self.fetch();
+        Ok(Some(phase))
+    }
+
+    /// Write the remaining samples, waiting for the sample period after each.
+    ///
+    /// The time needed for the SPI write adds to the sample period.
+    /// When looping, this stops at the end of the current repetition.
+    ///
+    /// Returns `Error::InvalidArgument` if the sample rate is zero.
+    pub fn run<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<SpiE, PinE>> {
+        if self.sample_rate == 0 {
+            return Err(Error::InvalidArgument);
+        }
+        while self.advance()?.is_some() {
+            delay.delay_ns(self.clock.next_period_ns());
+            if self.index == 0 {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,369 @@
+//! Frequency planning
+//!
+//! The devices produce the frequency `word × MCLK / 2^28` for a 28-bit tuning
+//! word. A [`Planner`] lists the exact frequencies achievable near a target,
+//! predicts the spurs of a tuning word and recommends the word to use.
+//!
+//! The predicted spurs are:
+//! - The images of the output around multiples of MCLK, which the
+//!   reconstruction filter has to remove. Their level follows the
+//!   `sin(x)/x` response of the DAC.
+//! - The harmonics of the output caused by the DAC nonlinearity, aliased into
+//!   the first Nyquist zone. Their level depends on the DAC and is not predicted.
+//! - The phase truncation spurs: only the 12 MSBs of the 28-bit phase
+//!   accumulator address the sine lookup table. If the 16 LSBs of the tuning
+//!   word are not all zero, the discarded phase bits create spurs of up to
+//!   -68 dBc.
+//!
+//! Everything here is pure computation and does not need a device.
+//!
+//! ```
+//! use ad983x::planner::{Planner, SpurKind};
+//!
+//! let planner = Planner::new(25_000_000);
+//! let plan = planner.recommend(1_000_000.0, 1_000.0).unwrap();
+//! // 1 MHz is not reachable exactly, but 1.000977 MHz has no truncation spurs.
+//! assert_eq!(0, plan.word & 0xFFFF);
+//! assert!((plan.frequency - 1_000_976.6).abs() < 0.1);
+//! assert_eq!(None, plan.truncation_spur_dbc());
+//! for spur in planner.spurs(plan.word) {
+//!     if let SpurKind::Image { .. } = spur.kind {
+//!         println!("image at {} Hz, {:?} dBc", spur.frequency, spur.level_dbc);
+//!     }
+//! }
+//! ```
+
+use crate::frequency;
+
+const WORD_LIMIT: u32 = 1 << 28;
+/// Bits of the phase accumulator output used to address the sine lookup table
+const PHASE_BITS: u32 = 12;
+const TRUNCATED_BITS: u32 = 28 - PHASE_BITS;
+
+/// A tuning word and the exact frequency it produces
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candidate {
+    /// 28-bit tuning word
+    pub word: u32,
+    /// Output frequency in Hz
+    pub frequency: f64,
+    /// Difference to the target frequency in Hz
+    pub error: f64,
+}
+
+impl Candidate {
+    fn new(mclk_hz: u32, word: u32, target: f64) -> Self {
+        let frequency = frequency::hz_from_word_f64(mclk_hz, word);
+        Candidate {
+            word,
+            frequency,
+            error: frequency - target,
+        }
+    }
+
+    /// Level of the largest phase truncation spur in dBc
+    ///
+    /// Returns `None` if the word causes no phase truncation.
+    pub fn truncation_spur_dbc(&self) -> Option<f32> {
+        truncation_spur_dbc(self.word)
+    }
+}
+
+/// Level of the largest phase truncation spur of a tuning word in dBc
+///
+/// The discarded phase bits repeat with a period of `M = 2^16 / gcd(word mod 2^16, 2^16)`
+/// samples and the largest spur is `2^-12 × (π/M) / sin(π/M)` relative to the
+/// output (Nicholas and Samueli).
+fn truncation_spur_dbc(word: u32) -> Option<f32> {
+    let truncated = word & ((1 << TRUNCATED_BITS) - 1);
+    if truncated == 0 {
+        return None;
+    }
+    let period = f64::from((1u32 << TRUNCATED_BITS) >> truncated.trailing_zeros());
+    let x = core::f64::consts::PI / period;
+    let level = (x / libm::sin(x)) / f64::from(1u32 << PHASE_BITS);
+    Some((20.0 * libm::log10(level)) as f32)
+}
+
+/// Origin of a spur
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SpurKind {
+    /// Image of the output at `order × MCLK ± output` (above the first
+    /// Nyquist zone)
+    Image {
+        /// Multiple of the master clock frequency
+        order: u8,
+    },
+    /// Harmonic of the output, aliased into the first Nyquist zone
+    Harmonic {
+        /// Harmonic number, 2 for the second harmonic
+        order: u8,
+    },
+    /// Phase truncation spur, aliased into the first Nyquist zone
+    PhaseTruncation,
+}
+
+/// A predicted spur
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spur {
+    /// Origin of the spur
+    pub kind: SpurKind,
+    /// Frequency in Hz
+    pub frequency: f64,
+    /// Level relative to the output in dBc, if it can be predicted
+    pub level_dbc: Option<f32>,
+}
+
+/// Frequency planner for a master clock frequency
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Planner {
+    mclk_hz: u32,
+    images: u8,
+    harmonics: u8,
+}
+
+impl Planner {
+    /// Create a planner for a master clock frequency in Hz.
+    ///
+    /// By default the images around MCLK and 2 × MCLK and the harmonics up to
+    /// the fifth are predicted.
+    pub fn new(mclk_hz: u32) -> Self {
+        Planner {
+            mclk_hz,
+            images: 2,
+            harmonics: 5,
+        }
+    }
+
+    /// Predict the images up to `order × MCLK`.
+    pub fn with_images(self, order: u8) -> Self {
+        Planner {
+            images: order,
+            ..self
+        }
+    }
+
+    /// Predict the harmonics up to `order`.
+    pub fn with_harmonics(self, order: u8) -> Self {
+        Planner {
+            harmonics: order,
+            ..self
+        }
+    }
+
+    /// Master clock frequency in Hz
+    pub fn mclk_frequency(&self) -> u32 {
+        self.mclk_hz
+    }
+
+    /// Frequency resolution (the step between tuning words) in Hz
+    pub fn resolution(&self) -> f64 {
+        frequency::hz_from_word_f64(self.mclk_hz, 1)
+    }
+
+    /// Candidate with the tuning word nearest to the target frequency in Hz
+    ///
+    /// This is the word [`Ad983x::set_frequency_hz()`](crate::Ad983x::set_frequency_hz)
+    /// uses. Returns `None` if the frequency is negative, not finite or its
+    /// tuning word does not fit in 28 bits.
+    pub fn nearest(&self, target: f64) -> Option<Candidate> {
+        let word = frequency::word_from_hz_f64(self.mclk_hz, target)?;
+        Some(Candidate::new(self.mclk_hz, word, target))
+    }
+
+    /// Achievable frequencies around the target frequency in Hz, nearest first
+    ///
+    /// The iterator is empty if there is no [`nearest()`](#method.nearest) word.
+    pub fn candidates(&self, target: f64) -> Candidates {
+        let nearest = self.nearest(target).map(|candidate| candidate.word);
+        Candidates {
+            mclk_hz: self.mclk_hz,
+            target,
+            below: nearest,
+            above: nearest.and_then(|word| word.checked_add(1)),
+        }
+    }
+
+    /// Recommended tuning word within `max_error` Hz of the target frequency
+    ///
+    /// Words without phase truncation spurs are preferred, then the words
+    /// with the lowest truncation spur level and finally the word nearest to
+    /// the target. Returns `None` if there is no word in this range.
+    pub fn recommend(&self, target: f64, max_error: f64) -> Option<Candidate> {
+        let nearest = self.nearest(target)?;
+        if nearest.error.abs() > max_error {
+            return None;
+        }
+        let step = 1 << TRUNCATED_BITS;
+        let below = nearest.word & !(step - 1);
+        let exact = [Some(below), below.checked_add(step)]
+            .into_iter()
+            .flatten()
+            .filter(|word| *word < WORD_LIMIT)
+            .map(|word| Candidate::new(self.mclk_hz, word, target))
+            .filter(|candidate| candidate.error.abs() <= max_error)
+            .min_by(|a, b| a.error.abs().total_cmp(&b.error.abs()));
+        if exact.is_some() {
+            return exact;
+        }
+        // The spur level only depends on the trailing zeros of the word and
+        // is lowest for odd words, so one of the nearest words is the best.
+        self.candidates(target)
+            .take(1 << 4)
+            .filter(|candidate| candidate.error.abs() <= max_error)
+            .fold(None, |best: Option<Candidate>, candidate| match best {
+                Some(best) if candidate.word.trailing_zeros() >= best.word.trailing_zeros() => {
+                    Some(best)
+                }
+                _ => Some(candidate),
+            })
+    }
+
+    /// Predicted spurs of a tuning word
+    pub fn spurs(&self, word: u32) -> Spurs {
+        Spurs {
+            mclk_hz: f64::from(self.mclk_hz),
+            output: frequency::hz_from_word_f64(self.mclk_hz, word),
+            truncation: truncation_spur_dbc(word).map(|level| {
+                let truncated = word & ((1 << TRUNCATED_BITS) - 1);
+                let rate = frequency::hz_from_word_f64(self.mclk_hz, truncated << PHASE_BITS);
+                (rate, level)
+            }),
+            images: self.images,
+            harmonics: self.harmonics,
+            index: 0,
+        }
+    }
+}
+
+/// Iterator over the achievable frequencies around a target, nearest first
+///
+/// See [`Planner::candidates()`].
+#[derive(Debug, Clone)]
+pub struct Candidates {
+    mclk_hz: u32,
+    target: f64,
+    below: Option<u32>,
+    above: Option<u32>,
+}
+
+impl Iterator for Candidates {
+    type Item = Candidate;
+
+    fn next(&mut self) -> Option<Candidate> {
+        let below = self
+            .below
+            .map(|word| Candidate::new(self.mclk_hz, word, self.target));
+        let above = self
+            .above
+            .filter(|word| *word < WORD_LIMIT)
+            .map(|word| Candidate::new(self.mclk_hz, word, self.target));
+        match (below, above) {
+            (Some(below), Some(above)) if above.error.abs() < below.error.abs() => {
+                self.above = above.word.checked_add(1);
+                Some(above)
+            }
+            (Some(below), _) => {
+                self.below = below.word.checked_sub(1);
+                Some(below)
+            }
+            (None, Some(above)) => {
+                self.above = above.word.checked_add(1);
+                Some(above)
+            }
+            (None, None) => None,
+        }
+    }
+}
+
+/// Fold a frequency into the first Nyquist zone
+fn fold(frequency: f64, mclk_hz: f64) -> f64 {
+    let frequency = libm::fmod(frequency.abs(), mclk_hz);
+    if frequency > mclk_hz / 2.0 {
+        mclk_hz - frequency
+    } else {
+        frequency
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x == 0.0 {
+        1.0
+    } else {
+        let x = core::f64::consts::PI * x;
+        libm::sin(x) / x
+    }
+}
+
+/// Iterator over the predicted spurs of a tuning word
+///
+/// See [`Planner::spurs()`]. This yields the images in increasing order,
+/// then the harmonics and finally the phase truncation spurs.
+#[derive(Debug, Clone)]
+pub struct Spurs {
+    mclk_hz: f64,
+    output: f64,
+    /// Rate of the discarded phase bits sawtooth and largest spur level
+    truncation: Option<(f64, f32)>,
+    images: u8,
+    harmonics: u8,
+    index: usize,
+}
+
+impl Spurs {
+    fn image(&self, index: usize) -> Spur {
+        let order = (index / 2 + 1) as u8;
+        let center = f64::from(order) * self.mclk_hz;
+        // The lower image comes first.
+        let frequency = match index % 2 {
+            0 => center - self.output,
+            _ => center + self.output,
+        };
+        let response = sinc(frequency / self.mclk_hz) / sinc(self.output / self.mclk_hz);
+        Spur {
+            kind: SpurKind::Image { order },
+            frequency,
+            level_dbc: Some((20.0 * libm::log10(response.abs())) as f32),
+        }
+    }
+
+    fn harmonic(&self, index: usize) -> Spur {
+        let order = (index + 2) as u8;
+        Spur {
+            kind: SpurKind::Harmonic { order },
+            frequency: fold(f64::from(order) * self.output, self.mclk_hz),
+            level_dbc: None,
+        }
+    }
+}
+
+impl Iterator for Spurs {
+    type Item = Spur;
+
+    fn next(&mut self) -> Option<Spur> {
+        let images = 2 * usize::from(self.images);
+        let harmonics = usize::from(self.harmonics.saturating_sub(1));
+        let index = self.index;
+        self.index += 1;
+        if index < images {
+            return Some(self.image(index));
+        }
+        let index = index - images;
+        if index < harmonics {
+            return Some(self.harmonic(index));
+        }
+        // The sawtooth of the discarded phase bits modulates the output.
+        let (rate, level) = self.truncation?;
+        let frequency = match index - harmonics {
+            0 => self.output - rate,
+            1 => self.output + rate,
+            _ => return None,
+        };
+        Some(Spur {
+            kind: SpurKind::PhaseTruncation,
+            frequency: fold(frequency, self.mclk_hz),
+            level_dbc: Some(level),
+        })
+    }
+}
@@ -0,0 +1,264 @@
+//! Phase-shift keying (PSK) modulation
+//!
+//! The [`PskModulator`] supports BPSK, QPSK and 8-PSK. Symbols are
+//! transmitted by selecting the phase register holding the symbol phase.
+//! When neither phase register holds it, the phase is first written into the
+//! register not in use, so that the output switches phase in a single step.
+//! For BPSK both symbol phases are loaded once and symbols only require
+//! selecting the phase register.
+//!
+//! ```no_run
+//! use ad983x::{psk::{PskConfig, PskModulator, PskOrder}, Ad983x, FrequencyRegister};
+//! use embedded_hal_bus::spi::ExclusiveDevice;
+//! use linux_embedded_hal::{Delay, SpidevBus, SysfsPin};
+//!
+//! let spi = SpidevBus::open("/dev/spidev0.0").unwrap();
+//! let chip_select = SysfsPin::new(25);
+//! let dev = ExclusiveDevice::new(spi, chip_select, Delay);
+//! let mut dds = Ad983x::new_ad9833(dev);
+//! dds.reset().unwrap();
+//! dds.set_frequency_hz(FrequencyRegister::F0, 10_000.0).unwrap();
+//! dds.enable().unwrap();
+//! let mut psk = PskModulator::new(dds, PskConfig::new(PskOrder::Qpsk, 1000));
+//! psk.init().unwrap();
+//! psk.send_bytes(b"hello", &mut Delay).unwrap();
+//! ```
+
+use embedded_hal::delay::DelayNs;
+
+use crate::{interface::WriteData, timing::SymbolClock, Ad983x, Error, Phase, PhaseRegister};
+
+/// Modulation order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PskOrder {
+    /// Binary PSK: 2 phases, 1 bit per symbol
+    Bpsk,
+    /// Quadrature PSK: 4 phases, 2 bits per symbol
+    Qpsk,
+    /// 8-PSK: 8 phases, 3 bits per symbol
+    Psk8,
+}
+
+impl PskOrder {
+    /// Number of bits carried by each symbol
+    pub fn bits_per_symbol(self) -> u8 {
+        match self {
+            PskOrder::Bpsk => 1,
+            PskOrder::Qpsk => 2,
+            PskOrder::Psk8 => 3,
+        }
+    }
+
+    /// Number of different symbols
+    pub fn symbol_count(self) -> u8 {
+        1 << self.bits_per_symbol()
+    }
+}
+
+/// Mapping of the bits of a symbol to its phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SymbolMapping {
+    /// Gray code: neighboring phases differ in a single bit (default)
+    Gray,
+    /// Natural binary: the symbol value is the phase index
+    Binary,
+}
+
+/// PSK modulation configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PskConfig {
+    order: PskOrder,
+    symbol_rate: u32,
+    mapping: SymbolMapping,
+    phase_offset: Phase,
+}
+
+impl PskConfig {
+    /// Create a configuration with a modulation order and a symbol rate in baud.
+    ///
+    /// Gray coding and no phase offset are used.
+    pub fn new(order: PskOrder, symbol_rate: u32) -> Self {
+        PskConfig {
+            order,
+            symbol_rate,
+            mapping: SymbolMapping::Gray,
+            phase_offset: Phase::ZERO,
+        }
+    }
+
+    /// Set the bit-to-symbol mapping.
+    pub fn with_mapping(self, mapping: SymbolMapping) -> Self {
+        PskConfig { mapping, ..self }
+    }
+
+    /// Set the phase of the symbol with phase index zero.
+    pub fn with_phase_offset(self, phase_offset: Phase) -> Self {
+        PskConfig {
+            phase_offset,
+            ..self
+        }
+    }
+
+    /// Modulation order
+    pub fn order(&self) -> PskOrder {
+        self.order
+    }
+
+    /// Symbol rate in baud
+    pub fn symbol_rate(&self) -> u32 {
+        self.symbol_rate
+    }
+
+    /// Bit-to-symbol mapping
+    pub fn mapping(&self) -> SymbolMapping {
+        self.mapping
+    }
+
+    /// Phase of the symbol with phase index zero
+    pub fn phase_offset(&self) -> Phase {
+        self.phase_offset
+    }
+
+    /// Phase used to transmit a symbol
+    ///
+    /// Only the bits fitting in a symbol of the modulation order are taken into account.
+    pub fn symbol_phase(&self, symbol: u8) -> Phase {
+        let bits = self.order.bits_per_symbol();
+        let symbol = symbol & (self.order.symbol_count() - 1);
+        let index = match self.mapping {
+            SymbolMapping::Binary => symbol,
+            SymbolMapping::Gray => {
+                // Inverse Gray code
+                let mut index = symbol;
+                let mut shifted = symbol >> 1;
+                while shifted != 0 {
+                    index ^= shifted;
+                    shifted >>= 1;
+                }
+                index
+            }
+        };
+        self.phase_offset + Phase::from_raw(u32::from(index) << (32 - bits))
+    }
+}
+
+/// PSK modulator using the two phase registers of a device
+///
+/// The symbol duration is timed with a delay after switching to each symbol
+/// phase, so the time needed for the SPI writes adds to it.
+/// On AD9834/AD9838 devices with the hardware pin control source selected
+/// this requires the PSELECT pin to be assigned.
+#[derive(Debug)]
+pub struct PskModulator<DEV, IC> {
+    dds: Ad983x<DEV, IC>,
+    config: PskConfig,
+    clock: SymbolClock,
+}
+
+impl<DEV, IC> PskModulator<DEV, IC> {
+    /// Create a new modulator. The device should already be reset, have its
+    /// frequency set and be enabled.
+    ///
+    /// Call [`init()`](#method.init) to load the phases before transmitting.
+    pub fn new(dds: Ad983x<DEV, IC>, config: PskConfig) -> Self {
+        PskModulator {
+            dds,
+            config,
+            clock: SymbolClock::new(config.symbol_rate),
+        }
+    }
+
+    /// Destroy the modulator and return the device.
+    pub fn destroy(self) -> Ad983x<DEV, IC> {
+        self.dds
+    }
+
+    /// Modulation configuration
+    pub fn config(&self) -> &PskConfig {
+        &self.config
+    }
+}
+
+impl<DEV, IC, SpiE, PinE> PskModulator<DEV, IC>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+{
+    /// Load the phases of the symbols 0 and 1 into the phase registers 0 and 1
+    /// and output the symbol 0.
+    ///
+    /// Returns `Error::InvalidArgument` if the symbol rate is zero.
+    pub fn init(&mut self) -> Result<(), Error<SpiE, PinE>> {
+        if self.config.symbol_rate == 0 {
+            return Err(Error::InvalidArgument);
+        }
+        for (register, symbol) in [(PhaseRegister::P0, 0), (PhaseRegister::P1, 1)] {
+            let value = self.config.symbol_phase(symbol).register_value();
+            self.dds.set_phase(register, value)?;
+        }
+        self.dds.select_phase(PhaseRegister::P0)?;
+        self.clock = SymbolClock::new(self.config.symbol_rate);
+        Ok(())
+    }
+
+    /// Switch the output to a symbol without waiting.
+    ///
+    /// Only the bits fitting in a symbol of the modulation order are taken into account.
+    pub fn set_symbol(&mut self, symbol: u8) -> Result<(), Error<SpiE, PinE>> {
+        let value = self.config.symbol_phase(symbol).register_value();
+        self.dds.switch_phase(value)
+    }
+
+    /// Transmit a single symbol, waiting for one symbol period.
+    pub fn send_symbol<D: DelayNs>(
+        &mut self,
+        symbol: u8,
+        delay: &mut D,
+    ) -> Result<(), Error<SpiE, PinE>> {
+        self.set_symbol(symbol)?;
+        delay.delay_ns(self.clock.next_period_ns());
+        Ok(())
+    }
+
+    /// Transmit a sequence of bits grouped into symbols, first bit as symbol MSB.
+    ///
+    /// If the number of bits is not a multiple of the bits per symbol,
+    /// the last symbol is padded with zeros.
+    pub fn send_bits<I, D>(&mut self, bits: I, delay: &mut D) -> Result<(), Error<SpiE, PinE>>
+    where
+        I: IntoIterator<Item = bool>,
+        D: DelayNs,
+    {
+        let bits_per_symbol = self.config.order.bits_per_symbol();
+        let mut symbol = 0;
+        let mut count = 0;
+        for bit in bits {
+            symbol = (symbol << 1) | u8::from(bit);
+            count += 1;
+            if count == bits_per_symbol {
+                self.send_symbol(symbol, delay)?;
+                symbol = 0;
+                count = 0;
+            }
+        }
+        if count != 0 {
+            self.send_symbol(symbol << (bits_per_symbol - count), delay)?;
+        }
+        Ok(())
+    }
+
+    /// Transmit bytes MSB first, grouping the bits into symbols.
+    ///
+    /// See [`send_bits()`](#method.send_bits).
+    pub fn send_bytes<D: DelayNs>(
+        &mut self,
+        bytes: &[u8],
+        delay: &mut D,
+    ) -> Result<(), Error<SpiE, PinE>> {
+        let bits = bytes
+            .iter()
+            .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1 != 0));
+        self.send_bits(bits, delay)
+    }
+}
@@ -0,0 +1,171 @@
+//! Quadrature (I/Q) signal pairs
+//!
+//! A [`QuadratureGenerator`] drives two devices clocked from the same MCLK
+//! at the same frequency, with the second output (Q) shifted by a fixed phase
+//! offset from the first one (I), 90° by default.
+//!
+//! The two devices cannot change their frequency at exactly the same time,
+//! so while the frequency changes their phase accumulators drift apart.
+//! For this reason every frequency change holds both devices in reset, loads
+//! the new tuning word and releases them together, which restarts both
+//! outputs aligned. Changing the offset only writes a phase register, which
+//! takes effect immediately.
+//!
+//! ```no_run
+//! use ad983x::{quadrature::QuadratureGenerator, Ad983x};
+//! use linux_embedded_hal::SpidevDevice;
+//!
+//! let i = Ad983x::new_ad9833(SpidevDevice::open("/dev/spidev0.0").unwrap());
+//! let q = Ad983x::new_ad9833(SpidevDevice::open("/dev/spidev0.1").unwrap());
+//! // The Q device leaves reset about 4 µs after the I device.
+//! let mut generator = QuadratureGenerator::new(i, q).with_release_skew_ns(4_000);
+//! generator.set_frequency_hz(1_000.0).unwrap();
+//! ```
+
+use crate::{array::DdsArray, frequency, interface::WriteData, Ad983x, Error, Phase};
+
+/// Two devices generating a signal and a phase-shifted copy of it
+///
+/// The first device outputs the in-phase signal (I) and the second one the
+/// signal shifted by the offset (Q). The frequency and phase registers currently
+/// selected on each device are used.
+#[derive(Debug)]
+pub struct QuadratureGenerator<DEV, IC> {
+    array: DdsArray<DEV, IC, 2>,
+    offset: Phase,
+    release_skew_ns: u32,
+}
+
+impl<DEV, IC> QuadratureGenerator<DEV, IC> {
+    /// Create a new generator with a 90° offset.
+    pub fn new(i: Ad983x<DEV, IC>, q: Ad983x<DEV, IC>) -> Self {
+        QuadratureGenerator {
+            array: DdsArray::new([i, q]),
+            offset: Phase::from_raw(1 << 30),
+            release_skew_ns: 0,
+        }
+    }
+
+    /// Use a different offset of the Q output.
+    ///
+    /// This takes effect with the next frequency change.
+    /// See [`set_offset()`](#method.set_offset) to change it right away.
+    pub fn with_offset(self, offset: Phase) -> Self {
+        QuadratureGenerator { offset, ..self }
+    }
+
+    /// Compensate the time between releasing the I and the Q device from reset
+    /// in nanoseconds.
+    ///
+    /// The Q device starts later and lags behind by this time, which is added
+    /// to its phase register at every frequency change. This is about the
+    /// duration of an SPI transaction.
+    pub fn with_release_skew_ns(self, release_skew_ns: u32) -> Self {
+        QuadratureGenerator {
+            release_skew_ns,
+            ..self
+        }
+    }
+
+    /// Destroy the generator and return the I and Q devices.
+    pub fn destroy(self) -> (Ad983x<DEV, IC>, Ad983x<DEV, IC>) {
+        let [i, q] = self.array.destroy();
+        (i, q)
+    }
+
+    /// Offset of the Q output
+    pub fn offset(&self) -> Phase {
+        self.offset
+    }
+
+    /// Tuning word currently used by both devices
+    ///
+    /// Returns `None` until a frequency has been set.
+    pub fn frequency(&self) -> Option<u32> {
+        let [i, q] = self.array.channels();
+        let word = i.frequency(i.selected_frequency())?;
+        if q.frequency(q.selected_frequency()) == Some(word) {
+            Some(word)
+        } else {
+            None
+        }
+    }
+
+    /// Phase register values of the I and Q devices for a tuning word
+    fn phases(&self, word: u32) -> [u16; 2] {
+        let mclk_hz = self.array.channels()[0].corrected_mclk_frequency();
+        // Lag in units of 2^-32 turns: word / 2^28 * mclk * skew / 10^9 * 2^32
+        let lag = u128::from(word) * u128::from(mclk_hz) * u128::from(self.release_skew_ns) * 16
+            / 1_000_000_000;
+        let q = self.offset + Phase::from_raw(lag as u32);
+        [0, q.register_value()]
+    }
+}
+
+impl<DEV, IC, SpiE, PinE> QuadratureGenerator<DEV, IC>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+{
+    /// Set the frequency of both outputs as a 28-bit word.
+    ///
+    /// Unless both devices already output this frequency, they are held in
+    /// reset, loaded with the word and the phase offsets and released again.
+    /// Returns `Error::InvalidArgument` if providing a value that does not fit in 28 bits.
+    pub fn set_frequency(&mut self, word: u32) -> Result<(), Error<SpiE, PinE>> {
+        if word >= 1 << 28 {
+            return Err(Error::InvalidArgument);
+        }
+        let [i, q] = self.array.channels();
+        if self.frequency() == Some(word) && !i.is_reset() && !q.is_reset() {
+            return Ok(());
+        }
+        let phases = self.phases(word);
+        self.array.hold_reset()?;
+        self.array.configure(|index, dds| {
+            let register = dds.selected_frequency();
+            if dds.frequency(register) != Some(word) {
+                dds.set_frequency(register, word)?;
+            }
+            let register = dds.selected_phase();
+            if dds.phase(register) != Some(phases[index]) {
+                dds.set_phase(register, phases[index])?;
+            }
+            Ok(())
+        })?;
+        self.array.release()
+    }
+
+    /// Set the frequency of both outputs in Hz.
+    ///
+    /// See [`set_frequency()`](#method.set_frequency).
+    /// Returns the frequency actually produced by the devices.
+    /// Returns `Error::InvalidArgument` if the frequency is negative, not finite or
+    /// its tuning word does not fit in 28 bits.
+    pub fn set_frequency_hz(&mut self, frequency: f32) -> Result<f32, Error<SpiE, PinE>> {
+        let mclk_hz = self.array.channels()[0].corrected_mclk_frequency();
+        let word = frequency::word_from_hz(mclk_hz, frequency).ok_or(Error::InvalidArgument)?;
+        self.set_frequency(word)?;
+        Ok(frequency::hz_from_word(mclk_hz, word))
+    }
+
+    /// Change the offset of the Q output right away.
+    ///
+    /// This only writes the phase register of the Q device.
+    pub fn set_offset(&mut self, offset: Phase) -> Result<(), Error<SpiE, PinE>> {
+        self.offset = offset;
+        let [_, phase] = self.phases(self.frequency().unwrap_or(0));
+        let q = &mut self.array.channels_mut()[1];
+        let register = q.selected_phase();
+        if q.phase(register) != Some(phase) {
+            q.set_phase(register, phase)?;
+        }
+        Ok(())
+    }
+
+    /// Restart both outputs aligned by holding both devices in reset and
+    /// releasing them together.
+    pub fn realign(&mut self) -> Result<(), Error<SpiE, PinE>> {
+        self.array.hold_reset()?;
+        self.array.release()
+    }
+}
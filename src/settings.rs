@@ -0,0 +1,226 @@
+//! Device settings snapshots for storing presets
+//!
+//! [`DeviceSettings`] holds a complete device configuration like
+//! [`DeviceConfig`] with human-friendly units: frequencies in Hz and phases in
+//! degrees. It implements `serde::Serialize` and `serde::Deserialize`, so that
+//! presets can be stored for example in TOML or JSON files.
+//! Deserialized settings are validated against the limits of the chip they
+//! are meant for. Only available with the `serde` feature.
+//!
+//! ```
+//! use ad983x::{settings::{Chip, DeviceSettings}, DeviceConfig, OutputWaveform};
+//!
+//! let config = DeviceConfig {
+//!     frequency0: 4724,
+//!     phase0: 1024,
+//!     output_waveform: OutputWaveform::Triangle,
+//!     ..Default::default()
+//! };
+//! let settings = DeviceSettings::from_config(Chip::Ad9833, 25_000_000, &config);
+//! assert_eq!(90.0, settings.phase0_degrees);
+//! assert_eq!(Ok(config), settings.to_config());
+//! ```
+//!
+//! A preset in TOML format:
+//!
+//! ```toml
+//! chip = "ad9838"
+//! mclk_hz = 16000000
+//! frequency0_hz = 1000.0
+//! frequency1_hz = 2500.5
+//! phase0_degrees = 0.0
+//! phase1_degrees = 90.0
+//! selected_frequency = "f1"
+//! selected_phase = "p0"
+//! output_waveform = "sinusoidal"
+//! sign_bit_output = "comparator"
+//! powered_down = "nothing"
+//! control_source = "software"
+//! ```
+
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    frequency, ControlSource, DeviceConfig, FrequencyRegister, OutputWaveform, Phase,
+    PhaseRegister, PoweredDown, SignBitOutput,
+};
+
+/// Device model the settings are meant for
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[serde(rename_all = "lowercase")]
+pub enum Chip {
+    /// AD9833
+    Ad9833,
+    /// AD9834
+    Ad9834,
+    /// AD9837
+    Ad9837,
+    /// AD9838
+    Ad9838,
+}
+
+/// Settings validation errors
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SettingsError {
+    /// A frequency is negative, not finite or not below the master clock frequency
+    FrequencyOutOfRange,
+    /// A phase is not finite or outside of `[0, 360)` degrees
+    PhaseOutOfRange,
+    /// A setting or a combination of settings is not available on the chip
+    Unsupported,
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::FrequencyOutOfRange => {
+                f.write_str("frequency does not fit in the 28-bit frequency register")
+            }
+            SettingsError::PhaseOutOfRange => f.write_str("phase is outside of [0, 360) degrees"),
+            SettingsError::Unsupported => f.write_str("setting not available on the chip"),
+        }
+    }
+}
+
+/// Complete device settings with frequencies in Hz and phases in degrees
+///
+/// The frequencies are rounded to the nearest tuning word and the phases to
+/// the nearest 12-bit phase register value when converted with
+/// [`to_config()`](#method.to_config).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Unvalidated")]
+pub struct DeviceSettings {
+    /// Device model
+    pub chip: Chip,
+    /// Master clock (MCLK) frequency in Hz
+    pub mclk_hz: u32,
+    /// Frequency register 0 in Hz
+    pub frequency0_hz: f64,
+    /// Frequency register 1 in Hz
+    pub frequency1_hz: f64,
+    /// Phase register 0 in degrees
+    pub phase0_degrees: f32,
+    /// Phase register 1 in degrees
+    pub phase1_degrees: f32,
+    /// Frequency register used
+    pub selected_frequency: FrequencyRegister,
+    /// Phase register used
+    pub selected_phase: PhaseRegister,
+    /// Output waveform
+    pub output_waveform: OutputWaveform,
+    /// Sign bit output (only available on AD9834/AD9838 devices)
+    pub sign_bit_output: SignBitOutput,
+    /// Powered-down device parts
+    pub powered_down: PoweredDown,
+    /// Control source (only available on AD9834/AD9838 devices)
+    pub control_source: ControlSource,
+}
+
+impl DeviceSettings {
+    /// Create the settings from a register configuration.
+    pub fn from_config(chip: Chip, mclk_hz: u32, config: &DeviceConfig) -> Self {
+        let hz = |word| frequency::hz_from_word_f64(mclk_hz, word);
+        let degrees = |value| Phase::from_register_value(value).degrees();
+        DeviceSettings {
+            chip,
+            mclk_hz,
+            frequency0_hz: hz(config.frequency0),
+            frequency1_hz: hz(config.frequency1),
+            phase0_degrees: degrees(config.phase0),
+            phase1_degrees: degrees(config.phase1),
+            selected_frequency: config.selected_frequency,
+            selected_phase: config.selected_phase,
+            output_waveform: config.output_waveform,
+            sign_bit_output: config.sign_bit_output,
+            powered_down: config.powered_down,
+            control_source: config.control_source,
+        }
+    }
+
+    /// Convert the settings into a register configuration which can be
+    /// brought to a device with `apply()`.
+    ///
+    /// This checks the settings against the limits of the chip.
+    pub fn to_config(&self) -> Result<DeviceConfig, SettingsError> {
+        let word = |hz| {
+            frequency::word_from_hz_f64(self.mclk_hz, hz).ok_or(SettingsError::FrequencyOutOfRange)
+        };
+        let value = |degrees: f32| {
+            if (0.0..360.0).contains(&degrees) {
+                Ok(Phase::from_degrees(degrees).register_value())
+            } else {
+                Err(SettingsError::PhaseOutOfRange)
+            }
+        };
+        let config = DeviceConfig {
+            frequency0: word(self.frequency0_hz)?,
+            frequency1: word(self.frequency1_hz)?,
+            phase0: value(self.phase0_degrees)?,
+            phase1: value(self.phase1_degrees)?,
+            selected_frequency: self.selected_frequency,
+            selected_phase: self.selected_phase,
+            output_waveform: self.output_waveform,
+            sign_bit_output: self.sign_bit_output,
+            powered_down: self.powered_down,
+            control_source: self.control_source,
+        };
+        let supported = match self.chip {
+            Chip::Ad9833 | Chip::Ad9837 => config.control_ad9833::<(), ()>().is_ok(),
+            Chip::Ad9834 | Chip::Ad9838 => config.control_ad9834::<(), ()>().is_ok(),
+        };
+        if supported {
+            Ok(config)
+        } else {
+            Err(SettingsError::Unsupported)
+        }
+    }
+
+    /// Check the settings against the limits of the chip.
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        self.to_config().map(|_| ())
+    }
+}
+
+/// Deserialized settings before validation
+#[derive(Deserialize)]
+struct Unvalidated {
+    chip: Chip,
+    mclk_hz: u32,
+    frequency0_hz: f64,
+    frequency1_hz: f64,
+    phase0_degrees: f32,
+    phase1_degrees: f32,
+    selected_frequency: FrequencyRegister,
+    selected_phase: PhaseRegister,
+    output_waveform: OutputWaveform,
+    sign_bit_output: SignBitOutput,
+    powered_down: PoweredDown,
+    control_source: ControlSource,
+}
+
+impl TryFrom<Unvalidated> for DeviceSettings {
+    type Error = SettingsError;
+
+    fn try_from(raw: Unvalidated) -> Result<Self, Self::Error> {
+        let settings = DeviceSettings {
+            chip: raw.chip,
+            mclk_hz: raw.mclk_hz,
+            frequency0_hz: raw.frequency0_hz,
+            frequency1_hz: raw.frequency1_hz,
+            phase0_degrees: raw.phase0_degrees,
+            phase1_degrees: raw.phase1_degrees,
+            selected_frequency: raw.selected_frequency,
+            selected_phase: raw.selected_phase,
+            output_waveform: raw.output_waveform,
+            sign_bit_output: raw.sign_bit_output,
+            powered_down: raw.powered_down,
+            control_source: raw.control_source,
+        };
+        settings.validate()?;
+        Ok(settings)
+    }
+}
@@ -0,0 +1,405 @@
+//! Software simulation of the devices
+//!
+//! [`Simulator`] is a virtual AD9833/AD9834/AD9837/AD9838 implementing the
+//! `embedded_hal::spi::SpiDevice` trait (and the `embedded-hal-async` one
+//! when the `async` feature is enabled). It decodes the 16-bit words written
+//! to it, keeps the complete register file and the phase accumulator and can
+//! render the output signals, so that application logic can be tested
+//! without hardware. Only available with the `sim` feature.
+//!
+//! ```
+//! use ad983x::{sim::{Sample, Simulator}, Ad983x, FrequencyRegister};
+//!
+//! let mut dds = Ad983x::new_ad9833(Simulator::new_ad9833(1_000_000));
+//! dds.set_mclk_frequency(1_000_000);
+//! dds.reset().unwrap();
+//! dds.set_frequency_hz(FrequencyRegister::F0, 1_000.0).unwrap();
+//! dds.enable().unwrap();
+//! let mut sim = dds.destroy();
+//!
+//! let mut samples = [Sample::default(); 8];
+//! sim.render(8_000, &mut samples);
+//! assert!(samples[1].vout.unwrap() > 0.99); // a quarter period
+//! ```
+//!
+//! Simplifications: the square wave on VOUT and the sign bit output are
+//! rendered as -1 (low) and +1 (high), the comparator output is the MSB of
+//! the phase, as it would be with an ideal filter, and the timing of the
+//! writes relative to MCLK is not modeled.
+
+use core::{convert::Infallible, f64::consts::PI};
+
+use embedded_hal::spi::{ErrorType, Operation, SpiDevice};
+
+use crate::{BitFlags, FrequencyRegister, PhaseRegister};
+
+const ACCUMULATOR_MASK: u64 = (1 << 28) - 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Model {
+    Ad9833Ad9837,
+    Ad9834Ad9838,
+}
+
+/// Level of the function control pins of AD9834/AD9838 devices
+///
+/// These are only taken into account if the hardware pin control
+/// source is selected.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct HardwarePins {
+    /// FSELECT pin: selects frequency register 1 when high
+    pub fselect: bool,
+    /// PSELECT pin: selects phase register 1 when high
+    pub pselect: bool,
+    /// RESET pin: holds the device in reset when high
+    pub reset: bool,
+    /// SLEEP pin: powers down the DAC when high
+    pub sleep: bool,
+}
+
+/// Output sample
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Sample {
+    /// Analog output (VOUT/IOUT) normalized to `[-1, 1]` or
+    /// `None` if the DAC is powered down.
+    pub vout: Option<f32>,
+    /// Digital SIGN BIT OUT pin or `None` if it is in high impedance.
+    /// On AD9833/AD9837 devices this is the square wave output on VOUT.
+    pub sign_bit: Option<bool>,
+}
+
+/// Simulated device
+#[derive(Debug, Clone)]
+pub struct Simulator {
+    model: Model,
+    mclk_hz: u32,
+    control: u16,
+    frequency: [u32; 2],
+    phase: [u16; 2],
+    pending_lsb: Option<(usize, u16)>,
+    pins: HardwarePins,
+    accumulator: u64,
+    sample_rate_hz: u32,
+    sample_remainder: u64,
+    partial_byte: Option<u8>,
+    word_count: usize,
+}
+
+impl Simulator {
+    fn new(model: Model, mclk_hz: u32) -> Self {
+        Simulator {
+            model,
+            mclk_hz,
+            control: 0,
+            frequency: [0; 2],
+            phase: [0; 2],
+            pending_lsb: None,
+            pins: HardwarePins::default(),
+            accumulator: 0,
+            sample_rate_hz: 0,
+            sample_remainder: 0,
+            partial_byte: None,
+            word_count: 0,
+        }
+    }
+
+    /// Create a simulated AD9833 clocked at `mclk_hz`.
+    pub fn new_ad9833(mclk_hz: u32) -> Self {
+        Self::new(Model::Ad9833Ad9837, mclk_hz)
+    }
+
+    /// Create a simulated AD9837 clocked at `mclk_hz`.
+    pub fn new_ad9837(mclk_hz: u32) -> Self {
+        Self::new(Model::Ad9833Ad9837, mclk_hz)
+    }
+
+    /// Create a simulated AD9834 clocked at `mclk_hz`.
+    pub fn new_ad9834(mclk_hz: u32) -> Self {
+        Self::new(Model::Ad9834Ad9838, mclk_hz)
+    }
+
+    /// Create a simulated AD9838 clocked at `mclk_hz`.
+    pub fn new_ad9838(mclk_hz: u32) -> Self {
+        Self::new(Model::Ad9834Ad9838, mclk_hz)
+    }
+
+    /// Master clock frequency in Hz
+    pub fn mclk_frequency(&self) -> u32 {
+        self.mclk_hz
+    }
+
+    /// Control register content (14 bits)
+    pub fn control(&self) -> u16 {
+        self.control
+    }
+
+    /// Frequency register content (28 bits)
+    pub fn frequency(&self, register: FrequencyRegister) -> u32 {
+        self.frequency[register as usize]
+    }
+
+    /// Phase register content (12 bits)
+    pub fn phase(&self, register: PhaseRegister) -> u16 {
+        self.phase[register as usize]
+    }
+
+    /// Frequency register with a 28-bit write of which only the LSBs have
+    /// been received, if any.
+    pub fn pending_frequency_write(&self) -> Option<FrequencyRegister> {
+        self.pending_lsb
+            .map(|(index, _)| Self::frequency_register(index))
+    }
+
+    /// Number of 16-bit words received
+    pub fn word_count(&self) -> usize {
+        self.word_count
+    }
+
+    /// Phase accumulator content (28 bits)
+    pub fn accumulator(&self) -> u32 {
+        (self.accumulator & ACCUMULATOR_MASK) as u32
+    }
+
+    /// Set the level of the function control pins (AD9834/AD9838 only).
+    pub fn set_hardware_pins(&mut self, pins: HardwarePins) {
+        self.pins = pins;
+    }
+
+    fn frequency_register(index: usize) -> FrequencyRegister {
+        if index == 0 {
+            FrequencyRegister::F0
+        } else {
+            FrequencyRegister::F1
+        }
+    }
+
+    fn control_bit(&self, mask: u16) -> bool {
+        self.control & mask != 0
+    }
+
+    fn hardware_control(&self) -> bool {
+        self.model == Model::Ad9834Ad9838 && self.control_bit(BitFlags::PIN_SW)
+    }
+
+    /// Frequency register currently used for the output
+    pub fn selected_frequency(&self) -> FrequencyRegister {
+        let selected = if self.hardware_control() {
+            self.pins.fselect
+        } else {
+            self.control_bit(BitFlags::FSELECT)
+        };
+        Self::frequency_register(usize::from(selected))
+    }
+
+    /// Phase register currently used for the output
+    pub fn selected_phase(&self) -> PhaseRegister {
+        let selected = if self.hardware_control() {
+            self.pins.pselect
+        } else {
+            self.control_bit(BitFlags::PSELECT)
+        };
+        if selected {
+            PhaseRegister::P1
+        } else {
+            PhaseRegister::P0
+        }
+    }
+
+    /// Whether the device is held in reset
+    pub fn is_reset(&self) -> bool {
+        if self.hardware_control() {
+            self.pins.reset
+        } else {
+            self.control_bit(BitFlags::RESET)
+        }
+    }
+
+    fn dac_powered_down(&self) -> bool {
+        if self.hardware_control() {
+            self.pins.sleep
+        } else {
+            self.control_bit(BitFlags::SLEEP_DAC)
+        }
+    }
+
+    /// Process a 16-bit word as written by the SPI interface.
+    pub fn write_word(&mut self, word: u16) {
+        self.word_count += 1;
+        let data = word & 0x3FFF;
+        match word >> 14 {
+            0b00 => {
+                self.control = data;
+                if self.is_reset() {
+                    self.accumulator = 0;
+                }
+            }
+            0b11 => {
+                let index = usize::from(word & BitFlags::D13 != 0);
+                self.phase[index] = word & 0xFFF;
+            }
+            register => {
+                let index = usize::from(register == 0b10);
+                self.write_frequency(index, data);
+            }
+        }
+    }
+
+    fn write_frequency(&mut self, index: usize, data: u16) {
+        let value = self.frequency[index];
+        if self.control_bit(BitFlags::B28) {
+            match self.pending_lsb.take() {
+                Some((pending, lsb)) if pending == index => {
+                    self.frequency[index] = (u32::from(data) << 14) | u32::from(lsb);
+                }
+                _ => self.pending_lsb = Some((index, data)),
+            }
+        } else if self.control_bit(BitFlags::HLB) {
+            self.frequency[index] = (value & 0x3FFF) | (u32::from(data) << 14);
+        } else {
+            self.frequency[index] = (value & !0x3FFF) | u32::from(data);
+        }
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            match self.partial_byte.take() {
+                Some(msb) => self.write_word(u16::from_be_bytes([msb, *byte])),
+                None => self.partial_byte = Some(*byte),
+            }
+        }
+    }
+
+    /// Advance the time by a number of MCLK cycles.
+    pub fn advance(&mut self, mclk_cycles: u64) {
+        if self.is_reset() {
+            self.accumulator = 0;
+        } else if !self.control_bit(BitFlags::SLEEP_MCLK) {
+            let word = u64::from(self.frequency[self.selected_frequency() as usize]);
+            self.accumulator = self
+                .accumulator
+                .wrapping_add(word.wrapping_mul(mclk_cycles));
+        }
+    }
+
+    /// Current output
+    pub fn output(&self) -> Sample {
+        let phase = u64::from(self.phase[self.selected_phase() as usize]);
+        // The phase register is added to the 12 MSBs of the accumulator.
+        // Bit 28 is kept for the MSB/2 outputs.
+        let accumulator = self.accumulator.wrapping_add(phase << 16);
+        let msb = accumulator & (1 << 27) != 0;
+        let msb_div2 = accumulator & (1 << 28) != 0;
+        let digital = if self.control_bit(BitFlags::DIV2) {
+            msb
+        } else {
+            msb_div2
+        };
+        let opbiten = self.control_bit(BitFlags::OPBITEN);
+        let analog = if self.control_bit(BitFlags::MODE) {
+            // Triangle from the 10 MSBs
+            let x = ((accumulator & ACCUMULATOR_MASK) >> 18) as f32 / 1024.0;
+            if x < 0.5 {
+                4.0 * x - 1.0
+            } else {
+                3.0 - 4.0 * x
+            }
+        } else {
+            // Sine from the 12 MSBs quantized by the 10-bit DAC
+            let x = ((accumulator & ACCUMULATOR_MASK) >> 16) as f64 / 4096.0;
+            let code = ((libm::sin(2.0 * PI * x) + 1.0) / 2.0 * 1023.0 + 0.5) as u16;
+            (f64::from(code) / 1023.0 * 2.0 - 1.0) as f32
+        };
+        let square = |level: bool| if level { 1.0 } else { -1.0 };
+        match self.model {
+            Model::Ad9833Ad9837 => {
+                let vout = if self.dac_powered_down() && !opbiten {
+                    None
+                } else if opbiten {
+                    Some(square(digital))
+                } else {
+                    Some(analog)
+                };
+                Sample {
+                    vout,
+                    sign_bit: if opbiten { Some(digital) } else { None },
+                }
+            }
+            Model::Ad9834Ad9838 => {
+                let sign_bit = if !opbiten {
+                    None
+                } else if self.control_bit(BitFlags::SIGN_PIB) {
+                    Some(msb)
+                } else {
+                    Some(digital)
+                };
+                Sample {
+                    vout: if self.dac_powered_down() {
+                        None
+                    } else {
+                        Some(analog)
+                    },
+                    sign_bit,
+                }
+            }
+        }
+    }
+
+    /// Render output samples at a sample rate in Hz.
+    ///
+    /// The time advances by one sample period before each sample is taken.
+    /// Fractional MCLK cycles are carried over between calls for the same
+    /// sample rate.
+    pub fn render(&mut self, sample_rate_hz: u32, samples: &mut [Sample]) {
+        if sample_rate_hz == 0 {
+            return;
+        }
+        if sample_rate_hz != self.sample_rate_hz {
+            self.sample_rate_hz = sample_rate_hz;
+            self.sample_remainder = 0;
+        }
+        let rate = u64::from(sample_rate_hz);
+        for sample in samples {
+            let cycles = u64::from(self.mclk_hz) + self.sample_remainder;
+            self.sample_remainder = cycles % rate;
+            self.advance(cycles / rate);
+            *sample = self.output();
+        }
+    }
+}
+
+impl ErrorType for Simulator {
+    type Error = Infallible;
+}
+
+impl SpiDevice for Simulator {
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Infallible> {
+        for operation in operations {
+            match operation {
+                Operation::Write(data) => self.write_bytes(data),
+                Operation::Transfer(read, write) => {
+                    read.fill(0);
+                    self.write_bytes(write);
+                }
+                Operation::TransferInPlace(data) => {
+                    self.write_bytes(data);
+                    data.fill(0);
+                }
+                Operation::Read(data) => data.fill(0),
+                Operation::DelayNs(_) => (),
+            }
+        }
+        // Incomplete words are discarded when FSYNC goes high.
+        self.partial_byte = None;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::spi::SpiDevice for Simulator {
+    async fn transaction(
+        &mut self,
+        operations: &mut [Operation<'_, u8>],
+    ) -> Result<(), Infallible> {
+        SpiDevice::transaction(self, operations)
+    }
+}
@@ -0,0 +1,266 @@
+//! Frequency sweeps
+//!
+//! A [`Sweep`] describes the list of frequencies to go through and how long
+//! to stay on each of them. A [`Sweeper`] drives a device through a sweep:
+//! every step writes the next frequency into the frequency register that is
+//! not currently used for the output and then switches over to it, so that
+//! each step is glitch-free.
+//!
+//! ```no_run
+//! use ad983x::{sweep::{Sweep, Sweeper}, Ad983x};
+//! use embedded_hal_bus::spi::ExclusiveDevice;
+//! use linux_embedded_hal::{Delay, SpidevBus, SysfsPin};
+//!
+//! let spi = SpidevBus::open("/dev/spidev0.0").unwrap();
+//! let chip_select = SysfsPin::new(25);
+//! let dev = ExclusiveDevice::new(spi, chip_select, Delay);
+//! let mut dds = Ad983x::new_ad9833(dev);
+//! dds.reset().unwrap();
+//! dds.enable().unwrap();
+//! // 1 kHz to 100 kHz in 50 logarithmically-spaced steps of 10 ms each
+//! let sweep = Sweep::logarithmic(1_000.0, 100_000.0, 50).with_dwell_us(10_000);
+//! let mut sweeper = Sweeper::new(dds, sweep);
+//! sweeper.run(&mut Delay).unwrap();
+//! ```
+
+use embedded_hal::delay::DelayNs;
+
+use crate::{frequency, interface::WriteData, Ad983x, Error};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Profile<'a> {
+    Linear { start: f32, stop: f32 },
+    LinearStep { start: f32, step: f32 },
+    Logarithmic { start: f32, stop: f32 },
+    Table(&'a [f32]),
+}
+
+/// Description of a frequency sweep
+///
+/// All frequencies are in Hz.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sweep<'a> {
+    profile: Profile<'a>,
+    points: usize,
+    dwell_us: u32,
+}
+
+impl<'a> Sweep<'a> {
+    /// Linear sweep from `start` to `stop` (both included) in `points` equally-spaced steps.
+    pub fn linear(start: f32, stop: f32, points: usize) -> Self {
+        Self::new(Profile::Linear { start, stop }, points)
+    }
+
+    /// Linear sweep from `start` towards `stop` with a fixed step size.
+    ///
+    /// The step size must be greater than zero: its sign is taken from the
+    /// sweep direction. The last point is the last one not exceeding `stop`,
+    /// within the `f32` precision of the arguments.
+    ///
+    /// Returns `None` if the step size is not greater than zero, if an
+    /// argument is not finite or if the number of points does not fit
+    /// in a `usize`.
+    pub fn linear_step(start: f32, stop: f32, step: f32) -> Option<Self> {
+        let span = f64::from(stop) - f64::from(start);
+        let step = f64::from(step);
+        if !(span.is_finite() && step.is_finite() && step > 0.0) {
+            return None;
+        }
+        // Keep `stop` in the sweep despite rounding errors in the arguments
+        let tolerance =
+            f64::from(f32::EPSILON) * (f64::from(start).abs() + f64::from(stop).abs()) / step;
+        let points = ((span.abs() / step + tolerance) as usize).checked_add(1)?;
+        let step = if span < 0.0 { -step } else { step };
+        Some(Self::new(
+            Profile::LinearStep {
+                start,
+                step: step as f32,
+            },
+            points,
+        ))
+    }
+
+    /// Logarithmic sweep from `start` to `stop` (both included) in `points` steps
+    /// with a constant frequency ratio between them.
+    ///
+    /// Both frequencies must be greater than zero, otherwise the frequencies
+    /// are not a number and stepping through the sweep returns an error.
+    pub fn logarithmic(start: f32, stop: f32, points: usize) -> Self {
+        Self::new(Profile::Logarithmic { start, stop }, points)
+    }
+
+    /// Sweep going through the frequencies of a table in order.
+    pub fn table(frequencies: &'a [f32]) -> Self {
+        Self::new(Profile::Table(frequencies), frequencies.len())
+    }
+
+    fn new(profile: Profile<'a>, points: usize) -> Self {
+        Sweep {
+            profile,
+            points,
+            dwell_us: 0,
+        }
+    }
+
+    /// Set the time to stay on each frequency in microseconds.
+    ///
+    /// This is only used by [`Sweeper::run()`].
+    pub fn with_dwell_us(self, dwell_us: u32) -> Self {
+        Sweep { dwell_us, ..self }
+    }
+
+    /// Time to stay on each frequency in microseconds
+    pub fn dwell_us(&self) -> u32 {
+        self.dwell_us
+    }
+
+    /// Number of frequency steps
+    pub fn len(&self) -> usize {
+        self.points
+    }
+
+    /// Whether the sweep contains no frequencies
+    pub fn is_empty(&self) -> bool {
+        self.points == 0
+    }
+
+    /// Frequency of the step at `index`, if there is such a step
+    pub fn frequency_at(&self, index: usize) -> Option<f32> {
+        if index >= self.points {
+            return None;
+        }
+        let fraction = if self.points > 1 {
+            index as f64 / (self.points - 1) as f64
+        } else {
+            0.0
+        };
+        let frequency = match self.profile {
+            Profile::Linear { start, stop } => {
+                let start = f64::from(start);
+                start + (f64::from(stop) - start) * fraction
+            }
+            Profile::LinearStep { start, step } => {
+                f64::from(start) + f64::from(step) * index as f64
+            }
+            Profile::Logarithmic { start, stop } if start > 0.0 && stop > 0.0 => {
+                let start = f64::from(start);
+                start * libm::pow(f64::from(stop) / start, fraction)
+            }
+            Profile::Logarithmic { .. } => f64::NAN,
+            Profile::Table(table) => f64::from(table[index]),
+        };
+        Some(frequency as f32)
+    }
+
+    /// Iterate over the frequencies of the sweep
+    pub fn frequencies(&self) -> Frequencies<'a> {
+        Frequencies {
+            sweep: *self,
+            index: 0,
+        }
+    }
+}
+
+/// Iterator over the frequencies of a [`Sweep`]
+#[derive(Debug, Clone)]
+pub struct Frequencies<'a> {
+    sweep: Sweep<'a>,
+    index: usize,
+}
+
+impl Iterator for Frequencies<'_> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let frequency = self.sweep.frequency_at(self.index)?;
+        self.index += 1;
+        Some(frequency)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.sweep.len().saturating_sub(self.index);
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Frequencies<'_> {}
+
+/// Drives a device through a [`Sweep`]
+///
+/// Each step is done with [`Ad983x::retune()`], which writes the frequency
+/// into the register that is not selected for the output and then selects it.
+/// On AD9834/AD9838 devices with the hardware pin control source selected
+/// this requires the FSELECT pin to be assigned.
+#[derive(Debug)]
+pub struct Sweeper<'a, DEV, IC> {
+    dds: Ad983x<DEV, IC>,
+    sweep: Sweep<'a>,
+    index: usize,
+}
+
+impl<'a, DEV, IC> Sweeper<'a, DEV, IC> {
+    /// Create a new sweeper. The device should already be reset and enabled.
+    pub fn new(dds: Ad983x<DEV, IC>, sweep: Sweep<'a>) -> Self {
+        Sweeper {
+            dds,
+            sweep,
+            index: 0,
+        }
+    }
+
+    /// Destroy the sweeper and return the device.
+    pub fn destroy(self) -> Ad983x<DEV, IC> {
+        self.dds
+    }
+
+    /// Sweep description
+    pub fn sweep(&self) -> &Sweep<'a> {
+        &self.sweep
+    }
+
+    /// Index of the next step
+    pub fn position(&self) -> usize {
+        self.index
+    }
+
+    /// Whether all the steps of the sweep have been done
+    pub fn is_finished(&self) -> bool {
+        self.index >= self.sweep.len()
+    }
+
+    /// Start over from the first step
+    pub fn restart(&mut self) {
+        self.index = 0;
+    }
+}
+
+impl<DEV, IC, SpiE, PinE> Sweeper<'_, DEV, IC>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+{
+    /// Go to the next frequency of the sweep without waiting.
+    ///
+    /// This is intended to be called periodically, for example from a timer
+    /// interrupt. Returns the frequency actually set or `None` if the sweep
+    /// is finished.
+    /// Returns `Error::InvalidArgument` if the frequency cannot be set.
+    pub fn step(&mut self) -> Result<Option<f32>, Error<SpiE, PinE>> {
+        let frequency = match self.sweep.frequency_at(self.index) {
+            Some(frequency) => frequency,
+            None => return Ok(None),
+        };
+        let mclk_hz = self.dds.corrected_mclk_frequency();
+        let word = frequency::word_from_hz(mclk_hz, frequency).ok_or(Error::InvalidArgument)?;
+        self.dds.retune(word)?;
+        self.index += 1;
+        Ok(Some(frequency::hz_from_word(mclk_hz, word)))
+    }
+
+    /// Run through the remaining steps of the sweep, waiting the dwell time on each.
+    pub fn run<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<SpiE, PinE>> {
+        while self.step()?.is_some() {
+            delay.delay_us(self.sweep.dwell_us);
+        }
+        Ok(())
+    }
+}
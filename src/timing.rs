@@ -0,0 +1,26 @@
+/// Symbol period generator for a given symbol rate.
+///
+/// Keeps track of the fractional nanoseconds so that long
+/// transmissions do not drift.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SymbolClock {
+    rate: u32,
+    remainder_ns: u32,
+}
+
+impl SymbolClock {
+    pub(crate) fn new(rate: u32) -> Self {
+        SymbolClock {
+            rate,
+            remainder_ns: 0,
+        }
+    }
+
+    /// Duration of the next symbol in nanoseconds
+    pub(crate) fn next_period_ns(&mut self) -> u32 {
+        let rate = u64::from(self.rate.max(1));
+        let period = 1_000_000_000 + u64::from(self.remainder_ns);
+        self.remainder_ns = (period % rate) as u32;
+        (period / rate) as u32
+    }
+}
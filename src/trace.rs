@@ -0,0 +1,108 @@
+//! Decoded trace of the words written to the device
+//!
+//! Every word is decoded the way the device interprets it and emitted at the
+//! trace level through `defmt` and/or `log`, depending on the features enabled.
+
+use core::fmt;
+
+use crate::BitFlags;
+
+/// Meaning of a word written to the device
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Word {
+    /// Control register content
+    Control(u16),
+    /// 14-bit half of a frequency register
+    Frequency { index: usize, msb: bool, data: u16 },
+    /// 12-bit phase register
+    Phase { index: usize, data: u16 },
+}
+
+const CONTROL_BITS: [(u16, &str); 12] = [
+    (BitFlags::B28, "B28"),
+    (BitFlags::HLB, "HLB"),
+    (BitFlags::FSELECT, "FSELECT"),
+    (BitFlags::PSELECT, "PSELECT"),
+    (BitFlags::PIN_SW, "PIN_SW"),
+    (BitFlags::RESET, "RESET"),
+    (BitFlags::SLEEP_MCLK, "SLEEP1"),
+    (BitFlags::SLEEP_DAC, "SLEEP12"),
+    (BitFlags::OPBITEN, "OPBITEN"),
+    (BitFlags::SIGN_PIB, "SIGN_PIB"),
+    (BitFlags::DIV2, "DIV2"),
+    (BitFlags::MODE, "MODE"),
+];
+
+fn half(msb: bool) -> &'static str {
+    if msb {
+        "MSB"
+    } else {
+        "LSB"
+    }
+}
+
+impl fmt::Display for Word {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Word::Control(bits) => {
+                f.write_str("CTRL")?;
+                let mut separator = " ";
+                for (mask, name) in CONTROL_BITS {
+                    if bits & mask != 0 {
+                        write!(f, "{}{}", separator, name)?;
+                        separator = "|";
+                    }
+                }
+                if bits == 0 {
+                    f.write_str(" 0")?;
+                }
+                Ok(())
+            }
+            Word::Frequency { index, msb, data } => {
+                write!(f, "FREQ{} {}=0x{:04X}", index, half(msb), data)
+            }
+            Word::Phase { index, data } => write!(f, "PHASE{}=0x{:04X}", index, data),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Word {
+    fn format(&self, f: defmt::Formatter) {
+        match *self {
+            Word::Control(bits) => {
+                defmt::write!(f, "CTRL");
+                let mut separator = " ";
+                for (mask, name) in CONTROL_BITS {
+                    if bits & mask != 0 {
+                        defmt::write!(f, "{=str}{=str}", separator, name);
+                        separator = "|";
+                    }
+                }
+                if bits == 0 {
+                    defmt::write!(f, " 0");
+                }
+            }
+            Word::Frequency { index, msb, data } => {
+                defmt::write!(
+                    f,
+                    "FREQ{=usize} {=str}=0x{=u16:04X}",
+                    index,
+                    half(msb),
+                    data
+                )
+            }
+            Word::Phase { index, data } => {
+                defmt::write!(f, "PHASE{=usize}=0x{=u16:04X}", index, data)
+            }
+        }
+    }
+}
+
+/// Emit the trace line of a word
+pub(crate) fn word(word: Word) {
+    #[cfg(feature = "defmt")]
+    defmt::trace!("{}", word);
+    #[cfg(feature = "log")]
+    log::trace!("{}", word);
+}
@@ -0,0 +1,80 @@
+use crate::{
+    interface::WriteData,
+    state::{self, Transition},
+    Ad983x,
+};
+
+impl<DEV, IC> Ad983x<DEV, IC, state::Unchecked> {
+    /// Turn into the typestate interface, where the reset and enable
+    /// sequence is checked at compile time.
+    ///
+    /// The device is considered not to be reset yet.
+    pub fn into_typestate(self) -> Ad983x<DEV, IC, state::Uninitialized> {
+        self.into_state()
+    }
+}
+
+impl<DEV, IC> Ad983x<DEV, IC, state::Reset> {
+    /// Leave the typestate interface
+    pub fn into_unchecked(self) -> Ad983x<DEV, IC, state::Unchecked> {
+        self.into_state()
+    }
+}
+
+impl<DEV, IC> Ad983x<DEV, IC, state::Running> {
+    /// Leave the typestate interface
+    pub fn into_unchecked(self) -> Ad983x<DEV, IC, state::Unchecked> {
+        self.into_state()
+    }
+}
+
+impl<DEV, IC, SpiE, PinE> Ad983x<DEV, IC, state::Uninitialized>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+{
+    /// Reset the internal registers and leave the device held in reset.
+    ///
+    /// The registers can be configured afterwards.
+    /// In case of error the device is returned in its previous state.
+    pub fn reset(mut self) -> Transition<DEV, IC, state::Uninitialized, state::Reset, SpiE, PinE> {
+        match self.write_reset(true) {
+            Ok(()) => Ok(self.into_state()),
+            Err(e) => Err((self, e)),
+        }
+    }
+}
+
+impl<DEV, IC, SpiE, PinE> Ad983x<DEV, IC, state::Reset>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+{
+    /// Enable the device (release reset)
+    ///
+    /// On AD9834/AD9838 devices with the hardware pin control source selected
+    /// this drives the RESET pin instead.
+    /// In case of error the device is returned in its previous state.
+    pub fn enable(mut self) -> Transition<DEV, IC, state::Reset, state::Running, SpiE, PinE> {
+        match self.write_reset(false) {
+            Ok(()) => Ok(self.into_state()),
+            Err(e) => Err((self, e)),
+        }
+    }
+}
+
+impl<DEV, IC, SpiE, PinE> Ad983x<DEV, IC, state::Running>
+where
+    DEV: WriteData<SpiError = SpiE, PinError = PinE>,
+{
+    /// Disable the device (hold in reset)
+    ///
+    /// This resets the internal registers.
+    /// On AD9834/AD9838 devices with the hardware pin control source selected
+    /// this drives the RESET pin instead.
+    /// In case of error the device is returned in its previous state.
+    pub fn disable(mut self) -> Transition<DEV, IC, state::Running, state::Reset, SpiE, PinE> {
+        match self.write_reset(true) {
+            Ok(()) => Ok(self.into_state()),
+            Err(e) => Err((self, e)),
+        }
+    }
+}
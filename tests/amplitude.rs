@@ -0,0 +1,137 @@
+use ad983x::{
+    amplitude::{
+        Ad983xWithAmplitude, AmplitudeCalibration, AmplitudeControl, AmplitudeError,
+        CalibrationPoint,
+    },
+    Error, OutputWaveform,
+};
+use embedded_hal_mock::eh1::spi::Transaction as SpiTrans;
+
+mod base;
+use crate::base::{new_ad9833, new_ad9834, BitFlags as BF};
+
+#[derive(Default)]
+struct Potentiometer {
+    codes: Vec<u32>,
+    fail: bool,
+}
+
+impl AmplitudeControl for Potentiometer {
+    type Error = ();
+
+    fn max_code(&self) -> u32 {
+        255
+    }
+
+    fn set_code(&mut self, code: u32) -> Result<(), ()> {
+        if self.fail {
+            return Err(());
+        }
+        self.codes.push(code);
+        Ok(())
+    }
+}
+
+const LINEAR: AmplitudeCalibration = AmplitudeCalibration::Linear { full_scale_mv: 650 };
+const SQUARE: AmplitudeCalibration = AmplitudeCalibration::Linear {
+    full_scale_mv: 3300,
+};
+const TRIANGLE: [CalibrationPoint; 3] = [
+    CalibrationPoint {
+        code: 0,
+        millivolts: 0,
+    },
+    CalibrationPoint {
+        code: 128,
+        millivolts: 290,
+    },
+    CalibrationPoint {
+        code: 255,
+        millivolts: 600,
+    },
+];
+
+#[test]
+fn sets_linear_amplitude() {
+    let mut dds = Ad983xWithAmplitude::new(new_ad9833(&[]), Potentiometer::default(), LINEAR);
+    assert_eq!(None, dds.amplitude_mv());
+    assert_eq!(326, dds.set_amplitude_mv(325).unwrap());
+    assert_eq!(Some(325), dds.amplitude_mv());
+    assert_eq!(Some(128), dds.code());
+    // Same setting: nothing to write
+    dds.set_amplitude_mv(325).unwrap();
+    assert_eq!(0, dds.set_amplitude_mv(0).unwrap());
+    assert_eq!(650, dds.set_amplitude_mv(650).unwrap());
+    assert_eq!(vec![128, 0, 255], dds.amplitude_control().codes);
+    base::destroy(dds.destroy().0);
+}
+
+#[test]
+fn rejects_amplitude_out_of_range() {
+    let table = AmplitudeCalibration::Table(&TRIANGLE[1..]);
+    let mut dds = Ad983xWithAmplitude::new(new_ad9833(&[]), Potentiometer::default(), table);
+    match dds.set_amplitude_mv(700) {
+        Err(AmplitudeError::OutOfRange) => (),
+        _ => panic!("Should return OutOfRange"),
+    }
+    match dds.set_amplitude_mv(100) {
+        Err(AmplitudeError::OutOfRange) => (),
+        _ => panic!("Should return OutOfRange"),
+    }
+    assert_eq!(None, dds.amplitude_mv());
+    assert!(dds.amplitude_control().codes.is_empty());
+    base::destroy(dds.destroy().0);
+}
+
+#[test]
+fn waveform_change_keeps_amplitude() {
+    let transactions = [
+        SpiTrans::write_vec(vec![BF::RESET, BF::MODE]),
+        SpiTrans::write_vec(vec![BF::RESET, BF::OPBITEN | BF::DIV2]),
+    ];
+    let mut dds =
+        Ad983xWithAmplitude::new(new_ad9833(&transactions), Potentiometer::default(), LINEAR)
+            .with_calibration(
+                OutputWaveform::Triangle,
+                AmplitudeCalibration::Table(&TRIANGLE),
+            )
+            .with_calibration(OutputWaveform::SquareMsbOfDacDiv2, SQUARE);
+    dds.set_amplitude_mv(300).unwrap();
+    dds.set_output_waveform(OutputWaveform::Triangle).unwrap();
+    assert_eq!(OutputWaveform::Triangle, dds.dds().output_waveform());
+    dds.set_output_waveform(OutputWaveform::SquareMsbOfDac)
+        .unwrap();
+    assert_eq!(Some(300), dds.amplitude_mv());
+    assert_eq!(vec![118, 132, 23], dds.amplitude_control().codes);
+    base::destroy(dds.destroy().0);
+}
+
+#[test]
+fn lowers_gain_before_changing_waveform() {
+    let mut dds = Ad983xWithAmplitude::new(new_ad9833(&[]), Potentiometer::default(), LINEAR)
+        .with_calibration(OutputWaveform::SquareMsbOfDac, SQUARE);
+    dds.set_amplitude_mv(300).unwrap();
+    dds.amplitude_control_mut().fail = true;
+    // The gain is lowered first and fails, so the waveform is not written.
+    match dds.set_output_waveform(OutputWaveform::SquareMsbOfDac) {
+        Err(AmplitudeError::Control(())) => (),
+        _ => panic!("Should return Control error"),
+    }
+    assert_eq!(OutputWaveform::Sinusoidal, dds.dds().output_waveform());
+    assert_eq!(Some(118), dds.code());
+    base::destroy(dds.destroy().0);
+}
+
+#[test]
+fn waveform_change_without_amplitude_only_writes_waveform() {
+    let transactions = [SpiTrans::write_vec(vec![BF::RESET, BF::MODE])];
+    let mut dds =
+        Ad983xWithAmplitude::new(new_ad9834(&transactions), Potentiometer::default(), LINEAR);
+    dds.set_output_waveform(OutputWaveform::Triangle).unwrap();
+    match dds.set_output_waveform(OutputWaveform::SquareMsbOfDac) {
+        Err(AmplitudeError::Device(Error::InvalidArgument)) => (),
+        _ => panic!("Should return InvalidArgument"),
+    }
+    assert!(dds.amplitude_control().codes.is_empty());
+    base::destroy(dds.destroy().0);
+}
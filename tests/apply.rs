@@ -0,0 +1,160 @@
+use ad983x::{
+    ControlSource, DeviceConfig, FrequencyRegister as FreqReg, OutputWaveform as OW, PhaseRegister,
+    PoweredDown, SignBitOutput as SBO,
+};
+use embedded_hal_mock::eh1::spi::Transaction as SpiTrans;
+
+mod base;
+use crate::base::{destroy, new_ad9833, new_ad9838, write_words, BitFlags as BF};
+
+const P0: u8 = BF::D15 | BF::D14;
+const P1: u8 = BF::D15 | BF::D14 | BF::D13;
+
+fn config() -> DeviceConfig {
+    DeviceConfig {
+        frequency0: 0x9AB_CDEF,
+        frequency1: 1000,
+        phase1: 0xABC,
+        selected_frequency: FreqReg::F1,
+        output_waveform: OW::Triangle,
+        ..Default::default()
+    }
+}
+
+fn initial_transitions() -> Vec<SpiTrans<u8>> {
+    vec![write_words(&[
+        [BF::B28 | BF::RESET, 0],
+        [BF::FREQ0 | 0xD, 0xEF],
+        [BF::FREQ0 | 0x26, 0xAF],
+        [BF::FREQ1 | 0x3, 0xE8],
+        [BF::FREQ1, 0],
+        [P0, 0],
+        [P1 | 0xA, 0xBC],
+        [BF::B28 | BF::FSELECT, BF::MODE],
+    ])]
+}
+
+#[test]
+fn first_apply_loads_everything_in_reset() {
+    let mut dev = new_ad9833(&initial_transitions());
+    dev.apply(&config()).unwrap();
+    assert!(!dev.is_reset());
+    assert_eq!(Some(1000), dev.frequency(FreqReg::F1));
+    destroy(dev);
+}
+
+#[test]
+fn applying_same_config_writes_nothing() {
+    let mut dev = new_ad9833(&initial_transitions());
+    dev.apply(&config()).unwrap();
+    dev.apply(&config()).unwrap();
+    destroy(dev);
+}
+
+#[test]
+fn apply_only_writes_changes() {
+    let mut transitions = initial_transitions();
+    transitions.push(write_words(&[[P1 | 0x1, 0x23], [BF::B28 | BF::FSELECT, 0]]));
+    transitions.push(write_words(&[[BF::FREQ0 | 0x7, 0xD0], [BF::FREQ0, 0]]));
+    let mut dev = new_ad9833(&transitions);
+    let mut config = config();
+    dev.apply(&config).unwrap();
+    config.phase1 = 0x123;
+    config.output_waveform = OW::Sinusoidal;
+    dev.apply(&config).unwrap();
+    config.frequency0 = 2000;
+    dev.apply(&config).unwrap();
+    destroy(dev);
+}
+
+#[test]
+fn apply_after_setters_only_writes_changes() {
+    let transitions = [
+        write_words(&[[BF::B28 | BF::RESET, 0], [BF::FREQ0, 0], [BF::FREQ0, 0]]),
+        write_words(&[[BF::FREQ1, 0], [BF::FREQ1, 0]]),
+        SpiTrans::write_vec(vec![P0, 0]),
+        SpiTrans::write_vec(vec![P1, 0]),
+        SpiTrans::write_vec(vec![BF::B28 | BF::PSELECT, 0]),
+    ];
+    let mut dev = new_ad9833(&transitions);
+    dev.set_frequency(FreqReg::F0, 0).unwrap();
+    dev.set_frequency(FreqReg::F1, 0).unwrap();
+    dev.set_phase(PhaseRegister::P0, 0).unwrap();
+    dev.set_phase(PhaseRegister::P1, 0).unwrap();
+    let config = DeviceConfig {
+        selected_phase: PhaseRegister::P1,
+        ..Default::default()
+    };
+    dev.apply(&config).unwrap();
+    destroy(dev);
+}
+
+#[test]
+fn can_apply_ad9838_settings() {
+    let transitions = [write_words(&[
+        [BF::B28 | BF::RESET, 0],
+        [BF::FREQ0, 0],
+        [BF::FREQ0, 0],
+        [BF::FREQ1, 0],
+        [BF::FREQ1, 0],
+        [P0, 0],
+        [P1, 0],
+        [BF::B28 | BF::PIN_SW, BF::OPBITEN | BF::SIGN_PIB | BF::DIV2],
+    ])];
+    let mut dev = new_ad9838(&transitions);
+    let config = DeviceConfig {
+        sign_bit_output: SBO::Comparator,
+        control_source: ControlSource::HardwarePins,
+        ..Default::default()
+    };
+    dev.apply(&config).unwrap();
+    assert_eq!(SBO::Comparator, dev.sign_bit_output());
+    assert_eq!(ControlSource::HardwarePins, dev.control_source());
+    destroy(dev);
+}
+
+#[test]
+fn cannot_apply_too_fast_frequency() {
+    let mut dev = new_ad9833(&[]);
+    let config = DeviceConfig {
+        frequency1: 1 << 28,
+        ..Default::default()
+    };
+    dev.apply(&config).expect_err("Should return error");
+    destroy(dev);
+}
+
+#[test]
+fn cannot_apply_sign_bit_output_on_ad9833() {
+    let mut dev = new_ad9833(&[]);
+    let config = DeviceConfig {
+        sign_bit_output: SBO::Comparator,
+        ..Default::default()
+    };
+    dev.apply(&config).expect_err("Should return error");
+    destroy(dev);
+}
+
+#[test]
+fn cannot_apply_triangle_with_sign_bit_output() {
+    let mut dev = new_ad9838(&[]);
+    let config = DeviceConfig {
+        output_waveform: OW::Triangle,
+        sign_bit_output: SBO::SquareMsbOfDac,
+        ..Default::default()
+    };
+    dev.apply(&config).expect_err("Should return error");
+    destroy(dev);
+}
+
+#[test]
+fn cannot_apply_internal_clock_power_down_with_hardware_pins() {
+    let mut dev = new_ad9838(&[]);
+    let config = DeviceConfig {
+        powered_down: PoweredDown::InternalClock,
+        control_source: ControlSource::HardwarePins,
+        ..Default::default()
+    };
+    dev.apply(&config).expect_err("Should return error");
+    destroy(dev);
+}
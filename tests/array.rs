@@ -0,0 +1,145 @@
+use ad983x::{
+    array::DdsArray,
+    interface::{ControlPins, WithControlPins},
+    marker, Ad983x, ControlSource, Error, FrequencyRegister as FreqReg, Phase, PhaseRegister,
+};
+use embedded_hal_mock::eh1::{
+    pin::{Mock as PinMock, State, Transaction as PinTrans},
+    spi::{Mock as SpiMock, Transaction as SpiTrans},
+};
+
+mod base;
+use crate::base::{new_ad9833, write_words, BitFlags as BF};
+
+type SpiDds = Ad983x<SpiMock<u8>, marker::Ad9834Ad9838>;
+
+type Dds = Ad983x<WithControlPins<SpiMock<u8>, PinMock>, marker::Ad9834Ad9838>;
+
+fn new_ad9834(transactions: &[SpiTrans<u8>]) -> Dds {
+    let spi: Vec<SpiTrans<u8>> = transactions
+        .iter()
+        .flat_map(|trans| {
+            [
+                SpiTrans::transaction_start(),
+                trans.clone(),
+                SpiTrans::transaction_end(),
+            ]
+        })
+        .collect();
+    Ad983x::new_ad9834_with_pins(SpiMock::new(&spi), ControlPins::default())
+}
+
+fn destroy_ad9834(dds: Dds) {
+    let (mut spi, _) = dds.destroy().release();
+    spi.done();
+}
+
+fn hardware_control() -> SpiTrans<u8> {
+    SpiTrans::write_vec(vec![BF::RESET | BF::PIN_SW, 0])
+}
+
+#[test]
+fn loads_channels_in_reset_and_releases_them() {
+    let channel = |phase: u8| {
+        [
+            SpiTrans::write_vec(vec![BF::RESET, 0]),
+            write_words(&[[BF::B28 | BF::RESET, 0], [BF::FREQ0, 100], [BF::FREQ0, 0]]),
+            SpiTrans::write_vec(vec![BF::D15 | BF::D14 | phase, 0]),
+            SpiTrans::write_vec(vec![BF::B28, 0]),
+        ]
+    };
+    let mut array = DdsArray::new([new_ad9833(&channel(0)), new_ad9833(&channel(0x4))]);
+    array.hold_reset().unwrap();
+    array
+        .configure(|_, dds| dds.set_frequency(FreqReg::F0, 100))
+        .unwrap();
+    let offsets = [Phase::ZERO, Phase::from_degrees(90.0)];
+    array
+        .set_phase_offsets(PhaseRegister::P0, &offsets)
+        .unwrap();
+    assert!(array.channel(1).unwrap().is_reset());
+    array.release().unwrap();
+    assert!(!array.channel(1).unwrap().is_reset());
+    assert!(array.channel(2).is_none());
+    for dds in array.destroy() {
+        base::destroy(dds);
+    }
+}
+
+#[test]
+fn configure_gets_channel_index() {
+    let mut array = DdsArray::new([new_ad9833(&[]), new_ad9833(&[]), new_ad9833(&[])]);
+    let mut indices = Vec::new();
+    array
+        .configure(|index, _| {
+            indices.push(index);
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(vec![0, 1, 2], indices);
+    for dds in array.destroy() {
+        base::destroy(dds);
+    }
+}
+
+#[test]
+fn shared_reset_pin_releases_all_devices() {
+    let mut reset = PinMock::new(&[PinTrans::set(State::High), PinTrans::set(State::Low)]);
+    let mut array = DdsArray::new([
+        new_ad9834(&[hardware_control()]),
+        new_ad9834(&[hardware_control()]),
+    ]);
+    for dds in array.channels_mut() {
+        dds.set_control_source(ControlSource::HardwarePins).unwrap();
+    }
+    array.hold_reset_pin(&mut reset).unwrap();
+    assert!(array.channel(0).unwrap().is_reset());
+    array.release_reset_pin(&mut reset).unwrap();
+    assert!(!array.channel(0).unwrap().is_reset());
+    assert!(!array.channel(1).unwrap().is_reset());
+    for dds in array.destroy() {
+        destroy_ad9834(dds);
+    }
+    reset.done();
+}
+
+#[test]
+fn shared_reset_pin_with_spi_devices() {
+    // The drivers have no pin error, unlike the GPIO driving RESET.
+    let mut reset = PinMock::new(&[PinTrans::set(State::High), PinTrans::set(State::Low)]);
+    let channels: [SpiDds; 2] = [
+        base::new_ad9834(&[hardware_control()]),
+        base::new_ad9834(&[hardware_control()]),
+    ];
+    let mut array = DdsArray::new(channels);
+    for dds in array.channels_mut() {
+        dds.set_control_source(ControlSource::HardwarePins).unwrap();
+    }
+    array.hold_reset_pin(&mut reset).unwrap();
+    assert!(array.channel(1).unwrap().is_reset());
+    array.release_reset_pin(&mut reset).unwrap();
+    assert!(!array.channel(1).unwrap().is_reset());
+    for dds in array.destroy() {
+        base::destroy(dds);
+    }
+    reset.done();
+}
+
+#[test]
+fn shared_reset_pin_needs_hardware_control() {
+    let mut reset = PinMock::new(&[]);
+    let mut array = DdsArray::new([new_ad9834(&[hardware_control()]), new_ad9834(&[])]);
+    array
+        .channel_mut(0)
+        .unwrap()
+        .set_control_source(ControlSource::HardwarePins)
+        .unwrap();
+    match array.hold_reset_pin(&mut reset) {
+        Err(Error::InvalidArgument) => (),
+        _ => panic!("Should return InvalidArgument"),
+    }
+    for dds in array.destroy() {
+        destroy_ad9834(dds);
+    }
+    reset.done();
+}
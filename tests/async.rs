@@ -0,0 +1,160 @@
+#![cfg(feature = "async")]
+
+use ad983x::{
+    marker, AsyncAd983x, ControlSource, DeviceConfig, FrequencyRegister as FreqReg,
+    OutputWaveform as OW, PhaseRegister as PhaseReg, SignBitOutput as SBO,
+};
+use embassy_futures::block_on;
+use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTrans};
+
+mod base;
+use crate::base::{write_words, BitFlags as BF};
+
+fn wrap(transactions: &[SpiTrans<u8>]) -> SpiMock<u8> {
+    let wrapped: Vec<SpiTrans<u8>> = transactions
+        .iter()
+        .flat_map(|trans| {
+            [
+                SpiTrans::transaction_start(),
+                trans.clone(),
+                SpiTrans::transaction_end(),
+            ]
+        })
+        .collect();
+    SpiMock::new(&wrapped)
+}
+
+fn new_ad9833(transactions: &[SpiTrans<u8>]) -> AsyncAd983x<SpiMock<u8>, marker::Ad9833Ad9837> {
+    AsyncAd983x::new_ad9833(wrap(transactions))
+}
+
+fn new_ad9838(transactions: &[SpiTrans<u8>]) -> AsyncAd983x<SpiMock<u8>, marker::Ad9834Ad9838> {
+    AsyncAd983x::new_ad9838(wrap(transactions))
+}
+
+fn destroy<IC>(device: AsyncAd983x<SpiMock<u8>, IC>) {
+    device.destroy().done()
+}
+
+#[test]
+fn can_reset_and_enable() {
+    let transitions = [
+        SpiTrans::write_vec(vec![BF::RESET, 0]),
+        SpiTrans::write_vec(vec![0, 0]),
+    ];
+    let mut dev = new_ad9833(&transitions);
+    block_on(dev.reset()).unwrap();
+    block_on(dev.enable()).unwrap();
+    destroy(dev);
+}
+
+#[test]
+fn can_set_freq1() {
+    let transitions = [write_words(&[
+        [BF::B28 | BF::RESET, 0],
+        [BF::FREQ1 | 0xD, 0xEF],
+        [BF::FREQ1 | 0x26, 0xAF],
+    ])];
+    let mut dev = new_ad9833(&transitions);
+    block_on(dev.set_frequency(FreqReg::F1, 0x9AB_CDEF)).unwrap();
+    destroy(dev);
+}
+
+#[test]
+fn can_retune() {
+    let transitions = [
+        write_words(&[
+            [BF::B28 | BF::RESET, 0],
+            [BF::FREQ1 | 0xD, 0xEF],
+            [BF::FREQ1 | 0x26, 0xAF],
+            [BF::B28 | BF::FSELECT | BF::RESET, 0],
+        ]),
+        write_words(&[
+            [BF::FREQ0 | 0x12, 0x74],
+            [BF::FREQ0, 0],
+            [BF::B28 | BF::RESET, 0],
+        ]),
+    ];
+    let mut dev = new_ad9833(&transitions);
+    assert_eq!(FreqReg::F1, block_on(dev.retune(0x9AB_CDEF)).unwrap());
+    assert_eq!(439_957, block_on(dev.retune_millihertz(440_000)).unwrap());
+    destroy(dev);
+}
+
+#[test]
+fn cannot_set_too_fast_frequency() {
+    let mut dev = new_ad9833(&[]);
+    block_on(dev.set_frequency(FreqReg::F0, 1 << 28)).expect_err("Should return error");
+    destroy(dev);
+}
+
+#[test]
+fn can_set_phase1_and_select() {
+    let transitions = [
+        SpiTrans::write_vec(vec![BF::D15 | BF::D14 | BF::D13 | 0xA, 0xBC]),
+        SpiTrans::write_vec(vec![BF::PSELECT | BF::RESET, 0]),
+        SpiTrans::write_vec(vec![BF::FSELECT | BF::PSELECT | BF::RESET, 0]),
+    ];
+    let mut dev = new_ad9833(&transitions);
+    block_on(dev.set_phase(PhaseReg::P1, 0xABC)).unwrap();
+    block_on(dev.select_phase(PhaseReg::P1)).unwrap();
+    block_on(dev.select_frequency(FreqReg::F1)).unwrap();
+    destroy(dev);
+}
+
+#[test]
+fn can_set_output_waveform() {
+    let transitions = [SpiTrans::write_vec(vec![BF::RESET, BF::OPBITEN | BF::DIV2])];
+    let mut dev = new_ad9833(&transitions);
+    block_on(dev.set_output_waveform(OW::SquareMsbOfDac)).unwrap();
+    destroy(dev);
+}
+
+#[test]
+fn cannot_set_ow_dac_ad9838() {
+    let mut dev = new_ad9838(&[]);
+    block_on(dev.set_output_waveform(OW::SquareMsbOfDac)).expect_err("Should return error");
+    destroy(dev);
+}
+
+#[test]
+fn can_set_sign_bit_output_and_control_source() {
+    let transitions = [
+        SpiTrans::write_vec(vec![BF::RESET, BF::OPBITEN | BF::SIGN_PIB | BF::DIV2]),
+        SpiTrans::write_vec(vec![
+            BF::RESET | BF::PIN_SW,
+            BF::OPBITEN | BF::SIGN_PIB | BF::DIV2,
+        ]),
+    ];
+    let mut dev = new_ad9838(&transitions);
+    block_on(dev.set_sign_bit_output(SBO::Comparator)).unwrap();
+    block_on(dev.set_control_source(ControlSource::HardwarePins)).unwrap();
+    destroy(dev);
+}
+
+#[test]
+fn can_apply_config() {
+    let transitions = [
+        write_words(&[
+            [BF::B28 | BF::RESET, 0],
+            [BF::FREQ0, 0],
+            [BF::FREQ0, 0],
+            [BF::FREQ1 | 0x3, 0xE8],
+            [BF::FREQ1, 0],
+            [BF::D15 | BF::D14, 0],
+            [BF::D15 | BF::D14 | BF::D13, 0],
+            [BF::B28 | BF::FSELECT, 0],
+        ]),
+        write_words(&[[BF::FREQ0 | 0x7, 0xD0], [BF::FREQ0, 0]]),
+    ];
+    let mut dev = new_ad9833(&transitions);
+    let mut config = DeviceConfig {
+        frequency1: 1000,
+        selected_frequency: FreqReg::F1,
+        ..Default::default()
+    };
+    block_on(dev.apply(&config)).unwrap();
+    config.frequency0 = 2000;
+    block_on(dev.apply(&config)).unwrap();
+    destroy(dev);
+}
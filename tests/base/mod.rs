@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 use ad983x::{marker, Ad983x};
 use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTrans};
 
@@ -81,3 +83,28 @@ pub fn new_ad9838(transactions: &[SpiTrans<u8>]) -> Ad983x<SpiMock<u8>, marker::
 pub fn destroy<IC>(device: Ad983x<SpiMock<u8>, IC>) {
     device.destroy().done()
 }
+
+/// Delay recording the requested delays in nanoseconds
+#[derive(Debug, Default)]
+pub struct RecordingDelay {
+    pub delays_ns: Vec<u64>,
+}
+
+impl embedded_hal::delay::DelayNs for RecordingDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.delays_ns.push(u64::from(ns));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        self.delays_ns.push(u64::from(us) * 1_000);
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        self.delays_ns.push(u64::from(ms) * 1_000_000);
+    }
+}
+
+/// Expect several 16-bit words written in a single SPI transaction
+pub fn write_words(words: &[[u8; 2]]) -> SpiTrans<u8> {
+    SpiTrans::write_vec(words.concat())
+}
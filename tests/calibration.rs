@@ -0,0 +1,107 @@
+use ad983x::{
+    calibration::{CalibrationError, FrequencyMeter},
+    Error, FrequencyRegister as FreqReg,
+};
+
+mod base;
+use crate::base::{new_ad9833, write_words, BitFlags as BF};
+
+// With a 2^28 Hz master clock the tuning word matches the frequency in Hz.
+const MCLK: u32 = 1 << 28;
+
+struct Meter(Result<f64, ()>);
+
+impl FrequencyMeter for Meter {
+    type Error = ();
+
+    fn measure_hz(&mut self) -> Result<f64, ()> {
+        self.0
+    }
+}
+
+fn set_word_1m() -> [u8; 2] {
+    [BF::FREQ0, 0x40]
+}
+
+#[test]
+fn default_has_no_correction() {
+    let mut dds = new_ad9833(&[]);
+    dds.set_mclk_frequency(MCLK);
+    assert_eq!(0.0, dds.mclk_correction_ppm());
+    assert_eq!(MCLK, dds.corrected_mclk_frequency());
+    base::destroy(dds);
+}
+
+#[test]
+fn hz_setters_use_corrected_mclk() {
+    // 100 kHz with a clock 50 ppm too slow needs 100005 instead of 100000.
+    let transactions = [write_words(&[
+        [BF::B28 | BF::RESET, 0],
+        [BF::FREQ0 | 0x06, 0xA5],
+        [BF::FREQ0, 0x06],
+    ])];
+    let mut dds = new_ad9833(&transactions);
+    dds.set_mclk_frequency(MCLK);
+    dds.set_mclk_correction_ppm(-50.0);
+    assert_eq!(MCLK, dds.mclk_frequency());
+    assert_eq!(268_422_034, dds.corrected_mclk_frequency());
+    let frequency = dds.set_frequency_hz(FreqReg::F0, 100_000.0).unwrap();
+    assert!((frequency - 100_000.0).abs() < 0.1);
+    base::destroy(dds);
+}
+
+#[test]
+fn invalid_correction_rejects_hz_setters() {
+    let mut dds = new_ad9833(&[]);
+    dds.set_mclk_correction_ppm(-1e6);
+    assert_eq!(0, dds.corrected_mclk_frequency());
+    match dds.set_frequency_hz(FreqReg::F0, 1_000.0) {
+        Err(Error::InvalidArgument) => (),
+        _ => panic!("Should return InvalidArgument"),
+    }
+    base::destroy(dds);
+}
+
+#[test]
+fn calibration_measures_correction() {
+    let transactions = [write_words(&[
+        [BF::B28 | BF::RESET, 0],
+        [BF::FREQ0, 0],
+        set_word_1m(),
+    ])];
+    let mut dds = new_ad9833(&transactions);
+    dds.set_mclk_frequency(MCLK);
+    dds.set_mclk_correction_ppm(3.0);
+    let mut meter = Meter(Ok(f64::from(1 << 20) * (1.0 + 20e-6)));
+    let ppm = dds.calibrate_mclk(1 << 20, &mut meter).unwrap();
+    assert!((ppm - 20.0).abs() < 1e-3);
+    assert_eq!(ppm, dds.mclk_correction_ppm());
+    assert_eq!(268_440_825, dds.corrected_mclk_frequency());
+    assert_eq!(Some(1 << 20), dds.frequency(FreqReg::F0));
+    base::destroy(dds);
+}
+
+#[test]
+fn failed_calibration_keeps_correction() {
+    let transactions = [
+        write_words(&[[BF::B28 | BF::RESET, 0], [BF::FREQ0, 0], set_word_1m()]),
+        // Already in 28-bit mode
+        write_words(&[[BF::FREQ0, 0], set_word_1m()]),
+    ];
+    let mut dds = new_ad9833(&transactions);
+    dds.set_mclk_correction_ppm(3.0);
+    match dds.calibrate_mclk(1 << 20, &mut Meter(Err(()))) {
+        Err(CalibrationError::Meter(())) => (),
+        _ => panic!("Should return Meter error"),
+    }
+    match dds.calibrate_mclk(1 << 20, &mut Meter(Ok(0.0))) {
+        Err(CalibrationError::InvalidMeasurement) => (),
+        _ => panic!("Should return InvalidMeasurement"),
+    }
+    match dds.calibrate_mclk(0, &mut Meter(Ok(1.0))) {
+        Err(CalibrationError::Device(Error::InvalidArgument)) => (),
+        _ => panic!("Should return InvalidArgument"),
+    }
+    assert_eq!(3.0, dds.mclk_correction_ppm());
+    base::destroy(dds);
+}
@@ -0,0 +1,152 @@
+use ad983x::chirp::{Chirp, ChirpShape, Chirper};
+
+mod base;
+use crate::base::{new_ad9833, write_words, BitFlags as BF, RecordingDelay};
+
+// With a 2^28 Hz master clock the tuning word matches the frequency in Hz.
+const MCLK: u32 = 1 << 28;
+
+fn words(chirp: &Chirp) -> Vec<u32> {
+    let mut buffer = [0; 16];
+    chirp.tuning_words(MCLK, &mut buffer).unwrap().to_vec()
+}
+
+#[test]
+fn chirp_tuning_words() {
+    assert_eq!(
+        vec![100, 102, 104, 106],
+        words(&Chirp::up(100.0, 106.0, 4_000, 1_000))
+    );
+    assert_eq!(
+        vec![106, 104, 102, 100],
+        words(&Chirp::down(100.0, 106.0, 4_000, 1_000))
+    );
+    let triangle = Chirp::new(ChirpShape::Triangle, 100.0, 106.0, 6_000, 1_000);
+    assert_eq!(vec![100, 102, 104, 106, 104, 102], words(&triangle));
+    assert_eq!(vec![0, 1, 1], words(&Chirp::up(0.0, 1.0, 3, 1_000_000)));
+}
+
+#[test]
+fn chirp_needs_big_enough_buffer() {
+    let chirp = Chirp::up(100.0, 106.0, 4_000, 1_000);
+    assert_eq!(4, chirp.len());
+    assert_eq!(None, chirp.tuning_words(MCLK, &mut [0; 3]));
+    let chirp = Chirp::up(100.0, 1e9, 4_000, 1_000);
+    assert_eq!(None, chirp.tuning_words(MCLK, &mut [0; 4]));
+}
+
+#[test]
+fn steps_write_only_changed_halves() {
+    let transitions = [
+        write_words(&[
+            [BF::B28 | BF::RESET, 0],
+            [BF::FREQ1, 100],
+            [BF::FREQ1, 0],
+            [BF::B28 | BF::FSELECT | BF::RESET, 0],
+        ]),
+        write_words(&[[BF::FREQ0, 102], [BF::FREQ0, 0], [BF::RESET, 0]]),
+        write_words(&[[BF::FREQ1, 104], [BF::FSELECT | BF::RESET, 0]]),
+        write_words(&[[BF::FREQ0, 106], [BF::RESET, 0]]),
+    ];
+    let mut dev = new_ad9833(&transitions);
+    dev.set_mclk_frequency(MCLK);
+    let chirp = Chirp::up(100.0, 106.0, 4_000, 1_000);
+    let words = words(&chirp);
+    let mut chirper = Chirper::new(dev, chirp, &words);
+    for word in &words {
+        assert_eq!(Some(*word), chirper.next_step().unwrap());
+    }
+    assert!(chirper.is_finished());
+    assert_eq!(None, chirper.next_step().unwrap());
+    let dev = chirper.destroy();
+    assert_eq!(Some(104), dev.frequency(ad983x::FrequencyRegister::F1));
+    assert_eq!(Some(106), dev.frequency(ad983x::FrequencyRegister::F0));
+    base::destroy(dev);
+}
+
+#[test]
+fn msb_change_is_prepared_by_previous_step() {
+    let transitions = [
+        write_words(&[
+            [BF::B28 | BF::RESET, 0],
+            [BF::FREQ1 | 0x3F, 0xFF],
+            [BF::FREQ1, 0],
+            [BF::B28 | BF::FSELECT | BF::RESET, 0],
+        ]),
+        write_words(&[
+            [BF::FREQ0 | 0x3F, 0xFF],
+            [BF::FREQ0, 0],
+            [BF::HLB | BF::RESET, 0],
+        ]),
+        write_words(&[[BF::FREQ1, 1], [BF::HLB | BF::FSELECT | BF::RESET, 0]]),
+    ];
+    let mut dev = new_ad9833(&transitions);
+    dev.set_mclk_frequency(MCLK);
+    let table = [0x3FFF, 0x3FFF, 0x7FFF];
+    let chirp = Chirp::up(0.0, 0.0, 3, 1_000_000);
+    let mut chirper = Chirper::new(dev, chirp, &table);
+    while chirper.next_step().unwrap().is_some() {}
+    base::destroy(chirper.destroy());
+}
+
+#[test]
+fn repeating_chirp_starts_over() {
+    let transitions = [
+        write_words(&[
+            [BF::B28 | BF::RESET, 0],
+            [BF::FREQ1, 100],
+            [BF::FREQ1, 0],
+            [BF::B28 | BF::FSELECT | BF::RESET, 0],
+        ]),
+        write_words(&[[BF::FREQ0, 102], [BF::FREQ0, 0], [BF::RESET, 0]]),
+        write_words(&[[BF::FREQ1, 104], [BF::FSELECT | BF::RESET, 0]]),
+        write_words(&[[BF::FREQ0, 100], [BF::RESET, 0]]),
+    ];
+    let mut dev = new_ad9833(&transitions);
+    dev.set_mclk_frequency(MCLK);
+    let table = [100, 102, 104];
+    let chirp = Chirp::up(100.0, 104.0, 3_000, 1_000);
+    let mut chirper = Chirper::new(dev, chirp, &table).with_repeat();
+    for _ in 0..4 {
+        chirper.next_step().unwrap();
+    }
+    assert!(!chirper.is_finished());
+    assert_eq!(1, chirper.position());
+    base::destroy(chirper.destroy());
+}
+
+#[test]
+fn run_waits_update_period() {
+    let transitions = [
+        write_words(&[
+            [BF::B28 | BF::RESET, 0],
+            [BF::FREQ1, 100],
+            [BF::FREQ1, 0],
+            [BF::B28 | BF::FSELECT | BF::RESET, 0],
+        ]),
+        write_words(&[[BF::FREQ0, 103], [BF::FREQ0, 0], [BF::B28 | BF::RESET, 0]]),
+    ];
+    let mut dev = new_ad9833(&transitions);
+    dev.set_mclk_frequency(MCLK);
+    let chirp = Chirp::up(100.0, 103.0, 1_000, 2_000);
+    let words = words(&chirp);
+    let mut chirper = Chirper::new(dev, chirp, &words);
+    let mut delay = RecordingDelay::default();
+    chirper.run(&mut delay).unwrap();
+    assert_eq!(vec![500_000, 500_000], delay.delays_ns);
+    base::destroy(chirper.destroy());
+}
+
+#[test]
+fn achieved_sweep_rate_is_limited_by_spi() {
+    let chirp = Chirp::up(100.0, 106.0, 4_000, 1_000);
+    assert_eq!(1500.0, chirp.sweep_rate());
+    let words = words(&chirp);
+    let chirper = Chirper::new(new_ad9833(&[]), chirp, &words);
+    assert_eq!(3, chirper.step_frames(1));
+    assert_eq!(2, chirper.step_frames(2));
+    assert_eq!(1500.0, chirper.achieved_sweep_rate(1_000));
+    // 3 + 3 + 2 + 2 frames of 0.5 ms: 5 ms instead of 4 ms
+    assert_eq!(1200.0, chirper.achieved_sweep_rate(500_000));
+    base::destroy(chirper.destroy());
+}
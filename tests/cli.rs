@@ -0,0 +1,79 @@
+#![cfg(feature = "cli")]
+
+use std::process::{Command, Output};
+
+fn ad983x(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ad983x"))
+        .arg("--dry-run")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+#[test]
+fn prints_frequency_frames() {
+    let output = ad983x(&["freq", "440"]);
+    assert!(output.status.success());
+    assert_eq!(
+        "SPI frame: 0x5274 0x4000\nF0: 439.9568 Hz\n",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn prints_phase_frames() {
+    let output = ad983x(&["phase", "-90", "--register", "p1"]);
+    assert!(output.status.success());
+    assert_eq!("SPI frame: 0xEC00\nP1: 270°\n", stdout(&output));
+}
+
+#[test]
+fn reset_loads_all_registers() {
+    let output = ad983x(&["--chip", "ad9838", "reset"]);
+    assert!(output.status.success());
+    assert_eq!(
+        "SPI frame: 0x2100 0x4000 0x4000 0x8000 0x8000 0xC000 0xE000 0x2000\n",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn reset_loads_registers_from_state() {
+    let state = std::env::temp_dir().join(format!("ad983x-cli-reset-{}.toml", std::process::id()));
+    let state_arg = state.to_str().unwrap();
+    let _ = std::fs::remove_file(&state);
+    let output = ad983x(&["--state", state_arg, "freq", "1000"]);
+    assert!(output.status.success());
+    let output = ad983x(&["--state", state_arg, "reset"]);
+    assert!(output.status.success());
+    assert_eq!(
+        "SPI frame: 0x2100 0x69F1 0x4000 0x8000 0x8000 0xC000 0xE000 0x2000\n",
+        stdout(&output)
+    );
+    let saved = std::fs::read_to_string(&state).unwrap();
+    assert!(saved.contains("frequency0_hz = 999.96"));
+    std::fs::remove_file(&state).unwrap();
+}
+
+#[test]
+fn fails_for_unsupported_settings() {
+    let output = ad983x(&["--chip", "ad9834", "wave", "square"]);
+    assert!(!output.status.success());
+    assert_eq!("", stdout(&output));
+}
+
+#[test]
+fn keeps_state_between_invocations() {
+    let state = std::env::temp_dir().join(format!("ad983x-cli-{}.toml", std::process::id()));
+    let state_arg = state.to_str().unwrap();
+    let _ = std::fs::remove_file(&state);
+    let output = ad983x(&["--state", state_arg, "select", "f1"]);
+    assert_eq!("SPI frame: 0x2800\n", stdout(&output));
+    let output = ad983x(&["--state", state_arg, "wave", "triangle"]);
+    assert_eq!("SPI frame: 0x2802\n", stdout(&output));
+    std::fs::remove_file(&state).unwrap();
+}
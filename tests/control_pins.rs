@@ -0,0 +1,285 @@
+use ad983x::{
+    interface::{ControlPins, WithControlPins},
+    marker, Ad983x, ControlPin, ControlSource, DeviceConfig, Error, FrequencyRegister as FreqReg,
+    PhaseRegister, PoweredDown,
+};
+use core::{cell::RefCell, convert::Infallible};
+use embedded_hal::{digital, spi};
+use embedded_hal_mock::eh1::{
+    pin::{Mock as PinMock, State, Transaction as PinTrans},
+    spi::{Mock as SpiMock, Transaction as SpiTrans},
+    MockError,
+};
+use std::{io::ErrorKind, rc::Rc};
+
+mod base;
+use crate::base::BitFlags as BF;
+
+type Dds = Ad983x<WithControlPins<SpiMock<u8>, PinMock>, marker::Ad9834Ad9838>;
+
+#[derive(Default)]
+struct Pins<'a> {
+    fselect: Option<&'a [PinTrans]>,
+    reset: Option<&'a [PinTrans]>,
+    sleep: Option<&'a [PinTrans]>,
+}
+
+fn new_ad9834(spi: &[SpiTrans<u8>], pins: Pins) -> Dds {
+    let spi: Vec<SpiTrans<u8>> = spi
+        .iter()
+        .flat_map(|trans| {
+            [
+                SpiTrans::transaction_start(),
+                trans.clone(),
+                SpiTrans::transaction_end(),
+            ]
+        })
+        .collect();
+    let pins = ControlPins {
+        fselect: pins.fselect.map(PinMock::new),
+        reset: pins.reset.map(PinMock::new),
+        sleep: pins.sleep.map(PinMock::new),
+        ..Default::default()
+    };
+    Ad983x::new_ad9834_with_pins(SpiMock::new(&spi), pins)
+}
+
+fn destroy(dds: Dds) {
+    let (mut spi, pins) = dds.destroy().release();
+    spi.done();
+    for mut pin in [pins.fselect, pins.pselect, pins.reset, pins.sleep]
+        .into_iter()
+        .flatten()
+    {
+        pin.done();
+    }
+}
+
+fn set(high: bool) -> PinTrans {
+    PinTrans::set(if high { State::High } else { State::Low })
+}
+
+fn hardware_control() -> SpiTrans<u8> {
+    SpiTrans::write_vec(vec![BF::RESET | BF::PIN_SW, 0])
+}
+
+#[test]
+fn can_create_and_destroy() {
+    destroy(new_ad9834(&[], Pins::default()));
+}
+
+#[test]
+fn software_control_does_not_use_pins() {
+    let spi = [SpiTrans::write_vec(vec![BF::FSELECT | BF::RESET, 0])];
+    let mut dds = new_ad9834(
+        &spi,
+        Pins {
+            fselect: Some(&[]),
+            ..Default::default()
+        },
+    );
+    dds.select_frequency(FreqReg::F1).unwrap();
+    destroy(dds);
+}
+
+#[test]
+fn selecting_hardware_control_sets_pins_to_current_state() {
+    let mut dds = new_ad9834(
+        &[hardware_control()],
+        Pins {
+            fselect: Some(&[set(false)]),
+            reset: Some(&[set(true)]),
+            sleep: Some(&[set(false)]),
+        },
+    );
+    dds.set_control_source(ControlSource::HardwarePins).unwrap();
+    assert_eq!(ControlSource::HardwarePins, dds.control_source());
+    destroy(dds);
+}
+
+#[test]
+fn drives_pins_in_hardware_control() {
+    let mut dds = new_ad9834(
+        &[hardware_control()],
+        Pins {
+            fselect: Some(&[set(false), set(true)]),
+            reset: Some(&[set(true), set(false), set(true)]),
+            sleep: Some(&[set(false), set(true)]),
+        },
+    );
+    dds.set_control_source(ControlSource::HardwarePins).unwrap();
+    dds.select_frequency(FreqReg::F1).unwrap();
+    dds.enable().unwrap();
+    dds.set_powered_down(PoweredDown::Dac).unwrap();
+    dds.disable().unwrap();
+    assert_eq!(FreqReg::F1, dds.selected_frequency());
+    assert_eq!(PoweredDown::Dac, dds.powered_down());
+    assert!(dds.is_reset());
+    destroy(dds);
+}
+
+#[test]
+fn switching_back_to_software_control_keeps_state() {
+    let spi = [
+        hardware_control(),
+        SpiTrans::write_vec(vec![BF::FSELECT, 0]),
+    ];
+    let mut dds = new_ad9834(
+        &spi,
+        Pins {
+            fselect: Some(&[set(false), set(true)]),
+            reset: Some(&[set(true), set(false)]),
+            ..Default::default()
+        },
+    );
+    dds.set_control_source(ControlSource::HardwarePins).unwrap();
+    dds.select_frequency(FreqReg::F1).unwrap();
+    dds.enable().unwrap();
+    dds.set_control_source(ControlSource::Software).unwrap();
+    assert_eq!(FreqReg::F1, dds.selected_frequency());
+    assert!(!dds.is_reset());
+    destroy(dds);
+}
+
+#[test]
+fn returns_error_for_missing_pin() {
+    let mut dds = new_ad9834(&[hardware_control()], Pins::default());
+    dds.set_control_source(ControlSource::HardwarePins).unwrap();
+    match dds.select_phase(PhaseRegister::P1) {
+        Err(Error::PinNotAssigned(ControlPin::PSelect)) => (),
+        _ => panic!("Should return pin not assigned error"),
+    }
+    assert_eq!(PhaseRegister::P0, dds.selected_phase());
+    destroy(dds);
+}
+
+#[test]
+fn cannot_power_down_internal_clock_in_hardware_control() {
+    let mut dds = new_ad9834(
+        &[hardware_control()],
+        Pins {
+            sleep: Some(&[set(false)]),
+            ..Default::default()
+        },
+    );
+    dds.set_control_source(ControlSource::HardwarePins).unwrap();
+    match dds.set_powered_down(PoweredDown::InternalClock) {
+        Err(Error::InvalidArgument) => (),
+        _ => panic!("Should return invalid argument error"),
+    }
+    destroy(dds);
+}
+
+#[test]
+fn reports_pin_error() {
+    let error = MockError::Io(ErrorKind::Other);
+    let mut dds = new_ad9834(
+        &[hardware_control()],
+        Pins {
+            fselect: Some(&[set(false), set(true).with_error(error.clone())]),
+            ..Default::default()
+        },
+    );
+    dds.set_control_source(ControlSource::HardwarePins).unwrap();
+    match dds.select_frequency(FreqReg::F1) {
+        Err(Error::Pin(e)) => assert_eq!(error, e),
+        _ => panic!("Should return pin error"),
+    }
+    assert_eq!(FreqReg::F0, dds.selected_frequency());
+    destroy(dds);
+}
+
+/// Bus activity on the SPI bus and the control pins, in order
+#[derive(Debug, PartialEq)]
+enum Event {
+    Write(Vec<u8>),
+    Pin(ControlPin, bool),
+}
+
+type Log = Rc<RefCell<Vec<Event>>>;
+
+struct RecordingSpi(Log);
+
+impl spi::ErrorType for RecordingSpi {
+    type Error = Infallible;
+}
+
+impl spi::SpiDevice for RecordingSpi {
+    fn transaction(&mut self, operations: &mut [spi::Operation<'_, u8>]) -> Result<(), Infallible> {
+        for operation in operations {
+            if let spi::Operation::Write(data) = operation {
+                self.0.borrow_mut().push(Event::Write(data.to_vec()));
+            }
+        }
+        Ok(())
+    }
+}
+
+struct RecordingPin(ControlPin, Log);
+
+impl digital::ErrorType for RecordingPin {
+    type Error = Infallible;
+}
+
+impl digital::OutputPin for RecordingPin {
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        self.1.borrow_mut().push(Event::Pin(self.0, false));
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        self.1.borrow_mut().push(Event::Pin(self.0, true));
+        Ok(())
+    }
+}
+
+#[test]
+fn first_apply_loads_registers_with_reset_pin_held() {
+    let log = Log::default();
+    let pin = |pin| Some(RecordingPin(pin, log.clone()));
+    let pins = ControlPins {
+        fselect: pin(ControlPin::FSelect),
+        pselect: pin(ControlPin::PSelect),
+        reset: pin(ControlPin::Reset),
+        sleep: pin(ControlPin::Sleep),
+    };
+    let mut dds = Ad983x::new_ad9834_with_pins(RecordingSpi(log.clone()), pins);
+    let config = DeviceConfig {
+        frequency1: 1000,
+        selected_frequency: FreqReg::F1,
+        control_source: ControlSource::HardwarePins,
+        ..Default::default()
+    };
+    dds.apply(&config).unwrap();
+    let load = [
+        [BF::B28 | BF::RESET, 0],
+        [BF::FREQ0, 0],
+        [BF::FREQ0, 0],
+        [BF::FREQ1 | 0x3, 0xE8],
+        [BF::FREQ1, 0],
+        [BF::D15 | BF::D14, 0],
+        [BF::D15 | BF::D14 | BF::D13, 0],
+        [BF::B28 | BF::FSELECT | BF::PIN_SW, 0],
+    ];
+    let expected = vec![
+        Event::Pin(ControlPin::Reset, true),
+        Event::Write(load.concat()),
+        Event::Pin(ControlPin::FSelect, true),
+        Event::Pin(ControlPin::PSelect, false),
+        Event::Pin(ControlPin::Sleep, false),
+        Event::Pin(ControlPin::Reset, false),
+    ];
+    assert_eq!(expected, *log.borrow());
+    assert!(!dds.is_reset());
+
+    // Registers already loaded: no reset
+    log.borrow_mut().clear();
+    dds.apply(&config).unwrap();
+    let expected = vec![
+        Event::Pin(ControlPin::FSelect, true),
+        Event::Pin(ControlPin::PSelect, false),
+        Event::Pin(ControlPin::Sleep, false),
+        Event::Pin(ControlPin::Reset, false),
+    ];
+    assert_eq!(expected, *log.borrow());
+}
@@ -0,0 +1,96 @@
+use ad983x::fsk::{BitOrder, Framing, FskConfig, FskModulator};
+use embedded_hal_mock::eh1::spi::Transaction as SpiTrans;
+
+mod base;
+use crate::base::{destroy, new_ad9833, write_words, BitFlags as BF, RecordingDelay};
+
+// With a 2^28 Hz master clock the tuning word matches the frequency in Hz.
+const MCLK: u32 = 1 << 28;
+
+fn init_transitions() -> Vec<SpiTrans<u8>> {
+    vec![
+        write_words(&[
+            [BF::B28 | BF::RESET, 0],
+            [BF::FREQ0 | 0x8, 0x98],
+            [BF::FREQ0, 0],
+        ]),
+        write_words(&[[BF::FREQ1 | 0x4, 0xB0], [BF::FREQ1, 0]]),
+        SpiTrans::write_vec(vec![BF::B28 | BF::FSELECT | BF::RESET, 0]),
+    ]
+}
+
+const MARK: [u8; 2] = [BF::B28 | BF::FSELECT | BF::RESET, 0];
+const SPACE: [u8; 2] = [BF::B28 | BF::RESET, 0];
+
+#[test]
+fn can_init() {
+    let mut dev = new_ad9833(&init_transitions());
+    dev.set_mclk_frequency(MCLK);
+    let mut fsk = FskModulator::new(dev, FskConfig::new(1200.0, 2200.0, 1200));
+    fsk.init().unwrap();
+    destroy(fsk.destroy());
+}
+
+#[test]
+fn cannot_init_with_zero_baud_rate() {
+    let mut fsk = FskModulator::new(new_ad9833(&[]), FskConfig::new(1200.0, 2200.0, 0));
+    fsk.init().expect_err("Should return error");
+    destroy(fsk.destroy());
+}
+
+#[test]
+fn can_send_bits_only_switching_on_change() {
+    let mut transitions = init_transitions();
+    transitions.push(SpiTrans::write_vec(SPACE.to_vec()));
+    transitions.push(SpiTrans::write_vec(MARK.to_vec()));
+    let mut dev = new_ad9833(&transitions);
+    dev.set_mclk_frequency(MCLK);
+    let mut fsk = FskModulator::new(dev, FskConfig::new(1200.0, 2200.0, 3));
+    fsk.init().unwrap();
+    let mut delay = RecordingDelay::default();
+    fsk.send_bits([false, false, true], &mut delay).unwrap();
+    // 1/3 s does not divide into whole nanoseconds: the remainder is carried over.
+    assert_eq!(vec![333_333_333, 333_333_333, 333_333_334], delay.delays_ns);
+    destroy(fsk.destroy());
+}
+
+#[test]
+fn can_send_byte_with_uart_framing() {
+    let mut transitions = init_transitions();
+    // start bit, then 0x0E LSB first: 0 1 1 1 0 0 0 0, then stop bit
+    for word in [SPACE, MARK, SPACE, MARK] {
+        transitions.push(SpiTrans::write_vec(word.to_vec()));
+    }
+    let mut dev = new_ad9833(&transitions);
+    dev.set_mclk_frequency(MCLK);
+    let config = FskConfig::new(1200.0, 2200.0, 1000).with_framing(Framing::UART_8N1);
+    let mut fsk = FskModulator::new(dev, config);
+    fsk.init().unwrap();
+    let mut delay = RecordingDelay::default();
+    fsk.send_bytes(&[0x0E], &mut delay).unwrap();
+    assert_eq!(vec![1_000_000; 10], delay.delays_ns);
+    destroy(fsk.destroy());
+}
+
+#[test]
+fn can_send_byte_msb_first_with_two_stop_bits() {
+    let mut transitions = init_transitions();
+    // 0x80 MSB first: 1 0 0 0 0 0 0 0, then two stop bits
+    for word in [SPACE, MARK] {
+        transitions.push(SpiTrans::write_vec(word.to_vec()));
+    }
+    let mut dev = new_ad9833(&transitions);
+    dev.set_mclk_frequency(MCLK);
+    let framing = Framing {
+        start_bits: 0,
+        stop_bits: 2,
+        bit_order: BitOrder::MsbFirst,
+    };
+    let config = FskConfig::new(1200.0, 2200.0, 1000).with_framing(framing);
+    let mut fsk = FskModulator::new(dev, config);
+    fsk.init().unwrap();
+    let mut delay = RecordingDelay::default();
+    fsk.send_byte(0x80, &mut delay).unwrap();
+    assert_eq!(10, delay.delays_ns.len());
+    destroy(fsk.destroy());
+}
@@ -1,11 +1,13 @@
 use ad983x::{
-    ControlSource, FrequencyRegister as FreqReg, OutputWaveform as OW, PhaseRegister as PhaseReg,
-    PoweredDown as PD, SignBitOutput as SBO,
+    ControlSource, FrequencyRegister as FreqReg, OutputWaveform as OW, Phase,
+    PhaseRegister as PhaseReg, PoweredDown as PD, SignBitOutput as SBO,
 };
 use embedded_hal_mock::eh1::spi::Transaction as SpiTrans;
 
 mod base;
-use crate::base::{destroy, new_ad9833, new_ad9834, new_ad9837, new_ad9838, BitFlags as BF};
+use crate::base::{
+    destroy, new_ad9833, new_ad9834, new_ad9837, new_ad9838, write_words, BitFlags as BF,
+};
 
 #[test]
 fn can_create_and_destroy_ad9833() {
@@ -65,11 +67,11 @@ fn cannot_set_too_fast_frequency() {
 
 #[test]
 fn can_set_freq0() {
-    let transitions = [
-        SpiTrans::write_vec(vec![BF::B28 | BF::RESET, 0]),
-        SpiTrans::write_vec(vec![BF::FREQ0 | 0xD, 0xEF]),
-        SpiTrans::write_vec(vec![BF::FREQ0 | 0x26, 0xAF]),
-    ];
+    let transitions = [write_words(&[
+        [BF::B28 | BF::RESET, 0],
+        [BF::FREQ0 | 0xD, 0xEF],
+        [BF::FREQ0 | 0x26, 0xAF],
+    ])];
     let mut dev = new_ad9833(&transitions);
     dev.set_frequency(FreqReg::F0, 0x9AB_CDEF).unwrap();
     destroy(dev);
@@ -77,11 +79,11 @@ fn can_set_freq0() {
 
 #[test]
 fn can_set_freq1() {
-    let transitions = [
-        SpiTrans::write_vec(vec![BF::B28 | BF::RESET, 0]),
-        SpiTrans::write_vec(vec![BF::FREQ1 | 0xD, 0xEF]),
-        SpiTrans::write_vec(vec![BF::FREQ1 | 0x26, 0xAF]),
-    ];
+    let transitions = [write_words(&[
+        [BF::B28 | BF::RESET, 0],
+        [BF::FREQ1 | 0xD, 0xEF],
+        [BF::FREQ1 | 0x26, 0xAF],
+    ])];
     let mut dev = new_ad9833(&transitions);
     dev.set_frequency(FreqReg::F1, 0x9AB_CDEF).unwrap();
     destroy(dev);
@@ -153,6 +155,7 @@ macro_rules! ow_test {
             let transitions = [SpiTrans::write_vec(vec![BF::RESET, $control])];
             let mut dev = $create(&transitions);
             dev.set_output_waveform(OW::$ow).unwrap();
+            assert_eq!(OW::$ow, dev.output_waveform());
             destroy(dev);
         }
     };
@@ -182,6 +185,7 @@ macro_rules! pd_test {
             let transitions = [SpiTrans::write_vec(vec![BF::RESET, $control])];
             let mut dev = new_ad9833(&transitions);
             dev.set_powered_down(PD::$pd).unwrap();
+            assert_eq!(PD::$pd, dev.powered_down());
             destroy(dev);
         }
     };
@@ -214,10 +218,10 @@ fn cannot_set_wrong_freq_lsb() {
 
 #[test]
 fn can_set_freq_msb() {
-    let transitions = [
-        SpiTrans::write_vec(vec![BF::HLB | BF::RESET, 0]),
-        SpiTrans::write_vec(vec![BF::FREQ0 | 0xD, 0xEF]),
-    ];
+    let transitions = [write_words(&[
+        [BF::HLB | BF::RESET, 0],
+        [BF::FREQ0 | 0xD, 0xEF],
+    ])];
     let mut dev = new_ad9833(&transitions);
     dev.set_frequency_msb(FreqReg::F0, 0xDEF).unwrap();
     destroy(dev);
@@ -254,6 +258,7 @@ macro_rules! sbo_test {
             let transitions = [SpiTrans::write_vec(vec![BF::RESET, $control])];
             let mut dev = new_ad9838(&transitions);
             dev.set_sign_bit_output(SBO::$sbo).unwrap();
+            assert_eq!(SBO::$sbo, dev.sign_bit_output());
             destroy(dev);
         }
     };
@@ -281,6 +286,7 @@ fn can_set_control_source_sw() {
     let transitions = [SpiTrans::write_vec(vec![BF::RESET, 0])];
     let mut dev = new_ad9838(&transitions);
     dev.set_control_source(ControlSource::Software).unwrap();
+    assert_eq!(ControlSource::Software, dev.control_source());
     destroy(dev);
 }
 
@@ -289,5 +295,244 @@ fn can_set_control_source_hw_pins() {
     let transitions = [SpiTrans::write_vec(vec![BF::RESET | BF::PIN_SW, 0])];
     let mut dev = new_ad9838(&transitions);
     dev.set_control_source(ControlSource::HardwarePins).unwrap();
+    assert_eq!(ControlSource::HardwarePins, dev.control_source());
+    destroy(dev);
+}
+
+#[test]
+fn can_set_freq0_hz() {
+    // 440 Hz with 25 MHz MCLK -> 4724 (0x1274)
+    let transitions = [write_words(&[
+        [BF::B28 | BF::RESET, 0],
+        [BF::FREQ0 | 0x12, 0x74],
+        [BF::FREQ0, 0],
+    ])];
+    let mut dev = new_ad9833(&transitions);
+    let actual = dev.set_frequency_hz(FreqReg::F0, 440.0).unwrap();
+    assert!((actual - 439.957).abs() < 0.001);
+    destroy(dev);
+}
+
+#[test]
+fn can_set_freq1_millihertz_with_mclk() {
+    // 1 kHz with 1 MHz MCLK -> 268435.456 -> 268435 (0x4_1893)
+    let transitions = [write_words(&[
+        [BF::B28 | BF::RESET, 0],
+        [BF::FREQ1 | 0x18, 0x93],
+        [BF::FREQ1, 0x10],
+    ])];
+    let mut dev = new_ad9833(&transitions);
+    dev.set_mclk_frequency(1_000_000);
+    assert_eq!(1_000_000, dev.mclk_frequency());
+    let actual = dev
+        .set_frequency_millihertz(FreqReg::F1, 1_000_000)
+        .unwrap();
+    assert_eq!(999_998, actual);
+    destroy(dev);
+}
+
+#[test]
+fn cannot_set_too_fast_frequency_hz() {
+    let mut dev = new_ad9833(&[]);
+    dev.set_frequency_hz(FreqReg::F0, 25_000_000.0)
+        .expect_err("Should return error");
+    dev.set_frequency_hz(FreqReg::F0, -1.0)
+        .expect_err("Should return error");
+    dev.set_frequency_hz(FreqReg::F0, f32::NAN)
+        .expect_err("Should return error");
+    dev.set_frequency_millihertz(FreqReg::F0, 25_000_000_000)
+        .expect_err("Should return error");
+    destroy(dev);
+}
+
+#[test]
+fn can_set_phase0_degrees() {
+    // -90° wraps to 270° -> 3072 (0xC00)
+    let transitions = [SpiTrans::write_vec(vec![BF::D15 | BF::D14 | 0xC, 0])];
+    let mut dev = new_ad9833(&transitions);
+    let actual = dev.set_phase_degrees(PhaseReg::P0, -90.0).unwrap();
+    assert_eq!(270.0, actual);
+    destroy(dev);
+}
+
+#[test]
+fn can_set_phase1_radians() {
+    // 2π + π/2 wraps to π/2 -> 1024 (0x400)
+    let transitions = [SpiTrans::write_vec(vec![
+        BF::D15 | BF::D14 | BF::D13 | 0x4,
+        0,
+    ])];
+    let mut dev = new_ad9833(&transitions);
+    let actual = dev
+        .set_phase_radians(PhaseReg::P1, 2.5 * core::f32::consts::PI)
+        .unwrap();
+    assert!((actual - core::f32::consts::FRAC_PI_2).abs() < 1e-6);
+    destroy(dev);
+}
+
+#[test]
+fn can_set_phase_turns_rounded() {
+    // 0.1° is closest to step 1 (0.088°)
+    let transitions = [
+        SpiTrans::write_vec(vec![BF::D15 | BF::D14, 1]),
+        SpiTrans::write_vec(vec![BF::D15 | BF::D14 | BF::D13 | 0xC, 0]),
+    ];
+    let mut dev = new_ad9833(&transitions);
+    let actual = dev.set_phase_turns(PhaseReg::P0, 0.1 / 360.0).unwrap();
+    assert_eq!(Phase::from_register_value(1).turns(), actual);
+    assert_eq!(0.75, dev.set_phase_turns(PhaseReg::P1, -0.25).unwrap());
+    destroy(dev);
+}
+
+#[test]
+fn can_set_phase_value_rounded() {
+    let transitions = [SpiTrans::write_vec(vec![BF::D15 | BF::D14 | BF::D13, 1])];
+    let mut dev = new_ad9833(&transitions);
+    let actual = dev
+        .set_phase_value(PhaseReg::P1, Phase::from_degrees(0.1))
+        .unwrap();
+    assert_eq!(Phase::from_register_value(1), actual);
+    destroy(dev);
+}
+
+#[test]
+fn cannot_set_non_finite_phase() {
+    let mut dev = new_ad9833(&[]);
+    dev.set_phase_degrees(PhaseReg::P0, f32::INFINITY)
+        .expect_err("Should return error");
+    dev.set_phase_radians(PhaseReg::P0, f32::NAN)
+        .expect_err("Should return error");
+    dev.set_phase_turns(PhaseReg::P0, f32::NEG_INFINITY)
+        .expect_err("Should return error");
+    destroy(dev);
+}
+
+#[test]
+fn phase_wraps_and_rounds() {
+    assert_eq!(Phase::ZERO, Phase::from_degrees(360.0));
+    assert_eq!(Phase::from_degrees(10.0), Phase::from_degrees(-350.0));
+    assert_eq!(0, Phase::from_degrees(359.99).register_value());
+    assert_eq!(2048, Phase::from_turns(0.5).register_value());
+    assert_eq!(
+        Phase::from_degrees(30.0),
+        Phase::from_degrees(90.0) + Phase::from_degrees(300.0)
+    );
+    assert_eq!(Phase::from_degrees(270.0), -Phase::from_degrees(90.0));
+}
+
+#[test]
+fn can_retune_alternating_registers() {
+    let transitions = [
+        write_words(&[
+            [BF::B28 | BF::RESET, 0],
+            [BF::FREQ1 | 0xD, 0xEF],
+            [BF::FREQ1 | 0x26, 0xAF],
+            [BF::B28 | BF::FSELECT | BF::RESET, 0],
+        ]),
+        write_words(&[
+            [BF::FREQ0 | 0x12, 0x74],
+            [BF::FREQ0, 0],
+            [BF::B28 | BF::RESET, 0],
+        ]),
+    ];
+    let mut dev = new_ad9833(&transitions);
+    assert_eq!(FreqReg::F1, dev.retune(0x9AB_CDEF).unwrap());
+    let actual = dev.retune_hz(440.0).unwrap();
+    assert!((actual - 439.957).abs() < 0.001);
+    destroy(dev);
+}
+
+#[test]
+fn retune_uses_register_not_selected() {
+    let transitions = [
+        SpiTrans::write_vec(vec![BF::FSELECT | BF::RESET, 0]),
+        write_words(&[
+            [BF::B28 | BF::FSELECT | BF::RESET, 0],
+            [BF::FREQ0 | 0xD, 0xEF],
+            [BF::FREQ0 | 0x26, 0xAF],
+            [BF::B28 | BF::RESET, 0],
+        ]),
+    ];
+    let mut dev = new_ad9833(&transitions);
+    dev.select_frequency(FreqReg::F1).unwrap();
+    assert_eq!(FreqReg::F0, dev.retune(0x9AB_CDEF).unwrap());
+    destroy(dev);
+}
+
+#[test]
+fn cannot_retune_too_fast_frequency() {
+    let mut dev = new_ad9833(&[]);
+    dev.retune(1 << 28).expect_err("Should return error");
+    destroy(dev);
+}
+
+#[test]
+fn registers_are_unknown_after_creation() {
+    let dev = new_ad9833(&[]);
+    assert_eq!(None, dev.frequency(FreqReg::F0));
+    assert_eq!(None, dev.frequency(FreqReg::F1));
+    assert_eq!(None, dev.phase(PhaseReg::P0));
+    assert_eq!(None, dev.phase(PhaseReg::P1));
+    assert_eq!(FreqReg::F0, dev.selected_frequency());
+    assert_eq!(PhaseReg::P0, dev.selected_phase());
+    assert_eq!(OW::Sinusoidal, dev.output_waveform());
+    assert_eq!(PD::Nothing, dev.powered_down());
+    assert!(dev.is_reset());
+    destroy(dev);
+}
+
+#[test]
+fn can_read_back_freq_and_phase() {
+    let transitions = [
+        write_words(&[
+            [BF::B28 | BF::RESET, 0],
+            [BF::FREQ1 | 0xD, 0xEF],
+            [BF::FREQ1 | 0x26, 0xAF],
+        ]),
+        SpiTrans::write_vec(vec![BF::D15 | BF::D14 | BF::D13 | 0xA, 0xBC]),
+        SpiTrans::write_vec(vec![BF::B28, 0]),
+    ];
+    let mut dev = new_ad9833(&transitions);
+    dev.set_frequency(FreqReg::F1, 0x9AB_CDEF).unwrap();
+    dev.set_phase(PhaseReg::P1, 0xABC).unwrap();
+    dev.enable().unwrap();
+    assert_eq!(Some(0x9AB_CDEF), dev.frequency(FreqReg::F1));
+    assert_eq!(Some(0x26AF), dev.frequency_msb(FreqReg::F1));
+    assert_eq!(Some(0xDEF), dev.frequency_lsb(FreqReg::F1));
+    assert_eq!(None, dev.frequency(FreqReg::F0));
+    assert_eq!(Some(0xABC), dev.phase(PhaseReg::P1));
+    assert_eq!(None, dev.phase(PhaseReg::P0));
+    assert!(!dev.is_reset());
+    destroy(dev);
+}
+
+#[test]
+fn can_read_back_half_written_freq() {
+    let transitions = [
+        write_words(&[[BF::HLB | BF::RESET, 0], [BF::FREQ0 | 0x26, 0xAF]]),
+        write_words(&[[BF::RESET, 0], [BF::FREQ0 | 0xD, 0xEF]]),
+    ];
+    let mut dev = new_ad9833(&transitions);
+    dev.set_frequency_msb(FreqReg::F0, 0x26AF).unwrap();
+    assert_eq!(Some(0x26AF), dev.frequency_msb(FreqReg::F0));
+    assert_eq!(None, dev.frequency_lsb(FreqReg::F0));
+    assert_eq!(None, dev.frequency(FreqReg::F0));
+    dev.set_frequency_lsb(FreqReg::F0, 0xDEF).unwrap();
+    assert_eq!(Some(0x9AB_CDEF), dev.frequency(FreqReg::F0));
+    destroy(dev);
+}
+
+#[test]
+fn retune_updates_read_back_registers() {
+    let transitions = [write_words(&[
+        [BF::B28 | BF::RESET, 0],
+        [BF::FREQ1 | 0xD, 0xEF],
+        [BF::FREQ1 | 0x26, 0xAF],
+        [BF::B28 | BF::FSELECT | BF::RESET, 0],
+    ])];
+    let mut dev = new_ad9833(&transitions);
+    dev.retune(0x9AB_CDEF).unwrap();
+    assert_eq!(FreqReg::F1, dev.selected_frequency());
+    assert_eq!(Some(0x9AB_CDEF), dev.frequency(FreqReg::F1));
     destroy(dev);
 }
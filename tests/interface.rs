@@ -0,0 +1,79 @@
+use ad983x::{interface::SpiBusInterface, marker, Ad983x, Error, FrequencyRegister as FreqReg};
+use embedded_hal_mock::eh1::{
+    pin::{Mock as PinMock, State, Transaction as PinTrans},
+    spi::{Mock as SpiMock, Transaction as SpiTrans},
+    MockError,
+};
+use std::io::ErrorKind;
+
+mod base;
+use crate::base::{write_words, BitFlags as BF};
+
+type Dds = Ad983x<SpiBusInterface<SpiMock<u8>, PinMock>, marker::Ad9833Ad9837>;
+
+fn new_ad9833(spi: &[SpiTrans<u8>], fsync: &[PinTrans]) -> Dds {
+    let interface = SpiBusInterface::new(SpiMock::new(spi), PinMock::new(fsync));
+    Ad983x::new_ad9833(interface)
+}
+
+fn destroy(dds: Dds) {
+    let (mut spi, mut fsync) = dds.destroy().release();
+    spi.done();
+    fsync.done();
+}
+
+fn frame() -> [PinTrans; 2] {
+    [PinTrans::set(State::Low), PinTrans::set(State::High)]
+}
+
+#[test]
+fn can_create_and_destroy() {
+    destroy(new_ad9833(&[], &[]));
+}
+
+#[test]
+fn drives_fsync_around_each_update() {
+    let spi = [
+        write_words(&[
+            [BF::B28 | BF::RESET, 0],
+            [BF::FREQ0 | 0xD, 0xEF],
+            [BF::FREQ0 | 0x26, 0xAF],
+        ]),
+        SpiTrans::flush(),
+        SpiTrans::write_vec(vec![BF::B28, 0]),
+        SpiTrans::flush(),
+    ];
+    let fsync: Vec<PinTrans> = (0..2).flat_map(|_| frame()).collect();
+    let mut dds = new_ad9833(&spi, &fsync);
+    dds.set_frequency(FreqReg::F0, 0x9AB_CDEF).unwrap();
+    dds.enable().unwrap();
+    destroy(dds);
+}
+
+#[test]
+fn reports_fsync_error() {
+    let error = MockError::Io(ErrorKind::Other);
+    let fsync = [PinTrans::set(State::Low).with_error(error.clone())];
+    let mut dds = new_ad9833(&[], &fsync);
+    match dds.enable() {
+        Err(Error::Pin(e)) => assert_eq!(error, e),
+        _ => panic!("Should return pin error"),
+    }
+    destroy(dds);
+}
+
+#[test]
+fn reports_fsync_error_after_write() {
+    let error = MockError::Io(ErrorKind::Other);
+    let spi = [SpiTrans::write_vec(vec![0, 0]), SpiTrans::flush()];
+    let fsync = [
+        PinTrans::set(State::Low),
+        PinTrans::set(State::High).with_error(error.clone()),
+    ];
+    let mut dds = new_ad9833(&spi, &fsync);
+    match dds.enable() {
+        Err(Error::Pin(e)) => assert_eq!(error, e),
+        _ => panic!("Should return pin error"),
+    }
+    destroy(dds);
+}
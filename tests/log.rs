@@ -0,0 +1,65 @@
+#![cfg(feature = "log")]
+
+use ad983x::{FrequencyRegister as FreqReg, OutputWaveform, PhaseRegister};
+use embedded_hal_mock::eh1::spi::Transaction as SpiTrans;
+use log::{LevelFilter, Log, Metadata, Record};
+use std::sync::Mutex;
+
+mod base;
+use crate::base::{destroy, new_ad9833, write_words, BitFlags as BF};
+
+static LINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct Logger;
+
+impl Log for Logger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        LINES.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: Logger = Logger;
+
+// All in one test since the logger is global
+#[test]
+fn traces_decoded_words() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+    let transactions = [
+        write_words(&[
+            [BF::B28 | BF::RESET, 0],
+            [BF::FREQ1 | 0xD, 0xEF],
+            [BF::FREQ1 | 0x26, 0xAF],
+        ]),
+        SpiTrans::write_vec(vec![BF::D15 | BF::D14 | BF::D13 | 0xA, 0xBC]),
+        write_words(&[[BF::HLB | BF::RESET, 0], [BF::FREQ0 | 0x1, 0x23]]),
+        SpiTrans::write_vec(vec![BF::HLB | BF::FSELECT | BF::RESET, 0]),
+        SpiTrans::write_vec(vec![BF::HLB | BF::FSELECT | BF::RESET, BF::MODE]),
+    ];
+    let mut dev = new_ad9833(&transactions);
+    dev.set_frequency(FreqReg::F1, 0x9AB_CDEF).unwrap();
+    dev.set_phase(PhaseRegister::P1, 0xABC).unwrap();
+    dev.set_frequency_msb(FreqReg::F0, 0x123).unwrap();
+    dev.select_frequency(FreqReg::F1).unwrap();
+    dev.set_output_waveform(OutputWaveform::Triangle).unwrap();
+    destroy(dev);
+    assert_eq!(
+        vec![
+            "CTRL B28|RESET",
+            "FREQ1 LSB=0x0DEF",
+            "FREQ1 MSB=0x26AF",
+            "PHASE1=0x0ABC",
+            "CTRL HLB|RESET",
+            "FREQ0 MSB=0x0123",
+            "CTRL HLB|FSELECT|RESET",
+            "CTRL HLB|FSELECT|RESET|MODE",
+        ],
+        *LINES.lock().unwrap()
+    );
+}
@@ -0,0 +1,247 @@
+use ad983x::music::{
+    smf::{Division, Smf, SmfError, Step},
+    Note, Player, Tuning, JUST_INTONATION,
+};
+
+mod base;
+use crate::base::{new_ad9833, write_words, BitFlags as BF, RecordingDelay};
+
+// With a 2^28 Hz master clock the tuning word matches the frequency in Hz.
+const MCLK: u32 = 1 << 28;
+
+fn smf(format: u16, division: u16, tracks: &[&[u8]]) -> Vec<u8> {
+    let mut data = b"MThd".to_vec();
+    data.extend_from_slice(&6u32.to_be_bytes());
+    data.extend_from_slice(&format.to_be_bytes());
+    data.extend_from_slice(&(tracks.len() as u16).to_be_bytes());
+    data.extend_from_slice(&division.to_be_bytes());
+    for track in tracks {
+        data.extend_from_slice(b"MTrk");
+        data.extend_from_slice(&(track.len() as u32).to_be_bytes());
+        data.extend_from_slice(track);
+    }
+    data
+}
+
+fn note(name: &str) -> Option<Note> {
+    Some(name.parse().unwrap())
+}
+
+fn step(time_us: u64, name: &str) -> Step {
+    Step {
+        time_us,
+        note: note(name),
+    }
+}
+
+fn rest(time_us: u64) -> Step {
+    Step {
+        time_us,
+        note: None,
+    }
+}
+
+// A4 for a quarter note, a quarter rest and A5 for a quarter note at 480 ticks per quarter.
+// The note off of A5 uses running status and a note on with zero velocity.
+const A4_REST_A5: &[u8] = &[
+    0x00, 0x90, 69, 100, //
+    0x83, 0x60, 0x80, 69, 0, //
+    0x83, 0x60, 0x90, 81, 100, //
+    0x83, 0x60, 81, 0, //
+    0x00, 0xFF, 0x2F, 0x00,
+];
+
+#[test]
+fn can_parse_note_names() {
+    assert_eq!(Some(Note::A4), note("A4"));
+    assert_eq!(60, "C4".parse::<Note>().unwrap().midi());
+    assert_eq!(61, "C#4".parse::<Note>().unwrap().midi());
+    assert_eq!(58, "Bb3".parse::<Note>().unwrap().midi());
+    assert_eq!(59, "Cb4".parse::<Note>().unwrap().midi());
+    assert_eq!(0, "C-1".parse::<Note>().unwrap().midi());
+    assert_eq!(127, "G9".parse::<Note>().unwrap().midi());
+    assert!("G#9".parse::<Note>().is_err());
+    assert!("Cb-1".parse::<Note>().is_err());
+    assert!("H4".parse::<Note>().is_err());
+    assert!("C#b4".parse::<Note>().is_err());
+    assert!("C".parse::<Note>().is_err());
+}
+
+#[test]
+fn displays_note_names_with_sharps() {
+    assert_eq!("A#3", Note::from_midi(58).unwrap().to_string());
+    assert_eq!("C-1", Note::from_midi(0).unwrap().to_string());
+    assert_eq!(None, Note::from_midi(128));
+}
+
+#[test]
+fn equal_temperament_frequencies() {
+    assert_eq!(440.0, Note::A4.frequency());
+    assert_eq!(880.0, note("A5").unwrap().frequency());
+    assert!((note("C4").unwrap().frequency() - 261.6256).abs() < 1e-3);
+    let tuning = Tuning::equal_temperament(432.0);
+    assert_eq!(432.0, tuning.frequency(Note::A4));
+    assert_eq!(216.0, tuning.frequency(note("A3").unwrap()));
+}
+
+#[test]
+fn just_intonation_has_pure_intervals() {
+    let tuning = Tuning::default().with_temperament(9, JUST_INTONATION);
+    assert_eq!(440.0, tuning.frequency(Note::A4));
+    // Perfect fifth 3:2 and major third 5:4 above the root
+    assert!((tuning.frequency(note("E5").unwrap()) - 660.0).abs() < 0.01);
+    assert!((tuning.frequency(note("C#5").unwrap()) - 550.0).abs() < 0.01);
+}
+
+#[test]
+fn can_parse_format_0_file() {
+    let data = smf(0, 480, &[A4_REST_A5]);
+    let smf = Smf::parse(&data).unwrap();
+    assert_eq!(0, smf.format());
+    assert_eq!(1, smf.track_count());
+    assert_eq!(Division::TicksPerQuarter(480), smf.division());
+    let steps: Vec<Step> = smf.melody(0).unwrap().collect();
+    let expected = [
+        step(0, "A4"),
+        rest(500_000),
+        step(1_000_000, "A5"),
+        rest(1_500_000),
+    ];
+    assert_eq!(expected.to_vec(), steps);
+}
+
+#[test]
+fn format_1_melody_follows_tempo_track() {
+    let tempo: &[u8] = &[
+        0x00, 0xFF, 0x51, 0x03, 0x03, 0xD0, 0x90, // 250 ms per quarter
+        0x83, 0x60, 0xFF, 0x51, 0x03, 0x0F, 0x42, 0x40, // 1 s per quarter
+        0x00, 0xFF, 0x2F, 0x00,
+    ];
+    // C4 and then E4 overlapping it, with the note off of C4 while E4 sounds
+    let notes: &[u8] = &[
+        0x00, 0xC0, 0x05, // program change
+        0x00, 0x90, 60, 100, //
+        0x83, 0x60, 64, 100, //
+        0x78, 0x80, 60, 0, //
+        0x82, 0x68, 0x80, 64, 0, //
+        0x00, 0xFF, 0x2F, 0x00,
+    ];
+    let data = smf(1, 480, &[tempo, notes]);
+    let smf = Smf::parse(&data).unwrap();
+    assert_eq!(2, smf.track_count());
+    let steps: Vec<Step> = smf.melody(1).unwrap().collect();
+    let expected = [step(0, "C4"), step(250_000, "E4"), rest(1_250_000)];
+    assert_eq!(expected.to_vec(), steps);
+    assert_eq!(0, smf.melody(0).unwrap().count());
+}
+
+#[test]
+fn legato_notes_do_not_rest() {
+    let notes: &[u8] = &[
+        0x00, 0x90, 60, 100, //
+        0x83, 0x60, 0x80, 60, 0, //
+        0x00, 0x90, 62, 100, //
+        0x83, 0x60, 0x80, 62, 0, //
+    ];
+    let data = smf(0, 480, &[notes]);
+    let steps: Vec<Step> = Smf::parse(&data).unwrap().melody(0).unwrap().collect();
+    let expected = [step(0, "C4"), step(500_000, "D4"), rest(1_000_000)];
+    assert_eq!(expected.to_vec(), steps);
+}
+
+#[test]
+fn smpte_division_times() {
+    // 25 frames per second, 40 ticks per frame: 1 ms per tick
+    let notes: &[u8] = &[0x00, 0x90, 69, 100, 0x64, 0x80, 69, 0];
+    let data = smf(0, 0xE728, &[notes]);
+    let steps: Vec<Step> = Smf::parse(&data).unwrap().melody(0).unwrap().collect();
+    assert_eq!(vec![step(0, "A4"), rest(100_000)], steps);
+}
+
+#[test]
+fn rejects_invalid_files() {
+    assert_eq!(Some(SmfError::InvalidHeader), Smf::parse(b"RIFF").err());
+    let data = smf(2, 480, &[A4_REST_A5]);
+    assert_eq!(Some(SmfError::UnsupportedFormat), Smf::parse(&data).err());
+    let data = smf(0, 480, &[A4_REST_A5]);
+    let truncated = &data[..data.len() - 1];
+    assert_eq!(Some(SmfError::Truncated), Smf::parse(truncated).err());
+    let smf_file = Smf::parse(&data).unwrap();
+    assert_eq!(Some(SmfError::NoSuchTrack), smf_file.melody(1).err());
+    let data = smf(0, 480, &[&[0x00, 69, 100]]);
+    let smf_file = Smf::parse(&data).unwrap();
+    assert_eq!(Some(SmfError::InvalidEvent), smf_file.melody(0).err());
+}
+
+#[test]
+fn rejects_huge_header_length() {
+    let mut data = smf(0, 480, &[A4_REST_A5]);
+    data[4..8].copy_from_slice(&[0xFF; 4]);
+    assert_eq!(Some(SmfError::Truncated), Smf::parse(&data).err());
+}
+
+#[test]
+fn rejects_times_overflowing() {
+    // Slowest tempo, 1 tick per quarter, then 5000 maximal delta times
+    let mut notes = vec![0x00, 0xFF, 0x51, 0x03, 0xFF, 0xFF, 0xFF];
+    for _ in 0..5000 {
+        notes.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0x7F, 0xFF, 0x01, 0x00]);
+    }
+    notes.extend_from_slice(&[0x00, 0x90, 69, 100]);
+    let data = smf(0, 1, &[&notes]);
+    let smf_file = Smf::parse(&data).unwrap();
+    assert_eq!(Some(SmfError::TimeOverflow), smf_file.melody(0).err());
+}
+
+#[test]
+fn player_plays_notes_and_rests() {
+    let transitions = [
+        write_words(&[
+            [BF::B28 | BF::RESET, 0],
+            [BF::FREQ0 | 0x01, 0xB8],
+            [BF::FREQ0, 0],
+        ]),
+        write_words(&[[BF::B28 | BF::RESET, BF::SLEEP_DAC]]),
+        write_words(&[[BF::FREQ0 | 0x03, 0x70], [BF::FREQ0, 0]]),
+        write_words(&[[BF::B28 | BF::RESET, 0]]),
+        write_words(&[[BF::B28 | BF::RESET, BF::SLEEP_DAC]]),
+    ];
+    let mut dev = new_ad9833(&transitions);
+    dev.set_mclk_frequency(MCLK);
+    let data = smf(0, 480, &[A4_REST_A5]);
+    let smf = Smf::parse(&data).unwrap();
+    let mut player = Player::new(dev, smf.melody(0).unwrap());
+    assert_eq!(Some(500_000), player.poll(0).unwrap());
+    assert_eq!(Some(500_000), player.poll(499_999).unwrap());
+    assert_eq!(Some(1_000_000), player.poll(500_000).unwrap());
+    assert_eq!(Some(1_500_000), player.poll(1_200_000).unwrap());
+    assert!(!player.is_finished());
+    assert_eq!(None, player.poll(1_500_000).unwrap());
+    assert!(player.is_finished());
+    base::destroy(player.destroy());
+}
+
+#[test]
+fn player_waits_between_steps() {
+    let transitions = [
+        write_words(&[
+            [BF::B28 | BF::RESET, 0],
+            [BF::FREQ0 | 0x01, 0xB0],
+            [BF::FREQ0, 0],
+        ]),
+        write_words(&[[BF::B28 | BF::RESET, BF::SLEEP_DAC]]),
+        write_words(&[[BF::FREQ0 | 0x03, 0x60], [BF::FREQ0, 0]]),
+        write_words(&[[BF::B28 | BF::RESET, 0]]),
+        write_words(&[[BF::B28 | BF::RESET, BF::SLEEP_DAC]]),
+    ];
+    let mut dev = new_ad9833(&transitions);
+    dev.set_mclk_frequency(MCLK);
+    let data = smf(0, 480, &[A4_REST_A5]);
+    let smf = Smf::parse(&data).unwrap();
+    let mut player =
+        Player::new(dev, smf.melody(0).unwrap()).with_tuning(Tuning::equal_temperament(432.0));
+    let mut delay = RecordingDelay::default();
+    player.play(&mut delay).unwrap();
+    assert_eq!(vec![500_000_000; 3], delay.delays_ns);
+    base::destroy(player.destroy());
+}
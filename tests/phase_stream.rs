@@ -0,0 +1,131 @@
+use ad983x::{phase_stream::PhaseStreamer, Error, Phase, PhaseRegister};
+use embedded_hal_mock::eh1::spi::Transaction as SpiTrans;
+
+mod base;
+use crate::base::{new_ad9833, write_words, BitFlags as BF, RecordingDelay};
+
+fn write_p0(msb: u8) -> [u8; 2] {
+    [BF::D15 | BF::D14 | msb, 0]
+}
+
+fn write_p1(msb: u8) -> [u8; 2] {
+    [BF::D15 | BF::D14 | BF::D13 | msb, 0]
+}
+
+const SELECT_P0: [u8; 2] = [BF::RESET, 0];
+const SELECT_P1: [u8; 2] = [BF::RESET | BF::PSELECT, 0];
+
+#[test]
+fn streams_degrees_once() {
+    let transactions = [
+        write_words(&[write_p1(0), SELECT_P1]),
+        write_words(&[write_p0(0x4), SELECT_P0]),
+        // 90° again: nothing to write
+        write_words(&[write_p1(0x8), SELECT_P1]),
+    ];
+    let table = [0.0, 90.0, 90.0, 180.0];
+    let mut streamer = PhaseStreamer::from_degrees(new_ad9833(&transactions), &table, 1000);
+    assert_eq!(Some(Phase::ZERO), streamer.advance().unwrap());
+    assert_eq!(Some(Phase::from_degrees(90.0)), streamer.advance().unwrap());
+    streamer.advance().unwrap();
+    assert!(!streamer.is_finished());
+    streamer.advance().unwrap();
+    assert_eq!(4, streamer.position());
+    assert!(streamer.is_finished());
+    assert_eq!(None, streamer.advance().unwrap());
+    let dds = streamer.destroy();
+    assert_eq!(PhaseRegister::P1, dds.selected_phase());
+    base::destroy(dds);
+}
+
+#[test]
+fn loops_register_values() {
+    let transactions = [
+        write_words(&[write_p1(0), SELECT_P1]),
+        write_words(&[write_p0(0x8), SELECT_P0]),
+        // Both phases loaded: only select the register
+        SpiTrans::write_vec(SELECT_P1.to_vec()),
+        SpiTrans::write_vec(SELECT_P0.to_vec()),
+    ];
+    let values = [0, 0x800];
+    let mut streamer =
+        PhaseStreamer::from_register_values(new_ad9833(&transactions), &values, 1000)
+            .with_looping();
+    streamer.advance().unwrap();
+    streamer.advance().unwrap();
+    assert_eq!(0, streamer.position());
+    assert!(!streamer.is_finished());
+    streamer.advance().unwrap();
+    assert_eq!(
+        Some(Phase::from_register_value(0x800)),
+        streamer.advance().unwrap()
+    );
+    base::destroy(streamer.destroy());
+}
+
+#[test]
+fn runs_iterator_at_sample_rate() {
+    let transactions = [
+        write_words(&[write_p1(0), SELECT_P1]),
+        write_words(&[write_p0(0x4), SELECT_P0]),
+        write_words(&[write_p1(0x8), SELECT_P1]),
+    ];
+    let phases = (0..3).map(|quarter| Phase::from_raw(quarter << 30));
+    let mut streamer = PhaseStreamer::new(new_ad9833(&transactions), phases, 3);
+    let mut delay = RecordingDelay::default();
+    streamer.run(&mut delay).unwrap();
+    assert_eq!(vec![333_333_333, 333_333_333, 333_333_334], delay.delays_ns);
+    assert!(streamer.is_finished());
+    base::destroy(streamer.destroy());
+}
+
+#[test]
+fn run_stops_after_one_repetition() {
+    let transactions = [
+        write_words(&[write_p1(0), SELECT_P1]),
+        write_words(&[write_p0(0x4), SELECT_P0]),
+    ];
+    let table = [Phase::ZERO, Phase::from_degrees(90.0)];
+    let mut streamer =
+        PhaseStreamer::new(new_ad9833(&transactions), table.iter().copied(), 1000).with_looping();
+    let mut delay = RecordingDelay::default();
+    streamer.run(&mut delay).unwrap();
+    assert_eq!(2, delay.delays_ns.len());
+    assert_eq!(0, streamer.position());
+    base::destroy(streamer.destroy());
+}
+
+#[test]
+fn restart_and_empty_table() {
+    let transactions = [
+        write_words(&[write_p1(0x4), SELECT_P1]),
+        // Restarted: the same phase is still output
+    ];
+    let table = [90.0];
+    let mut streamer = PhaseStreamer::from_degrees(new_ad9833(&transactions), &table, 1000);
+    streamer.advance().unwrap();
+    assert!(streamer.is_finished());
+    streamer.restart();
+    assert_eq!(0, streamer.position());
+    streamer.advance().unwrap();
+    base::destroy(streamer.destroy());
+
+    let mut streamer = PhaseStreamer::from_degrees(new_ad9833(&[]), &[], 1000).with_looping();
+    assert!(streamer.is_finished());
+    assert_eq!(None, streamer.advance().unwrap());
+    base::destroy(streamer.destroy());
+}
+
+#[test]
+fn cannot_run_with_zero_sample_rate() {
+    let table = [90.0];
+    let mut streamer = PhaseStreamer::from_degrees(new_ad9833(&[]), &table, 0);
+    let mut delay = RecordingDelay::default();
+    match streamer.run(&mut delay) {
+        Err(Error::InvalidArgument) => (),
+        _ => panic!("Should return InvalidArgument"),
+    }
+    assert_eq!(0, streamer.position());
+    assert!(delay.delays_ns.is_empty());
+    base::destroy(streamer.destroy());
+}
@@ -0,0 +1,133 @@
+use ad983x::planner::{Planner, Spur, SpurKind};
+
+// With a 2^28 Hz master clock the tuning word matches the frequency in Hz.
+const MCLK: u32 = 1 << 28;
+const MCLK_HZ: f64 = MCLK as f64;
+
+fn spurs(word: u32) -> Vec<Spur> {
+    Planner::new(MCLK).spurs(word).collect()
+}
+
+#[test]
+fn nearest_word_and_resolution() {
+    let planner = Planner::new(MCLK);
+    assert_eq!(1.0, planner.resolution());
+    let nearest = planner.nearest(100.4).unwrap();
+    assert_eq!(100, nearest.word);
+    assert_eq!(100.0, nearest.frequency);
+    assert!((nearest.error + 0.4).abs() < 1e-9);
+    assert_eq!(None, planner.nearest(-1.0));
+    assert_eq!(None, planner.nearest(MCLK_HZ));
+    assert_eq!(None, planner.nearest(f64::NAN));
+}
+
+#[test]
+fn candidates_are_sorted_by_distance() {
+    let planner = Planner::new(MCLK);
+    let words: Vec<u32> = planner
+        .candidates(100.4)
+        .take(4)
+        .map(|candidate| candidate.word)
+        .collect();
+    assert_eq!(vec![100, 101, 99, 102], words);
+    let words: Vec<u32> = planner
+        .candidates(0.0)
+        .take(3)
+        .map(|candidate| candidate.word)
+        .collect();
+    assert_eq!(vec![0, 1, 2], words);
+    let words: Vec<u32> = planner
+        .candidates(MCLK_HZ - 1.0)
+        .take(3)
+        .map(|candidate| candidate.word)
+        .collect();
+    assert_eq!(vec![MCLK - 1, MCLK - 2, MCLK - 3], words);
+    assert_eq!(0, planner.candidates(-5.0).count());
+}
+
+#[test]
+fn truncation_spur_levels() {
+    let planner = Planner::new(MCLK);
+    let level = |word: u32| {
+        planner
+            .nearest(f64::from(word))
+            .unwrap()
+            .truncation_spur_dbc()
+    };
+    assert_eq!(None, level(0x1_0000));
+    assert_eq!(None, level(0));
+    // Half of the discarded range: worst case
+    assert!((level(0x8000).unwrap() + 68.33).abs() < 0.01);
+    // Odd words: best case of about -6.02 dB per phase bit
+    assert!((level(1).unwrap() + 72.25).abs() < 0.01);
+    assert!(level(0x4000).unwrap() < level(0x8000).unwrap());
+}
+
+#[test]
+fn recommends_words_without_truncation_spurs() {
+    let planner = Planner::new(MCLK);
+    let plan = planner.recommend(65_536.3, 1.0).unwrap();
+    assert_eq!(65_536, plan.word);
+    assert_eq!(None, plan.truncation_spur_dbc());
+    let plan = planner.recommend(131_071.0, 1.0).unwrap();
+    assert_eq!(131_072, plan.word);
+}
+
+#[test]
+fn recommends_lowest_truncation_spur() {
+    let planner = Planner::new(MCLK);
+    assert_eq!(65_541, planner.recommend(65_540.2, 1.0).unwrap().word);
+    assert_eq!(65_540, planner.recommend(65_540.2, 0.5).unwrap().word);
+    assert_eq!(None, planner.recommend(100.5, 0.1));
+    assert_eq!(None, planner.recommend(-1.0, 10.0));
+}
+
+#[test]
+fn predicts_images() {
+    let spurs = spurs(3 << 24);
+    let output = f64::from(3 << 24);
+    let images: Vec<&Spur> = spurs
+        .iter()
+        .filter(|spur| matches!(spur.kind, SpurKind::Image { .. }))
+        .collect();
+    assert_eq!(4, images.len());
+    assert_eq!(SpurKind::Image { order: 1 }, images[0].kind);
+    assert_eq!(MCLK_HZ - output, images[0].frequency);
+    assert_eq!(MCLK_HZ + output, images[1].frequency);
+    assert_eq!(SpurKind::Image { order: 2 }, images[3].kind);
+    assert_eq!(2.0 * MCLK_HZ + output, images[3].frequency);
+    // sinc(13/16) / sinc(3/16) = 3/13
+    assert!((images[0].level_dbc.unwrap() + 12.74).abs() < 0.01);
+    assert!(images[1].level_dbc.unwrap() < images[0].level_dbc.unwrap());
+}
+
+#[test]
+fn predicts_aliased_harmonics() {
+    let harmonics: Vec<f64> = spurs(3 << 24)
+        .iter()
+        .filter(|spur| matches!(spur.kind, SpurKind::Harmonic { .. }))
+        .map(|spur| spur.frequency / MCLK_HZ)
+        .collect();
+    assert_eq!(vec![0.375, 0.4375, 0.25, 0.0625], harmonics);
+    let planner = Planner::new(MCLK).with_harmonics(2).with_images(0);
+    let spurs: Vec<Spur> = planner.spurs(3 << 24).collect();
+    assert_eq!(1, spurs.len());
+    assert_eq!(SpurKind::Harmonic { order: 2 }, spurs[0].kind);
+    assert_eq!(None, spurs[0].level_dbc);
+}
+
+#[test]
+fn predicts_truncation_spurs() {
+    assert_eq!(8, spurs(3 << 24).len());
+    let word = (3 << 24) | 0x8000;
+    let truncation: Vec<Spur> = spurs(word)
+        .into_iter()
+        .filter(|spur| spur.kind == SpurKind::PhaseTruncation)
+        .collect();
+    assert_eq!(2, truncation.len());
+    // The discarded bits alternate at MCLK / 2.
+    let output = f64::from(word);
+    assert_eq!(MCLK_HZ / 2.0 - output, truncation[0].frequency);
+    assert_eq!(MCLK_HZ / 2.0 - output, truncation[1].frequency);
+    assert!((truncation[0].level_dbc.unwrap() + 68.33).abs() < 0.01);
+}
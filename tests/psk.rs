@@ -0,0 +1,98 @@
+use ad983x::{
+    psk::{PskConfig, PskModulator, PskOrder, SymbolMapping},
+    Phase,
+};
+use embedded_hal_mock::eh1::spi::Transaction as SpiTrans;
+
+mod base;
+use crate::base::{destroy, new_ad9833, write_words, BitFlags as BF, RecordingDelay};
+
+const P0: u8 = BF::D15 | BF::D14;
+const P1: u8 = BF::D15 | BF::D14 | BF::D13;
+
+#[test]
+fn gray_coded_qpsk_phases() {
+    let config = PskConfig::new(PskOrder::Qpsk, 1000);
+    let values: Vec<u16> = (0..4)
+        .map(|symbol| config.symbol_phase(symbol).register_value())
+        .collect();
+    assert_eq!(vec![0, 1024, 3072, 2048], values);
+}
+
+#[test]
+fn binary_coded_8psk_phases_with_offset() {
+    let config = PskConfig::new(PskOrder::Psk8, 1000)
+        .with_mapping(SymbolMapping::Binary)
+        .with_phase_offset(Phase::from_degrees(45.0));
+    assert_eq!(512, config.symbol_phase(0).register_value());
+    assert_eq!(0, config.symbol_phase(7).register_value());
+}
+
+#[test]
+fn bpsk_only_selects_phase_registers() {
+    let transitions = [
+        SpiTrans::write_vec(vec![P0, 0]),
+        SpiTrans::write_vec(vec![P1 | 0x8, 0]),
+        SpiTrans::write_vec(vec![BF::RESET, 0]),
+        SpiTrans::write_vec(vec![BF::PSELECT | BF::RESET, 0]),
+        SpiTrans::write_vec(vec![BF::RESET, 0]),
+    ];
+    let mut psk = PskModulator::new(
+        new_ad9833(&transitions),
+        PskConfig::new(PskOrder::Bpsk, 1000),
+    );
+    psk.init().unwrap();
+    let mut delay = RecordingDelay::default();
+    psk.send_bits([true, true, false], &mut delay).unwrap();
+    assert_eq!(vec![1_000_000; 3], delay.delays_ns);
+    destroy(psk.destroy());
+}
+
+#[test]
+fn qpsk_rewrites_inactive_phase_register() {
+    let transitions = [
+        SpiTrans::write_vec(vec![P0, 0]),
+        SpiTrans::write_vec(vec![P1 | 0x4, 0]),
+        SpiTrans::write_vec(vec![BF::RESET, 0]),
+        // symbol 0b11 -> 180°
+        write_words(&[[P1 | 0x8, 0], [BF::PSELECT | BF::RESET, 0]]),
+        // symbol 0b00 -> 0° is still in P0
+        SpiTrans::write_vec(vec![BF::RESET, 0]),
+    ];
+    let mut psk = PskModulator::new(
+        new_ad9833(&transitions),
+        PskConfig::new(PskOrder::Qpsk, 2000),
+    );
+    psk.init().unwrap();
+    let mut delay = RecordingDelay::default();
+    // 0b1100_0000: symbols 11, 00, 00, 00
+    psk.send_bytes(&[0xC0], &mut delay).unwrap();
+    assert_eq!(vec![500_000; 4], delay.delays_ns);
+    destroy(psk.destroy());
+}
+
+#[test]
+fn pads_last_symbol() {
+    let transitions = [
+        SpiTrans::write_vec(vec![P0, 0]),
+        SpiTrans::write_vec(vec![P1 | 0x2, 0]),
+        SpiTrans::write_vec(vec![BF::RESET, 0]),
+        // 0b100 Gray decoded is index 7
+        write_words(&[[P1 | 0xE, 0], [BF::PSELECT | BF::RESET, 0]]),
+    ];
+    let mut psk = PskModulator::new(
+        new_ad9833(&transitions),
+        PskConfig::new(PskOrder::Psk8, 1000),
+    );
+    psk.init().unwrap();
+    let mut delay = RecordingDelay::default();
+    psk.send_bits([true], &mut delay).unwrap();
+    destroy(psk.destroy());
+}
+
+#[test]
+fn cannot_init_with_zero_symbol_rate() {
+    let mut psk = PskModulator::new(new_ad9833(&[]), PskConfig::new(PskOrder::Bpsk, 0));
+    psk.init().expect_err("Should return error");
+    destroy(psk.destroy());
+}
@@ -0,0 +1,99 @@
+use ad983x::{quadrature::QuadratureGenerator, Phase};
+use embedded_hal_mock::eh1::spi::Transaction as SpiTrans;
+
+mod base;
+use crate::base::{new_ad9833, write_words, BitFlags as BF};
+
+// With a 2^28 Hz master clock the tuning word matches the frequency in Hz.
+const MCLK: u32 = 1 << 28;
+
+fn reset(control: u8) -> SpiTrans<u8> {
+    SpiTrans::write_vec(vec![control | BF::RESET, 0])
+}
+
+fn phase0(msb: u8) -> SpiTrans<u8> {
+    SpiTrans::write_vec(vec![BF::D15 | BF::D14 | msb, 0])
+}
+
+fn start(frequency: u8, phase: u8) -> [SpiTrans<u8>; 4] {
+    [
+        reset(0),
+        write_words(&[
+            [BF::B28 | BF::RESET, 0],
+            [BF::FREQ0, frequency],
+            [BF::FREQ0, 0],
+        ]),
+        phase0(phase),
+        SpiTrans::write_vec(vec![BF::B28, 0]),
+    ]
+}
+
+#[test]
+fn starts_outputs_in_quadrature() {
+    let mut i = new_ad9833(&start(100, 0));
+    i.set_mclk_frequency(MCLK);
+    let mut generator = QuadratureGenerator::new(i, new_ad9833(&start(100, 0x4)));
+    assert_eq!(None, generator.frequency());
+    assert_eq!(100.0, generator.set_frequency_hz(100.0).unwrap());
+    assert_eq!(Some(100), generator.frequency());
+    // Same frequency: nothing to write
+    generator.set_frequency(100).unwrap();
+    let (i, q) = generator.destroy();
+    base::destroy(i);
+    base::destroy(q);
+}
+
+#[test]
+fn frequency_change_realigns_outputs() {
+    let change = [
+        reset(BF::B28),
+        write_words(&[[BF::FREQ0, 200], [BF::FREQ0, 0]]),
+        SpiTrans::write_vec(vec![BF::B28, 0]),
+    ];
+    let transactions = |phase| [start(100, phase).to_vec(), change.to_vec()].concat();
+    let mut generator =
+        QuadratureGenerator::new(new_ad9833(&transactions(0)), new_ad9833(&transactions(0x4)));
+    generator.set_frequency(100).unwrap();
+    generator.set_frequency(200).unwrap();
+    assert_eq!(Some(200), generator.frequency());
+    let (i, q) = generator.destroy();
+    base::destroy(i);
+    base::destroy(q);
+}
+
+#[test]
+fn compensates_release_skew() {
+    // The Q device lags 1 ms behind, which is 90° at 250 Hz.
+    let mut i = new_ad9833(&start(250, 0));
+    i.set_mclk_frequency(MCLK);
+    let mut generator =
+        QuadratureGenerator::new(i, new_ad9833(&start(250, 0x8))).with_release_skew_ns(1_000_000);
+    generator.set_frequency(250).unwrap();
+    let (i, q) = generator.destroy();
+    base::destroy(i);
+    base::destroy(q);
+}
+
+#[test]
+fn can_change_offset_while_running() {
+    let q = [start(100, 0x4).to_vec(), vec![phase0(0x2)]].concat();
+    let mut generator = QuadratureGenerator::new(new_ad9833(&start(100, 0)), new_ad9833(&q));
+    generator.set_frequency(100).unwrap();
+    generator.set_offset(Phase::from_degrees(45.0)).unwrap();
+    assert_eq!(Phase::from_degrees(45.0), generator.offset());
+    let (i, q) = generator.destroy();
+    base::destroy(i);
+    base::destroy(q);
+}
+
+#[test]
+fn rejects_too_big_word() {
+    let mut generator = QuadratureGenerator::new(new_ad9833(&[]), new_ad9833(&[]))
+        .with_offset(Phase::from_degrees(180.0));
+    generator
+        .set_frequency(1 << 28)
+        .expect_err("Should return error");
+    let (i, q) = generator.destroy();
+    base::destroy(i);
+    base::destroy(q);
+}
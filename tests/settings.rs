@@ -0,0 +1,126 @@
+#![cfg(feature = "serde")]
+
+use ad983x::{
+    settings::{Chip, DeviceSettings, SettingsError},
+    ControlSource, DeviceConfig, FrequencyRegister as FreqReg, OutputWaveform as OW, PhaseRegister,
+    PoweredDown, SignBitOutput as SBO,
+};
+
+const PRESET: &str = r#"
+chip = "ad9838"
+mclk_hz = 16000000
+frequency0_hz = 1000.0
+frequency1_hz = 2500.5
+phase0_degrees = 0.0
+phase1_degrees = 90.0
+selected_frequency = "f1"
+selected_phase = "p0"
+output_waveform = "sinusoidal"
+sign_bit_output = "comparator"
+powered_down = "nothing"
+control_source = "hardware_pins"
+"#;
+
+fn settings(chip: Chip) -> DeviceSettings {
+    DeviceSettings {
+        chip,
+        mclk_hz: 25_000_000,
+        frequency0_hz: 1000.0,
+        frequency1_hz: 0.0,
+        phase0_degrees: 0.0,
+        phase1_degrees: 180.0,
+        selected_frequency: FreqReg::F0,
+        selected_phase: PhaseRegister::P1,
+        output_waveform: OW::Sinusoidal,
+        sign_bit_output: SBO::Disabled,
+        powered_down: PoweredDown::Nothing,
+        control_source: ControlSource::Software,
+    }
+}
+
+#[test]
+fn can_load_toml_preset() {
+    let settings: DeviceSettings = toml::from_str(PRESET).unwrap();
+    assert_eq!(Chip::Ad9838, settings.chip);
+    assert_eq!(FreqReg::F1, settings.selected_frequency);
+    assert_eq!(SBO::Comparator, settings.sign_bit_output);
+    assert_eq!(ControlSource::HardwarePins, settings.control_source);
+    let config = settings.to_config().unwrap();
+    assert_eq!(16777, config.frequency0);
+    assert_eq!(41951, config.frequency1);
+    assert_eq!(1024, config.phase1);
+}
+
+#[test]
+fn json_round_trip_keeps_config() {
+    let config = DeviceConfig {
+        frequency0: 0xFFF_FFFF,
+        frequency1: 4724,
+        phase0: 0xFFF,
+        phase1: 1,
+        selected_frequency: FreqReg::F1,
+        output_waveform: OW::SquareMsbOfDacDiv2,
+        powered_down: PoweredDown::Dac,
+        ..Default::default()
+    };
+    let settings = DeviceSettings::from_config(Chip::Ad9837, 16_000_000, &config);
+    let json = serde_json::to_string(&settings).unwrap();
+    assert!(json.contains(r#""output_waveform":"square_msb_of_dac_div2""#));
+    let loaded: DeviceSettings = serde_json::from_str(&json).unwrap();
+    assert_eq!(settings, loaded);
+    assert_eq!(Ok(config), loaded.to_config());
+}
+
+#[test]
+fn rounds_phase_close_to_full_turn_to_zero() {
+    let mut settings = settings(Chip::Ad9833);
+    settings.phase0_degrees = 359.99;
+    assert_eq!(0, settings.to_config().unwrap().phase0);
+}
+
+#[test]
+fn rejects_too_high_frequency() {
+    let mut settings = settings(Chip::Ad9833);
+    settings.frequency1_hz = 25_000_000.0;
+    assert_eq!(Err(SettingsError::FrequencyOutOfRange), settings.validate());
+    settings.frequency1_hz = -1.0;
+    assert_eq!(Err(SettingsError::FrequencyOutOfRange), settings.validate());
+}
+
+#[test]
+fn rejects_phase_out_of_range() {
+    let mut settings = settings(Chip::Ad9833);
+    settings.phase0_degrees = 360.0;
+    assert_eq!(Err(SettingsError::PhaseOutOfRange), settings.validate());
+    settings.phase0_degrees = f32::NAN;
+    assert_eq!(Err(SettingsError::PhaseOutOfRange), settings.validate());
+}
+
+#[test]
+fn rejects_square_output_waveform_on_ad9834() {
+    let mut settings = settings(Chip::Ad9834);
+    settings.output_waveform = OW::SquareMsbOfDac;
+    assert_eq!(Err(SettingsError::Unsupported), settings.validate());
+    settings.chip = Chip::Ad9833;
+    assert_eq!(Ok(()), settings.validate());
+}
+
+#[test]
+fn rejects_ad9834_settings_on_ad9833() {
+    let mut settings = settings(Chip::Ad9833);
+    settings.control_source = ControlSource::HardwarePins;
+    assert_eq!(Err(SettingsError::Unsupported), settings.validate());
+    settings.chip = Chip::Ad9838;
+    assert_eq!(Ok(()), settings.validate());
+}
+
+#[test]
+fn deserialization_validates_settings() {
+    let preset = PRESET.replace("sinusoidal", "square_msb_of_dac");
+    let error = toml::from_str::<DeviceSettings>(&preset).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("setting not available on the chip"));
+    let preset = PRESET.replace("1000.0", "16000000.0");
+    toml::from_str::<DeviceSettings>(&preset).expect_err("Should return error");
+}
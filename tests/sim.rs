@@ -0,0 +1,138 @@
+#![cfg(feature = "sim")]
+
+use ad983x::{
+    sim::{HardwarePins, Sample, Simulator},
+    Ad983x, ControlSource, FrequencyRegister as FreqReg, OutputWaveform as OW,
+    PhaseRegister as PhaseReg, SignBitOutput as SBO,
+};
+use embedded_hal::spi::SpiDevice;
+
+fn rising_edges(samples: &[Sample]) -> usize {
+    samples
+        .windows(2)
+        .filter(|pair| pair[0].vout.unwrap() < 0.0 && pair[1].vout.unwrap() >= 0.0)
+        .count()
+}
+
+#[test]
+fn decodes_28_bit_frequency_writes() {
+    let mut sim = Simulator::new_ad9833(25_000_000);
+    sim.write(&[0x20, 0x00]).unwrap(); // B28
+    sim.write(&[0x4D, 0xEF]).unwrap(); // FREQ0 LSB
+    assert_eq!(Some(FreqReg::F0), sim.pending_frequency_write());
+    assert_eq!(0, sim.frequency(FreqReg::F0));
+    sim.write(&[0x66, 0xAF]).unwrap(); // FREQ0 MSB
+    assert_eq!(None, sim.pending_frequency_write());
+    assert_eq!(0x9AB_CDEF, sim.frequency(FreqReg::F0));
+    assert_eq!(3, sim.word_count());
+}
+
+#[test]
+fn decodes_14_bit_frequency_writes() {
+    let mut sim = Simulator::new_ad9833(25_000_000);
+    sim.write(&[0x10, 0x00, 0x80, 0x01]).unwrap(); // HLB, FREQ1 MSB
+    sim.write(&[0x00, 0x00, 0x80, 0x02]).unwrap(); // FREQ1 LSB
+    assert_eq!((1 << 14) | 2, sim.frequency(FreqReg::F1));
+}
+
+#[test]
+fn discards_incomplete_words() {
+    let mut sim = Simulator::new_ad9833(25_000_000);
+    sim.write(&[0xC0]).unwrap();
+    sim.write(&[0xE1, 0x23]).unwrap();
+    assert_eq!(0x123, sim.phase(PhaseReg::P1));
+    assert_eq!(1, sim.word_count());
+}
+
+#[test]
+fn driver_produces_sine_wave() {
+    let mut dds = Ad983x::new_ad9833(Simulator::new_ad9833(1_000_000));
+    dds.set_mclk_frequency(1_000_000);
+    dds.reset().unwrap();
+    dds.set_frequency_hz(FreqReg::F1, 1_000.0).unwrap();
+    dds.select_frequency(FreqReg::F1).unwrap();
+    dds.set_phase_degrees(PhaseReg::P0, 90.0).unwrap();
+    dds.enable().unwrap();
+    let mut sim = dds.destroy();
+    assert!(!sim.is_reset());
+    // Starts at a cosine peak because of the 90° phase
+    assert!(sim.output().vout.unwrap() > 0.99);
+    let mut samples = [Sample::default(); 10_000];
+    sim.render(100_000, &mut samples);
+    assert_eq!(100, rising_edges(&samples));
+    let peak = samples
+        .iter()
+        .map(|s| s.vout.unwrap())
+        .fold(f32::MIN, f32::max);
+    assert!(peak > 0.99 && peak <= 1.0);
+}
+
+#[test]
+fn reset_holds_output() {
+    let mut dds = Ad983x::new_ad9833(Simulator::new_ad9833(1_000_000));
+    dds.set_mclk_frequency(1_000_000);
+    dds.reset().unwrap();
+    dds.set_frequency_hz(FreqReg::F0, 1_000.0).unwrap();
+    let mut sim = dds.destroy();
+    let mut samples = [Sample::default(); 100];
+    sim.render(10_000, &mut samples);
+    assert_eq!(0, sim.accumulator());
+    assert!(samples.iter().all(|s| s == &samples[0]));
+}
+
+#[test]
+fn renders_triangle_and_square() {
+    let mut dds = Ad983x::new_ad9833(Simulator::new_ad9833(1 << 20));
+    dds.set_frequency(FreqReg::F0, 1 << 18).unwrap(); // MCLK / 1024
+    dds.set_output_waveform(OW::Triangle).unwrap();
+    dds.enable().unwrap();
+    let mut sim = dds.destroy();
+    let mut samples = [Sample::default(); 4];
+    sim.render(4096, &mut samples); // 4 samples per period
+    let values: Vec<f32> = samples.iter().map(|s| s.vout.unwrap()).collect();
+    assert_eq!(vec![0.0, 1.0, 0.0, -1.0], values);
+
+    // A new driver instance starts with the reset bit set
+    let mut dds = Ad983x::new_ad9833(sim);
+    dds.set_output_waveform(OW::SquareMsbOfDacDiv2).unwrap();
+    dds.enable().unwrap();
+    let mut sim = dds.destroy();
+    let mut samples = [Sample::default(); 8];
+    sim.render(4096, &mut samples);
+    let levels: Vec<bool> = samples.iter().map(|s| s.sign_bit.unwrap()).collect();
+    // Half the output frequency
+    assert_eq!(
+        vec![false, false, false, true, true, true, true, false],
+        levels
+    );
+}
+
+#[test]
+fn ad9834_sign_bit_output_and_hardware_pins() {
+    let mut dds = Ad983x::new_ad9834(Simulator::new_ad9834(1 << 20));
+    dds.set_frequency(FreqReg::F1, 1 << 18).unwrap();
+    dds.set_sign_bit_output(SBO::SquareMsbOfDac).unwrap();
+    dds.enable().unwrap();
+    dds.set_control_source(ControlSource::HardwarePins).unwrap();
+    let mut sim = dds.destroy();
+    // Reset through the pin
+    sim.set_hardware_pins(HardwarePins {
+        reset: true,
+        ..Default::default()
+    });
+    sim.advance(1000);
+    assert!(sim.is_reset());
+    assert_eq!(0, sim.accumulator());
+    // F1 selected through the pin, DAC powered down through the pin
+    sim.set_hardware_pins(HardwarePins {
+        fselect: true,
+        sleep: true,
+        ..Default::default()
+    });
+    assert_eq!(FreqReg::F1, sim.selected_frequency());
+    let mut samples = [Sample::default(); 4];
+    sim.render(4096, &mut samples);
+    assert_eq!(None, samples[0].vout);
+    let levels: Vec<bool> = samples.iter().map(|s| s.sign_bit.unwrap()).collect();
+    assert_eq!(vec![false, true, true, false], levels);
+}
@@ -0,0 +1,109 @@
+use ad983x::sweep::{Sweep, Sweeper};
+
+mod base;
+use crate::base::{new_ad9833, write_words, BitFlags as BF, RecordingDelay};
+
+// With a 2^28 Hz master clock the tuning word matches the frequency in Hz.
+const MCLK: u32 = 1 << 28;
+
+#[test]
+fn linear_sweep_frequencies() {
+    let sweep = Sweep::linear(100.0, 200.0, 5);
+    let frequencies: Vec<f32> = sweep.frequencies().collect();
+    assert_eq!(vec![100.0, 125.0, 150.0, 175.0, 200.0], frequencies);
+}
+
+#[test]
+fn linear_step_sweep_frequencies() {
+    let sweep = Sweep::linear_step(200.0, 100.0, 30.0).unwrap();
+    let frequencies: Vec<f32> = sweep.frequencies().collect();
+    assert_eq!(vec![200.0, 170.0, 140.0, 110.0], frequencies);
+}
+
+#[test]
+fn linear_step_sweep_includes_stop_despite_rounding() {
+    let sweep = Sweep::linear_step(0.0, 0.5, 0.1).unwrap();
+    assert_eq!(6, sweep.len());
+    assert!((sweep.frequency_at(5).unwrap() - 0.5).abs() < 1e-6);
+    assert_eq!(3, Sweep::linear_step(0.1, 0.3, 0.1).unwrap().len());
+    assert_eq!(1, Sweep::linear_step(1.0, 1.0, 0.1).unwrap().len());
+}
+
+#[test]
+fn linear_step_sweep_rejects_invalid_step() {
+    assert!(Sweep::linear_step(100.0, 200.0, 0.0).is_none());
+    assert!(Sweep::linear_step(100.0, 200.0, -10.0).is_none());
+    assert!(Sweep::linear_step(100.0, 200.0, f32::NAN).is_none());
+    assert!(Sweep::linear_step(100.0, f32::INFINITY, 10.0).is_none());
+    // Too many points to count
+    assert!(Sweep::linear_step(-f32::MAX, f32::MAX, f32::MIN_POSITIVE).is_none());
+}
+
+#[test]
+fn logarithmic_sweep_frequencies() {
+    let sweep = Sweep::logarithmic(10.0, 1000.0, 3);
+    let frequencies: Vec<f32> = sweep.frequencies().collect();
+    assert_eq!(3, frequencies.len());
+    assert!((frequencies[1] - 100.0).abs() < 1e-3);
+    assert!((frequencies[2] - 1000.0).abs() < 1e-2);
+}
+
+#[test]
+fn table_sweep_frequencies() {
+    let table = [5.0, 1.0, 3.0];
+    let sweep = Sweep::table(&table);
+    assert_eq!(3, sweep.len());
+    assert_eq!(Some(1.0), sweep.frequency_at(1));
+    assert_eq!(None, sweep.frequency_at(3));
+}
+
+#[test]
+fn sweep_steps_alternate_registers() {
+    let transitions = [
+        write_words(&[
+            [BF::B28 | BF::RESET, 0],
+            [BF::FREQ1 | 0x3, 0xE8],
+            [BF::FREQ1, 0],
+            [BF::B28 | BF::FSELECT | BF::RESET, 0],
+        ]),
+        write_words(&[
+            [BF::FREQ0 | 0x7, 0xD0],
+            [BF::FREQ0, 0],
+            [BF::B28 | BF::RESET, 0],
+        ]),
+    ];
+    let mut dev = new_ad9833(&transitions);
+    dev.set_mclk_frequency(MCLK);
+    let mut sweeper = Sweeper::new(dev, Sweep::linear(1000.0, 2000.0, 2));
+    assert_eq!(Some(1000.0), sweeper.step().unwrap());
+    assert!(!sweeper.is_finished());
+    assert_eq!(Some(2000.0), sweeper.step().unwrap());
+    assert!(sweeper.is_finished());
+    assert_eq!(None, sweeper.step().unwrap());
+    base::destroy(sweeper.destroy());
+}
+
+#[test]
+fn sweep_run_waits_dwell_time() {
+    let transitions = [write_words(&[
+        [BF::B28 | BF::RESET, 0],
+        [BF::FREQ1 | 0x3, 0xE8],
+        [BF::FREQ1, 0],
+        [BF::B28 | BF::FSELECT | BF::RESET, 0],
+    ])];
+    let mut dev = new_ad9833(&transitions);
+    dev.set_mclk_frequency(MCLK);
+    let table = [1000.0];
+    let mut sweeper = Sweeper::new(dev, Sweep::table(&table).with_dwell_us(500));
+    let mut delay = RecordingDelay::default();
+    sweeper.run(&mut delay).unwrap();
+    assert_eq!(vec![500_000], delay.delays_ns);
+    base::destroy(sweeper.destroy());
+}
+
+#[test]
+fn sweep_rejects_unreachable_frequency() {
+    let mut sweeper = Sweeper::new(new_ad9833(&[]), Sweep::logarithmic(0.0, 100.0, 2));
+    sweeper.step().expect_err("Should return error");
+    base::destroy(sweeper.destroy());
+}
@@ -0,0 +1,116 @@
+use ad983x::{
+    interface::SpiBusInterface, Ad983x, DeviceConfig, Error, FrequencyRegister as FreqReg,
+    OutputWaveform as OW,
+};
+use embedded_hal_mock::eh1::{
+    pin::{Mock as PinMock, State, Transaction as PinTrans},
+    spi::{Mock as SpiMock, Transaction as SpiTrans},
+    MockError,
+};
+use std::io::ErrorKind;
+
+mod base;
+use crate::base::{destroy, new_ad9833, new_ad9838, write_words, BitFlags as BF};
+
+#[test]
+fn can_go_through_states() {
+    let transitions = [
+        SpiTrans::write_vec(vec![BF::RESET, 0]),
+        write_words(&[
+            [BF::B28 | BF::RESET, 0],
+            [BF::FREQ0 | 0xD, 0xEF],
+            [BF::FREQ0 | 0x26, 0xAF],
+        ]),
+        SpiTrans::write_vec(vec![BF::B28, 0]),
+        SpiTrans::write_vec(vec![BF::B28, BF::MODE]),
+        SpiTrans::write_vec(vec![BF::B28 | BF::RESET, BF::MODE]),
+    ];
+    let dev = new_ad9833(&transitions).into_typestate();
+    let mut dev = dev.reset().unwrap();
+    dev.set_frequency(FreqReg::F0, 0x9AB_CDEF).unwrap();
+    let mut dev = dev.enable().unwrap();
+    dev.set_output_waveform(OW::Triangle).unwrap();
+    let dev = dev.disable().unwrap();
+    destroy(dev.into_unchecked());
+}
+
+#[test]
+fn can_apply_config_in_reset_and_running() {
+    let transitions = [
+        SpiTrans::write_vec(vec![BF::RESET, 0]),
+        // Still held in reset
+        write_words(&[
+            [BF::B28 | BF::RESET, 0],
+            [BF::FREQ0 | 0x3, 0xE8],
+            [BF::FREQ0, 0],
+            [BF::FREQ1, 0],
+            [BF::FREQ1, 0],
+            [BF::D15 | BF::D14, 0],
+            [BF::D15 | BF::D14 | BF::D13, 0],
+        ]),
+        SpiTrans::write_vec(vec![BF::B28, 0]),
+        write_words(&[[BF::FREQ0 | 0x7, 0xD0], [BF::FREQ0, 0]]),
+    ];
+    let dev = new_ad9833(&transitions).into_typestate();
+    let mut dev = dev.reset().unwrap();
+    let mut config = DeviceConfig {
+        frequency0: 1000,
+        ..Default::default()
+    };
+    dev.apply(&config).unwrap();
+    assert!(dev.is_reset());
+    let mut dev = dev.enable().unwrap();
+    config.frequency0 = 2000;
+    dev.apply(&config).unwrap();
+    assert!(!dev.is_reset());
+    destroy(dev.into_unchecked());
+}
+
+#[test]
+fn running_device_can_be_unchecked() {
+    let transitions = [
+        SpiTrans::write_vec(vec![BF::RESET, 0]),
+        SpiTrans::write_vec(vec![0, 0]),
+    ];
+    let dev = new_ad9838(&transitions).into_typestate();
+    let dev = dev.reset().unwrap().enable().unwrap();
+    destroy(dev.into_unchecked());
+}
+
+#[test]
+fn failed_transition_returns_device() {
+    let error = MockError::Io(ErrorKind::Other);
+    let spi = [
+        SpiTrans::write_vec(vec![BF::RESET, 0]),
+        SpiTrans::flush(),
+        SpiTrans::write_vec(vec![0, 0]),
+        SpiTrans::flush(),
+    ];
+    let fsync = [
+        PinTrans::set(State::Low).with_error(error.clone()),
+        PinTrans::set(State::Low),
+        PinTrans::set(State::High),
+        PinTrans::set(State::Low),
+        PinTrans::set(State::High).with_error(error.clone()),
+    ];
+    let interface = SpiBusInterface::new(SpiMock::new(&spi), PinMock::new(&fsync));
+    let dev = Ad983x::new_ad9833(interface).into_typestate();
+    let dev = match dev.reset() {
+        Err((dev, Error::Pin(e))) => {
+            assert_eq!(error, e);
+            dev
+        }
+        _ => panic!("Should return pin error"),
+    };
+    let dev = dev.reset().unwrap();
+    let dev = match dev.enable() {
+        Err((dev, Error::Pin(e))) => {
+            assert_eq!(error, e);
+            dev
+        }
+        _ => panic!("Should return pin error"),
+    };
+    let (mut spi, mut fsync) = dev.into_unchecked().destroy().release();
+    spi.done();
+    fsync.done();
+}